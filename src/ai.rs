@@ -1,4 +1,14 @@
-use super::game::{GameRequest, PossibleActions, Request, ThrowableOnRiichi};
+use std::str::FromStr;
+
+use super::analysis;
+use super::game::{
+    Fuuro, Game, GameRequest, KantsuInner, PlayerView, PossibleActions, Request, Te,
+    ThrowableOnRiichi,
+};
+use super::safety;
+use super::shanten::{kokushimusou_shanten, shanten_counts};
+use super::tiles::{Fon, Hai, TileCounts, TileKind};
+use super::yaku::{self, WinContext, WinningMethod};
 use log::trace;
 
 #[derive(Debug, Copy, Eq, PartialEq, PartialOrd, Ord, Clone)]
@@ -39,36 +49,63 @@ pub struct AiServer {
     pub tx: std::sync::mpsc::Sender<GameRequest>,
     pub rx_call: std::sync::mpsc::Receiver<Option<Call>>,
     pub rx_turn: std::sync::mpsc::Receiver<TurnResult>,
+    /// Answer to tempai declaration at ryukyoku
+    pub rx_tenpai: std::sync::mpsc::Receiver<bool>,
 }
 pub struct AiClient {
     pub rx: std::sync::mpsc::Receiver<GameRequest>,
     pub tx_call: std::sync::mpsc::Sender<Option<Call>>,
     pub tx_turn: std::sync::mpsc::Sender<TurnResult>,
+    pub tx_tenpai: std::sync::mpsc::Sender<bool>,
 }
 
 pub fn channel() -> (AiServer, AiClient) {
     let (tx_call, rx_call) = std::sync::mpsc::channel();
     let (tx_turn, rx_turn) = std::sync::mpsc::channel();
+    let (tx_tenpai, rx_tenpai) = std::sync::mpsc::channel();
     let (tx, rx) = std::sync::mpsc::channel();
     (
         AiServer {
             tx,
             rx_call,
             rx_turn,
+            rx_tenpai,
         },
         AiClient {
             rx,
             tx_call,
             tx_turn,
+            tx_tenpai,
         },
     )
 }
 
 pub type CallHandler = fn(possible_calls: &[PossibleCall], request: &GameRequest) -> Option<Call>;
 pub type TurnHandler = fn(possible_actions: &PossibleActions, request: &GameRequest) -> TurnResult;
+/// Return true to declare tempai at ryukyoku
+pub type TenpaiHandler = fn(request: &GameRequest) -> bool;
 
 impl AiServer {
-    pub fn new(handle_call: CallHandler, handle_turn: TurnHandler) -> AiServer {
+    pub fn new(
+        handle_call: CallHandler,
+        handle_turn: TurnHandler,
+        handle_tenpai: TenpaiHandler,
+    ) -> AiServer {
+        AiServer::with_handlers(handle_call, handle_turn, handle_tenpai)
+    }
+
+    /// Same as [`AiServer::new`], with handlers which may capture state,
+    /// e.g. the parameters of a bot.
+    pub fn with_handlers<C, T, D>(
+        mut handle_call: C,
+        mut handle_turn: T,
+        mut handle_tenpai: D,
+    ) -> AiServer
+    where
+        C: FnMut(&[PossibleCall], &GameRequest) -> Option<Call> + Send + 'static,
+        T: FnMut(&PossibleActions, &GameRequest) -> TurnResult + Send + 'static,
+        D: FnMut(&GameRequest) -> bool + Send + 'static,
+    {
         let (server, client) = channel();
 
         std::thread::spawn(move || loop {
@@ -84,6 +121,10 @@ impl AiServer {
                     let result = handle_turn(possible_actions, &request);
                     client.tx_turn.send(result).expect("Sent!")
                 }
+                Request::DeclareTenpai => {
+                    let declare = handle_tenpai(&request);
+                    client.tx_tenpai.send(declare).expect("Sent!")
+                }
                 Request::EndGame => return,
                 _ => {}
             }
@@ -93,6 +134,54 @@ impl AiServer {
     }
 }
 
+/// Takes the decisions of the 4 players of a game, by seat wind
+///
+/// The [`AiServer`] of each player answers from its own thread. Implement
+/// this trait to play games without threads nor copies of the game, e.g.
+/// for playouts.
+pub trait Bots {
+    fn call(&mut self, game: &Game, player: Fon, possible_calls: Vec<PossibleCall>)
+        -> Option<Call>;
+    fn turn(&mut self, game: &Game, player: Fon, possible_actions: PossibleActions) -> TurnResult;
+    /// Return true to declare tempai at ryukyoku
+    fn declare_tenpai(&mut self, game: &Game, player: Fon) -> bool;
+    /// Send a request which needs no answer
+    fn notify(&mut self, _game: &Game, _player: Fon, _request: Request) {}
+}
+
+impl Bots for [AiServer; 4] {
+    fn call(
+        &mut self,
+        game: &Game,
+        player: Fon,
+        possible_calls: Vec<PossibleCall>,
+    ) -> Option<Call> {
+        self.notify(game, player, Request::Call(possible_calls));
+        self[player as usize].rx_call.recv().expect("Received!")
+    }
+
+    fn turn(&mut self, game: &Game, player: Fon, possible_actions: PossibleActions) -> TurnResult {
+        self.notify(game, player, Request::DoTurn(possible_actions));
+        self[player as usize].rx_turn.recv().expect("Received!")
+    }
+
+    fn declare_tenpai(&mut self, game: &Game, player: Fon) -> bool {
+        self.notify(game, player, Request::DeclareTenpai);
+        self[player as usize].rx_tenpai.recv().expect("Received!")
+    }
+
+    fn notify(&mut self, game: &Game, player: Fon, request: Request) {
+        self[player as usize]
+            .tx
+            .send(GameRequest {
+                game: game.clone(),
+                request,
+                player,
+            })
+            .expect("Sent!");
+    }
+}
+
 impl Default for AiServer {
     fn default() -> Self {
         null_bot()
@@ -109,6 +198,8 @@ pub fn null_bot() -> AiServer {
             index: TehaiIndex::Tsumohai,
             riichi: false,
         },
+        // Always declare tempai
+        |_| true,
     )
 }
 
@@ -151,12 +242,8 @@ pub fn dump_caller_bot() -> AiServer {
             }
 
             if !can_riichi.is_empty() {
-                let index = match can_riichi[0] {
-                    ThrowableOnRiichi::Te(index) => TehaiIndex::Tehai(index),
-                    ThrowableOnRiichi::Tsumohai => TehaiIndex::Tsumohai,
-                };
                 return TurnResult::ThrowHai {
-                    index,
+                    index: best_riichi_discard(can_riichi, game, *player),
                     riichi: true,
                 };
             }
@@ -193,5 +280,796 @@ pub fn dump_caller_bot() -> AiServer {
                 riichi: false,
             }
         },
+        // Always declare tempai
+        |_| true,
+    )
+}
+
+/// Discard among `can_riichi` leaving the most live waits
+fn best_riichi_discard(can_riichi: &[ThrowableOnRiichi], game: &Game, player: Fon) -> TehaiIndex {
+    let view = game.player_view(player);
+    let te = view.te();
+    can_riichi
+        .iter()
+        .map(|throwable| match throwable {
+            ThrowableOnRiichi::Te(index) => TehaiIndex::Tehai(*index),
+            ThrowableOnRiichi::Tsumohai => TehaiIndex::Tsumohai,
+        })
+        .min_by_key(|index| {
+            let mut hai = te.hai().to_vec();
+            if let TehaiIndex::Tehai(i) = index {
+                hai.remove(*i);
+                hai.extend(te.get(TehaiIndex::Tsumohai));
+            }
+            let live: usize = analysis::analyze_waits(&hai, te.fuuro(), &view)
+                .iter()
+                .map(|wait| wait.remaining)
+                .sum();
+            std::cmp::Reverse(live)
+        })
+        .expect("Has a discard on riichi")
+}
+
+/// When [`efficiency_bot`] and [`defense_bot`] declare riichi
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum RiichiPolicy {
+    /// On live waits without furiten
+    #[default]
+    Auto,
+    /// Whenever possible
+    Always,
+    /// Never: stay closed without riichi (黙聴)
+    Never,
+}
+
+impl FromStr for RiichiPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(RiichiPolicy::Auto),
+            "always" => Ok(RiichiPolicy::Always),
+            "never" => Ok(RiichiPolicy::Never),
+            _ => Err(format!(
+                "Unknown riichi policy '{}', expected auto, always or never",
+                s
+            )),
+        }
+    }
+}
+
+/// Tuning of [`efficiency_bot`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EfficiencyParams {
+    pub riichi: RiichiPolicy,
+    /// Call pon and chi keeping a yaku. Ron is always called.
+    pub calls: bool,
+}
+
+impl Default for EfficiencyParams {
+    fn default() -> Self {
+        EfficiencyParams {
+            riichi: RiichiPolicy::Auto,
+            calls: true,
+        }
+    }
+}
+
+/// AI playing for tile efficiency (牌効率) only
+///
+/// It discards to lower its shanten and widen its live ukeire, riichis as
+/// soon as it is tenpai and not furiten, and only calls when the call keeps
+/// a yaku and lowers its shanten. `params` changes when it riichis and
+/// whether it calls.
+pub fn efficiency_bot(params: EfficiencyParams) -> AiServer {
+    AiServer::with_handlers(
+        move |possible_calls, request| tuned_call(&params, possible_calls, request),
+        move |possible_actions, request| efficiency_turn(&params, possible_actions, request),
+        // Always declare tempai
+        |_| true,
+    )
+}
+
+/// [`efficiency_call`], or only ron without calls
+fn tuned_call(
+    params: &EfficiencyParams,
+    possible_calls: &[PossibleCall],
+    request: &GameRequest,
+) -> Option<Call> {
+    if params.calls {
+        efficiency_call(possible_calls, request)
+    } else if possible_calls.contains(&PossibleCall::Ron) {
+        Some(Call::Ron)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn efficiency_call(
+    possible_calls: &[PossibleCall],
+    request: &GameRequest,
+) -> Option<Call> {
+    if possible_calls.contains(&PossibleCall::Ron) {
+        return Some(Call::Ron);
+    }
+    let view = request.game.player_view(request.player);
+    let taken = view.last_discard()?;
+    let te = view.te();
+    let current = shanten_counts(&te.counts(), te.fuuro().len());
+
+    let mut candidates = vec![];
+    for call in possible_calls {
+        match call {
+            PossibleCall::Pon => {
+                let kind = TileKind::from(taken);
+                let mut own = te
+                    .hai()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, hai)| TileKind::from(**hai) == kind);
+                let first = own.next().map(|(i, _)| i);
+                let second = own.next().map(|(i, _)| i);
+                if let (Some(first), Some(second)) = (first, second) {
+                    candidates.push((Call::Pon, [first, second]));
+                }
+            }
+            PossibleCall::Chi { indices } => candidates.extend(
+                indices
+                    .iter()
+                    .map(|index| (Call::Chi { index: *index }, *index)),
+            ),
+            // Kan only slow the hand down
+            PossibleCall::Kan | PossibleCall::Ron => {}
+        }
+    }
+
+    candidates
+        .into_iter()
+        .filter_map(|(call, index)| {
+            let mut hidden = TileCounts::from_hai(te.hai());
+            let mut meld = vec![TileKind::from(taken)];
+            for i in index {
+                let kind = TileKind::from(te.hai()[i]);
+                hidden.remove(kind);
+                meld.push(kind);
+            }
+            let melds = te.fuuro().len() + 1;
+            let shanten = hidden
+                .iter()
+                .map(|(kind, _)| {
+                    let mut after = hidden;
+                    after.remove(kind);
+                    shanten_counts(&after, melds)
+                })
+                .min()?;
+
+            let mut open_melds: Vec<Vec<TileKind>> = te
+                .fuuro()
+                .iter()
+                .map(|fuuro| fuuro.hai().into_iter().map(TileKind::from).collect())
+                .collect();
+            open_melds.push(meld);
+            if shanten < current && keeps_open_yaku(&hidden, &open_melds, &view.win_context()) {
+                Some((shanten, call))
+            } else {
+                None
+            }
+        })
+        .min_by_key(|(shanten, _)| *shanten)
+        .map(|(_, call)| call)
+}
+
+/// Whether an open te still has a plausible yaku: yakuhai, tanyao or
+/// honitsu. At most one hidden tile may be in the way, as it is discarded
+/// right after the call.
+fn keeps_open_yaku(hidden: &TileCounts, melds: &[Vec<TileKind>], context: &WinContext) -> bool {
+    let is_yakuhai = |kind: TileKind| kind.to_hai().is_yakuhai(context.bakaze, context.jikaze);
+    let yakuhai = melds
+        .iter()
+        .any(|meld| meld[1..].iter().all(|kind| *kind == meld[0]) && is_yakuhai(meld[0]))
+        || hidden
+            .iter()
+            .any(|(kind, count)| count >= 3 && is_yakuhai(kind));
+    if yakuhai {
+        return true;
+    }
+
+    let in_the_way = |keep: &dyn Fn(TileKind) -> bool| {
+        melds.iter().flatten().all(|kind| keep(*kind))
+            && hidden
+                .iter()
+                .filter(|(kind, _)| !keep(*kind))
+                .map(|(_, count)| count as usize)
+                .sum::<usize>()
+                <= 1
+    };
+    let tanyao = in_the_way(&|kind: TileKind| !kind.is_jihai_or_1_9());
+    // The suit of honitsu is the one of the melds, or any if they are all
+    // honors
+    let meld_suit = melds
+        .iter()
+        .flatten()
+        .find(|kind| kind.is_suuhai())
+        .map(|kind| kind.index() / 9);
+    let honitsu = (0..3)
+        .filter(|suit| meld_suit.map_or(true, |meld_suit| meld_suit == *suit))
+        .any(|suit| in_the_way(&|kind: TileKind| !kind.is_suuhai() || kind.index() / 9 == suit));
+    tanyao || honitsu
+}
+
+fn efficiency_turn(
+    params: &EfficiencyParams,
+    possible_actions: &PossibleActions,
+    request: &GameRequest,
+) -> TurnResult {
+    if let Some(result) = forced_turn(possible_actions, request) {
+        return result;
+    }
+    let view = request.game.player_view(request.player);
+    let discards = te_discards(&view);
+    throw_first(
+        discards.iter().collect(),
+        &possible_actions.can_riichi,
+        params.riichi,
+        &view,
+    )
+}
+
+/// Actions taken before looking at discards: tsumo, tsumogiri in riichi,
+/// kyusyukyuhai with few terminals and ankan not slowing the te down
+fn forced_turn(possible_actions: &PossibleActions, request: &GameRequest) -> Option<TurnResult> {
+    let PossibleActions {
+        can_tsumo,
+        can_kyusyukyuhai,
+        can_ankan,
+        ..
+    } = possible_actions;
+    if *can_tsumo {
+        return Some(TurnResult::Tsumo);
+    }
+    let view = request.game.player_view(request.player);
+    let te = view.te();
+    if view.is_riichi(request.player) {
+        return Some(TurnResult::ThrowHai {
+            index: TehaiIndex::Tsumohai,
+            riichi: false,
+        });
+    }
+
+    let counts = te.counts();
+    let melds = te.fuuro().len();
+    // Keep going with 10 kinds or more, for kokushimusou
+    if *can_kyusyukyuhai && kokushimusou_shanten(&counts) > 3 {
+        return Some(TurnResult::Kyusyukyuhai);
+    }
+
+    let current = shanten_counts(&counts, melds);
+    for hai in can_ankan {
+        let mut after = counts;
+        for _ in 0..4 {
+            after.remove(TileKind::from(*hai));
+        }
+        // The replacement tile (嶺上牌) makes up for the kan, so the
+        // remaining tiles compare with the te after a discard
+        let shanten = shanten_counts(&after, melds + 1);
+        if shanten <= current {
+            let index = te.index(*hai).expect("Has ankan tile");
+            return Some(TurnResult::Ankan { index });
+        }
+    }
+    None
+}
+
+/// Every discard of the player's te, best for efficiency first
+fn te_discards(view: &PlayerView) -> Vec<analysis::Discard> {
+    let te = view.te();
+    let mut hai = te.hai().to_vec();
+    hai.extend(te.get(TehaiIndex::Tsumohai));
+    analysis::analyze_discards(&hai, te.fuuro(), view)
+}
+
+/// Throw the first of `discards`, or riichi on the first one allowing a
+/// riichi according to `policy`.
+fn throw_first(
+    discards: Vec<&analysis::Discard>,
+    can_riichi: &[ThrowableOnRiichi],
+    policy: RiichiPolicy,
+    view: &PlayerView,
+) -> TurnResult {
+    let te = view.te();
+    if !can_riichi.is_empty() && policy != RiichiPolicy::Never {
+        let riichi_kinds: Vec<TileKind> = can_riichi
+            .iter()
+            .map(|throwable| {
+                let index = match throwable {
+                    ThrowableOnRiichi::Te(index) => TehaiIndex::Tehai(*index),
+                    ThrowableOnRiichi::Tsumohai => TehaiIndex::Tsumohai,
+                };
+                TileKind::from(te.get(index).expect("Has tile"))
+            })
+            .collect();
+        let riichi = discards.iter().find(|discard| {
+            riichi_kinds.contains(&TileKind::from(discard.hai))
+                && (policy == RiichiPolicy::Always
+                    || (discard.ukeire_count() > 0 && !is_furiten_after(view, discard)))
+        });
+        if let Some(discard) = riichi {
+            return TurnResult::ThrowHai {
+                index: tehai_index(te, discard.hai),
+                riichi: true,
+            };
+        }
+    }
+
+    let discard = discards.first().expect("Has a tile to discard");
+    TurnResult::ThrowHai {
+        index: tehai_index(te, discard.hai),
+        riichi: false,
+    }
+}
+
+/// When [`defense_bot`] keeps building its te against a threat (押し) or
+/// folds (降り)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DefenseParams {
+    /// Push when the shanten is at most this, fold otherwise. 0 only pushes
+    /// when tenpai.
+    pub push_shanten: isize,
+    /// When tenpai, only push if the te is worth at least this many points
+    /// on ron. A closed te is valued with riichi.
+    pub push_value: usize,
+    /// Riichi and calls when not folding
+    pub efficiency: EfficiencyParams,
+}
+
+impl Default for DefenseParams {
+    fn default() -> Self {
+        DefenseParams {
+            push_shanten: 0,
+            push_value: 2000,
+            efficiency: EfficiencyParams::default(),
+        }
+    }
+}
+
+/// AI playing for tile efficiency, which defends against riichi and
+/// threatening open hands
+///
+/// Against a threat, it either pushes with the safest discard keeping its
+/// shanten, or folds by discarding the safest tile, according to `params`.
+pub fn defense_bot(params: DefenseParams) -> AiServer {
+    AiServer::with_handlers(
+        move |possible_calls, request| defense_call(&params, possible_calls, request),
+        move |possible_actions, request| defense_turn(&params, possible_actions, request),
+        // Always declare tempai
+        |_| true,
     )
 }
+
+fn defense_call(
+    params: &DefenseParams,
+    possible_calls: &[PossibleCall],
+    request: &GameRequest,
+) -> Option<Call> {
+    let view = request.game.player_view(request.player);
+    if !possible_calls.contains(&PossibleCall::Ron) && !threats(&view).is_empty() {
+        // A call lowers the shanten by one at most
+        let te = view.te();
+        let current = shanten_counts(&te.counts(), te.fuuro().len());
+        if current - 1 > params.push_shanten {
+            return None;
+        }
+    }
+    tuned_call(&params.efficiency, possible_calls, request)
+}
+
+fn defense_turn(
+    params: &DefenseParams,
+    possible_actions: &PossibleActions,
+    request: &GameRequest,
+) -> TurnResult {
+    let view = request.game.player_view(request.player);
+    let threats = threats(&view);
+    if threats.is_empty() {
+        return efficiency_turn(&params.efficiency, possible_actions, request);
+    }
+    if let Some(result) = forced_turn(possible_actions, request) {
+        return result;
+    }
+
+    let mut danger = [0; TileKind::COUNT];
+    for target in threats {
+        for (total, safety) in danger.iter_mut().zip(safety::analyze_safety(&view, target)) {
+            *total += safety.danger;
+        }
+    }
+    let discards = te_discards(&view);
+    let best = discards.first().expect("Has a tile to discard");
+    let push = best.shanten <= params.push_shanten
+        && (best.shanten > 0 || tenpai_value(&view, best) >= params.push_value);
+
+    let mut candidates: Vec<_> = discards
+        .iter()
+        .filter(|discard| !push || discard.shanten == best.shanten)
+        .collect();
+    // Safest first, keeping the efficiency order among equally safe tiles
+    candidates.sort_by_key(|discard| danger[TileKind::from(discard.hai).index()]);
+    let can_riichi = if push {
+        &possible_actions.can_riichi[..]
+    } else {
+        &[]
+    };
+    throw_first(candidates, can_riichi, params.efficiency.riichi, &view)
+}
+
+/// Other players in riichi, or with an open te showing at least 3 calls,
+/// or 2 calls with dora
+fn threats(view: &PlayerView) -> Vec<Fon> {
+    let dora = view.win_context().dora();
+    [Fon::Ton, Fon::Nan, Fon::Shaa, Fon::Pee]
+        .into_iter()
+        .filter(|p| *p != view.player())
+        .filter(|p| {
+            let open: Vec<_> = view
+                .fuuro(*p)
+                .iter()
+                .filter(|fuuro| !matches!(fuuro, Fuuro::Kantsu(KantsuInner::Ankan { .. })))
+                .collect();
+            let shows_dora = open
+                .iter()
+                .flat_map(|fuuro| fuuro.hai())
+                .any(|hai| hai.is_aka() || dora.contains(&hai));
+            view.is_riichi(*p) || open.len() >= 3 || (open.len() == 2 && shows_dora)
+        })
+        .collect()
+}
+
+/// Best ron value of the te after a discard into tenpai, without honba
+/// and riichi sticks
+fn tenpai_value(view: &PlayerView, discard: &analysis::Discard) -> usize {
+    let te = view.te();
+    let mut hai = te.hai().to_vec();
+    hai.extend(te.get(TehaiIndex::Tsumohai));
+    let i = hai
+        .iter()
+        .position(|h| *h == discard.hai && h.is_aka() == discard.hai.is_aka())
+        .expect("Has discard");
+    hai.remove(i);
+
+    let mut context = view.win_context();
+    let closed = te
+        .fuuro()
+        .iter()
+        .all(|fuuro| matches!(fuuro, Fuuro::Kantsu(KantsuInner::Ankan { .. })));
+    context.riichi |= closed;
+    context.honba = 0;
+    context.riichi_bou = 0;
+    discard
+        .ukeire
+        .iter()
+        .map(|acceptance| {
+            let score = yaku::score(
+                &hai,
+                te.fuuro(),
+                acceptance.hai,
+                WinningMethod::Ron,
+                &context,
+                view.rules(),
+            );
+            score.payments[context.jikaze as usize].max(0) as usize
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Whether discarding into tenpai leaves the player furiten: on their own
+/// discards, called ones included, or for a winning tile let go in riichi
+fn is_furiten_after(view: &PlayerView, discard: &analysis::Discard) -> bool {
+    if view.is_riichi_furiten() {
+        return true;
+    }
+    let discards = view.discards(view.player());
+    discard.ukeire.iter().any(|acceptance| {
+        let kind = TileKind::from(acceptance.hai);
+        kind == TileKind::from(discard.hai)
+            || discards
+                .iter()
+                .any(|thrown| TileKind::from(thrown.hai) == kind)
+    })
+}
+
+/// Index of `hai` in the te, telling red fives apart and preferring the
+/// drawn tile
+fn tehai_index(te: &Te, hai: Hai) -> TehaiIndex {
+    let same = |other: Hai| other == hai && other.is_aka() == hai.is_aka();
+    if te.get(TehaiIndex::Tsumohai).map_or(false, same) {
+        return TehaiIndex::Tsumohai;
+    }
+    te.hai()
+        .iter()
+        .position(|other| same(*other))
+        .map(TehaiIndex::Tehai)
+        .expect("Has tile")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tiles::hai_list_from_str;
+
+    fn turn(game: &Game, can_riichi: Vec<ThrowableOnRiichi>) -> TurnResult {
+        turn_with(&Default::default(), game, can_riichi)
+    }
+
+    fn turn_with(
+        params: &EfficiencyParams,
+        game: &Game,
+        can_riichi: Vec<ThrowableOnRiichi>,
+    ) -> TurnResult {
+        let possible_actions = PossibleActions {
+            can_tsumo: false,
+            can_riichi,
+            can_kyusyukyuhai: false,
+            can_shominkan: vec![],
+            can_ankan: vec![],
+        };
+        let request = GameRequest {
+            game: game.clone(),
+            request: Request::DoTurn(possible_actions.clone()),
+            player: Fon::Ton,
+        };
+        efficiency_turn(params, &possible_actions, &request)
+    }
+
+    /// Game where 北 just discarded `hai` and 東 has `te`
+    fn call(te: &str, hai: &str, possible_calls: Vec<PossibleCall>) -> Option<Call> {
+        let mut game = Game::default();
+        *game.player_te_mut(Fon::Ton) = te.parse().unwrap();
+        *game.player_te_mut(Fon::Pee) = hai.parse().unwrap();
+        game.throw_tile(Fon::Pee, TehaiIndex::Tehai(0), false);
+        let request = GameRequest {
+            game,
+            request: Request::Call(possible_calls.clone()),
+            player: Fon::Ton,
+        };
+        efficiency_call(&possible_calls, &request)
+    }
+
+    #[test]
+    fn test_efficiency_discards_isolated_honor() {
+        let mut game = Game::default();
+        *game.player_te_mut(Fon::Ton) = "12399m456p23578s 1z".parse().unwrap();
+        let result = turn(&game, vec![]);
+        assert!(matches!(
+            result,
+            TurnResult::ThrowHai {
+                index: TehaiIndex::Tsumohai,
+                riichi: false
+            }
+        ));
+
+        *game.player_te_mut(Fon::Ton) = "12399m456p2378s1z 5s".parse().unwrap();
+        let te = game.player_te_(Fon::Ton).clone();
+        match turn(&game, vec![]) {
+            TurnResult::ThrowHai { index, .. } => {
+                assert_eq!(
+                    te.get(index),
+                    hai_list_from_str("1z").unwrap().first().copied()
+                )
+            }
+            _ => panic!("Should discard"),
+        }
+    }
+
+    #[test]
+    fn test_efficiency_riichi_unless_furiten() {
+        let mut game = Game::default();
+        *game.player_te_mut(Fon::Ton) = "123m456p789s23s55z 9m".parse().unwrap();
+        let result = turn(&game, vec![ThrowableOnRiichi::Tsumohai]);
+        assert!(matches!(
+            result,
+            TurnResult::ThrowHai {
+                index: TehaiIndex::Tsumohai,
+                riichi: true
+            }
+        ));
+
+        // 1s in the river: furiten on the 1-4s wait
+        *game.player_te_mut(Fon::Ton) = "123m456p789s23s55z 1s".parse().unwrap();
+        game.throw_tile(Fon::Ton, TehaiIndex::Tsumohai, false);
+        let tsumo = hai_list_from_str("9m").unwrap()[0];
+        game.player_te_mut(Fon::Ton).set_tsumohai(tsumo);
+        let result = turn(&game, vec![ThrowableOnRiichi::Tsumohai]);
+        assert!(matches!(
+            result,
+            TurnResult::ThrowHai {
+                index: TehaiIndex::Tsumohai,
+                riichi: false
+            }
+        ));
+    }
+
+    #[test]
+    fn test_efficiency_riichi_furiten_on_called_tile() {
+        let mut game = Game::default();
+        *game.player_te_mut(Fon::Ton) = "123m456p789s23s55z 1s".parse().unwrap();
+        game.throw_tile(Fon::Ton, TehaiIndex::Tsumohai, false);
+        // 1s called by 南: out of the river, but still furiten
+        *game.player_te_mut(Fon::Nan) = "11s".parse().unwrap();
+        game.turn = Fon::Nan;
+        game.call_pon(Fon::Nan);
+        assert!(game.player_view(Fon::Ton).river(Fon::Ton).is_empty());
+        let tsumo = hai_list_from_str("9m").unwrap()[0];
+        game.player_te_mut(Fon::Ton).set_tsumohai(tsumo);
+        let result = turn(&game, vec![ThrowableOnRiichi::Tsumohai]);
+        assert!(matches!(
+            result,
+            TurnResult::ThrowHai {
+                index: TehaiIndex::Tsumohai,
+                riichi: false
+            }
+        ));
+    }
+
+    #[test]
+    fn test_efficiency_riichi_policy() {
+        let mut game = Game::default();
+        *game.player_te_mut(Fon::Ton) = "123m456p789s23s55z 1s".parse().unwrap();
+        game.throw_tile(Fon::Ton, TehaiIndex::Tsumohai, false);
+        let tsumo = hai_list_from_str("9m").unwrap()[0];
+        game.player_te_mut(Fon::Ton).set_tsumohai(tsumo);
+        let always = EfficiencyParams {
+            riichi: RiichiPolicy::Always,
+            ..Default::default()
+        };
+        let result = turn_with(&always, &game, vec![ThrowableOnRiichi::Tsumohai]);
+        assert!(matches!(result, TurnResult::ThrowHai { riichi: true, .. }));
+
+        *game.player_te_mut(Fon::Ton) = "123m456p789s23s55z 9m".parse().unwrap();
+        let never = EfficiencyParams {
+            riichi: RiichiPolicy::Never,
+            ..Default::default()
+        };
+        let result = turn_with(&never, &game, vec![ThrowableOnRiichi::Tsumohai]);
+        assert!(matches!(result, TurnResult::ThrowHai { riichi: false, .. }));
+        assert_eq!("never".parse(), Ok(RiichiPolicy::Never));
+        assert!("sometimes".parse::<RiichiPolicy>().is_err());
+    }
+
+    /// Game where 南 has 3 open calls and discarded 5m, and 東 has `te`
+    fn defense_turn_against_calls(te: &str, params: DefenseParams) -> (Te, TurnResult) {
+        let mut game = Game::default();
+        *game.player_te_mut(Fon::Nan) = "5m1z [2-34m] [55-5p] [6-78s]".parse().unwrap();
+        game.throw_tile(Fon::Nan, TehaiIndex::Tehai(0), false);
+        *game.player_te_mut(Fon::Ton) = te.parse().unwrap();
+        let possible_actions = PossibleActions {
+            can_tsumo: false,
+            can_riichi: vec![ThrowableOnRiichi::Tsumohai],
+            can_kyusyukyuhai: false,
+            can_shominkan: vec![],
+            can_ankan: vec![],
+        };
+        let request = GameRequest {
+            game: game.clone(),
+            request: Request::DoTurn(possible_actions.clone()),
+            player: Fon::Ton,
+        };
+        let te = game.player_te_(Fon::Ton).clone();
+        (te, defense_turn(&params, &possible_actions, &request))
+    }
+
+    #[test]
+    fn test_defense_folds_on_genbutsu() {
+        let te = "1245m3467p2368s 9p";
+        let (te, result) = defense_turn_against_calls(te, DefenseParams::default());
+        match result {
+            TurnResult::ThrowHai { index, riichi } => {
+                assert_eq!(
+                    te.get(index),
+                    hai_list_from_str("5m").unwrap().first().copied()
+                );
+                assert!(!riichi);
+            }
+            _ => panic!("Should discard"),
+        }
+    }
+
+    #[test]
+    fn test_defense_pushes_valuable_tenpai() {
+        let te = "123m456p789s23s55z 9m";
+        let (_, result) = defense_turn_against_calls(te, DefenseParams::default());
+        assert!(matches!(
+            result,
+            TurnResult::ThrowHai {
+                index: TehaiIndex::Tsumohai,
+                riichi: true
+            }
+        ));
+
+        // Not worth a mangan: fold on the suji of 5m
+        let params = DefenseParams {
+            push_value: 12000,
+            ..Default::default()
+        };
+        let (te, result) = defense_turn_against_calls(te, params);
+        match result {
+            TurnResult::ThrowHai { index, riichi } => {
+                assert_eq!(
+                    te.get(index),
+                    hai_list_from_str("2m").unwrap().first().copied()
+                );
+                assert!(!riichi);
+            }
+            _ => panic!("Should discard"),
+        }
+    }
+
+    #[test]
+    fn test_tenpai_value_without_honba() {
+        let mut game = Game::default();
+        *game.player_te_mut(Fon::Ton) = "123m456p789s23s55z 9m".parse().unwrap();
+        let value = |game: &Game| {
+            let view = game.player_view(Fon::Ton);
+            let discards = te_discards(&view);
+            tenpai_value(&view, &discards[0])
+        };
+        // Riichi, 40 fu 1 han for the oya
+        assert_eq!(value(&game), 2000);
+        *game.honba_mut() = 3;
+        assert_eq!(value(&game), 2000);
+    }
+
+    #[test]
+    fn test_efficiency_calls_only_with_yaku() {
+        let te = "23m457p789s11s577z";
+        // Chi on 1m leaves the te without yaku
+        let chi = PossibleCall::Chi {
+            indices: vec![[0, 1]],
+        };
+        assert_eq!(call(te, "1m", vec![chi]), None);
+        // Pon on 中 is a yakuhai
+        assert_eq!(call(te, "7z", vec![PossibleCall::Pon]), Some(Call::Pon));
+        // A pon not improving the te is declined
+        assert_eq!(
+            call("23m457p789s11s777z", "1s", vec![PossibleCall::Pon]),
+            None
+        );
+        assert_eq!(
+            call(te, "7z", vec![PossibleCall::Pon, PossibleCall::Ron]),
+            Some(Call::Ron)
+        );
+    }
+
+    #[test]
+    fn test_keeps_open_yaku_honitsu() {
+        let context = WinContext {
+            bakaze: Fon::Ton,
+            jikaze: Fon::Nan,
+            ..Default::default()
+        };
+        let melds = |melds: &[&str]| -> Vec<Vec<TileKind>> {
+            melds
+                .iter()
+                .map(|meld| {
+                    let hai = hai_list_from_str(meld).unwrap();
+                    hai.into_iter().map(TileKind::from).collect()
+                })
+                .collect()
+        };
+        let hidden = TileCounts::from_hai(&hai_list_from_str("1345689p4z").unwrap());
+        // A guest wind pon first does not hide the suit of the later chi
+        assert!(keeps_open_yaku(
+            &hidden,
+            &melds(&["444z", "123p"]),
+            &context
+        ));
+        assert!(!keeps_open_yaku(
+            &hidden,
+            &melds(&["444z", "123s"]),
+            &context
+        ));
+        // With only honors called, the suit comes from the hidden tiles
+        assert!(keeps_open_yaku(&hidden, &melds(&["444z"]), &context));
+        assert!(keeps_open_yaku(&hidden, &[], &context));
+    }
+}
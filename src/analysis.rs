@@ -0,0 +1,369 @@
+//! Analysis of a te from the point of view of its player.
+
+use serde::Serialize;
+
+use super::game::{Fuuro, PlayerView};
+use super::shanten::{chiitoitsu_shanten, kokushimusou_shanten, shanten_counts};
+use super::tiles::{Hai, TileCounts, TileKind};
+use super::yaku::{self, Machi, ScoreBreakdown, WinningMethod};
+
+/// A tile completing a tenpai te (待ち牌)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Wait {
+    pub hai: Hai,
+    /// Copies of the tile the player cannot see, i.e. which can still be
+    /// drawn or discarded by someone else
+    pub remaining: usize,
+    /// Shape of the wait, as counted for fu
+    pub machi: Machi,
+    /// Score on ron. Has no yaku if the player cannot ron on this tile.
+    pub ron: ScoreBreakdown,
+    /// Score on tsumo. Has no yaku if the player cannot tsumo this tile.
+    pub tsumo: ScoreBreakdown,
+}
+
+impl Wait {
+    /// Can win on this tile by ron or tsumo (has a yaku)
+    pub fn has_yaku(&self) -> bool {
+        self.ron.is_agari() || self.tsumo.is_agari()
+    }
+}
+
+/// Find all the tiles which would complete `te` (hidden tiles, without a
+/// drawn tile) and `fuuro`, with how many are still live and the score
+/// each of them would bring.
+///
+/// Waits without yaku are listed too, so that a player can know that they
+/// are tenpai but cannot win. Returns an empty list if the te is not
+/// tenpai.
+pub fn analyze_waits(te: &[Hai], fuuro: &[Fuuro], view: &PlayerView) -> Vec<Wait> {
+    let context = view.win_context();
+    let own_counts: TileCounts = te
+        .iter()
+        .copied()
+        .chain(fuuro.iter().flat_map(Fuuro::hai))
+        .collect();
+
+    let mut waits = vec![];
+    for kind in TileKind::all() {
+        let hai = kind.to_hai();
+        let own = own_counts[kind] as usize;
+        if own >= 4 {
+            // Cannot wait for a 5th copy
+            continue;
+        }
+        let ron = yaku::score(te, fuuro, hai, WinningMethod::Ron, &context, view.rules());
+        let machi = if let Some(machi) = ron.machi {
+            machi
+        } else {
+            continue;
+        };
+        let tsumo = yaku::score(te, fuuro, hai, WinningMethod::Tsumo, &context, view.rules());
+        waits.push(Wait {
+            hai,
+            remaining: 4usize.saturating_sub(own + view.visible_count(hai)),
+            machi,
+            ron,
+            tsumo,
+        });
+    }
+    waits
+}
+
+/// Tile kind accepted by a te, with its live count
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+pub struct Acceptance {
+    pub hai: Hai,
+    /// Copies of the tile the player cannot see
+    pub remaining: usize,
+}
+
+/// Draw which keeps the shanten but widens the ukeire (好形変化)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+pub struct Improvement {
+    pub hai: Hai,
+    /// Copies of the tile the player cannot see
+    pub remaining: usize,
+    /// Best tile to discard after drawing `hai`
+    pub discard: Hai,
+    /// Live count of the ukeire after this discard
+    pub ukeire_count: usize,
+}
+
+/// Outcome of discarding a tile (打牌候補)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Discard {
+    pub hai: Hai,
+    /// Shanten (向聴数) of the te after the discard. 0 is tenpai.
+    pub shanten: isize,
+    /// Tile kinds lowering the shanten (受け入れ). When tenpai, these are
+    /// the winning tiles, yaku or not.
+    pub ukeire: Vec<Acceptance>,
+    /// Tile kinds not lowering the shanten but widening the ukeire. Only
+    /// computed for the discards with the lowest shanten.
+    pub improvements: Vec<Improvement>,
+}
+
+impl Discard {
+    /// Number of live tiles lowering the shanten
+    pub fn ukeire_count(&self) -> usize {
+        self.ukeire.iter().map(|a| a.remaining).sum()
+    }
+
+    /// Number of live tiles widening the ukeire
+    pub fn improvement_count(&self) -> usize {
+        self.improvements.iter().map(|i| i.remaining).sum()
+    }
+}
+
+/// List every possible discard of a te of 14 tiles (hidden tiles with the
+/// drawn tile, 3 less per fuuro), best first: lowest shanten, then widest
+/// ukeire, then widest improvements.
+///
+/// Live counts are computed from what the player can see in `view`.
+pub fn analyze_discards(te: &[Hai], fuuro: &[Fuuro], view: &PlayerView) -> Vec<Discard> {
+    let mut hidden = TileCounts::from_hai(te);
+    let in_fuuro: TileCounts = fuuro.iter().flat_map(Fuuro::hai).collect();
+    let mut remaining = [0; TileKind::COUNT];
+    for kind in TileKind::all() {
+        let own = (hidden[kind] + in_fuuro[kind]) as usize;
+        remaining[kind.index()] = 4usize.saturating_sub(own + view.visible_count(kind.to_hai()));
+    }
+    let efficiency = Efficiency {
+        melds: fuuro.len(),
+        in_fuuro,
+        remaining,
+    };
+
+    let mut discards = vec![];
+    for (kind, _) in TileCounts::from_hai(te).iter() {
+        hidden.remove(kind);
+        let shanten = efficiency.shanten(&hidden);
+        let ukeire = efficiency.ukeire(&mut hidden, shanten);
+        hidden.add(kind);
+
+        // Rather discard a normal five than a red one
+        let hai = te
+            .iter()
+            .filter(|hai| TileKind::from(**hai) == kind)
+            .min_by_key(|hai| hai.is_aka())
+            .copied()
+            .expect("Has tile");
+        discards.push(Discard {
+            hai,
+            shanten,
+            ukeire: ukeire
+                .into_iter()
+                .map(|kind| Acceptance {
+                    hai: kind.to_hai(),
+                    remaining: remaining[kind.index()],
+                })
+                .collect(),
+            improvements: vec![],
+        });
+    }
+
+    // Improvements are only worth looking at for the best discards
+    let min_shanten = discards.iter().map(|d| d.shanten).min();
+    for discard in &mut discards {
+        if Some(discard.shanten) == min_shanten {
+            let kind = TileKind::from(discard.hai);
+            let ukeire: Vec<_> = discard.ukeire.iter().map(|a| a.hai.into()).collect();
+            hidden.remove(kind);
+            discard.improvements = efficiency.improvements(&mut hidden, discard.shanten, &ukeire);
+            hidden.add(kind);
+        }
+    }
+    discards.sort_by_key(|discard| {
+        (
+            discard.shanten,
+            std::cmp::Reverse(discard.ukeire_count()),
+            std::cmp::Reverse(discard.improvement_count()),
+        )
+    });
+    discards
+}
+
+/// What is known of the tiles besides the hidden ones
+struct Efficiency {
+    melds: usize,
+    /// Tiles in the player's fuuro
+    in_fuuro: TileCounts,
+    /// Live count of each kind
+    remaining: [usize; TileKind::COUNT],
+}
+
+impl Efficiency {
+    fn shanten(&self, hidden: &TileCounts) -> isize {
+        shanten_counts(hidden, self.melds)
+    }
+
+    fn live(&self, kinds: &[TileKind]) -> usize {
+        kinds.iter().map(|kind| self.remaining[kind.index()]).sum()
+    }
+
+    /// Kinds which lower the shanten of `hidden`. Dead kinds are listed
+    /// too, unless the player holds all of their copies.
+    fn ukeire(&self, hidden: &mut TileCounts, shanten: isize) -> Vec<TileKind> {
+        // Unless chiitoitsu or kokushimusou are as close, only tiles
+        // connected to the te can lower the shanten.
+        let only_normal = self.melds > 0
+            || (chiitoitsu_shanten(hidden) > shanten && kokushimusou_shanten(hidden) > shanten);
+        let mut ukeire = vec![];
+        for kind in TileKind::all() {
+            if hidden[kind] + self.in_fuuro[kind] >= 4
+                || (only_normal && !is_connected(hidden, kind))
+            {
+                continue;
+            }
+            hidden.add(kind);
+            if self.shanten(hidden) < shanten {
+                ukeire.push(kind);
+            }
+            hidden.remove(kind);
+        }
+        ukeire
+    }
+
+    /// Live kinds which keep the shanten of `hidden` but widen its ukeire
+    /// after the best discard
+    fn improvements(
+        &self,
+        hidden: &mut TileCounts,
+        shanten: isize,
+        ukeire: &[TileKind],
+    ) -> Vec<Improvement> {
+        let ukeire_count = self.live(ukeire);
+        let mut improvements = vec![];
+        for draw in TileKind::all() {
+            let remaining = self.remaining[draw.index()];
+            if remaining == 0 || ukeire.contains(&draw) || !is_connected(hidden, draw) {
+                continue;
+            }
+            hidden.add(draw);
+            let mut best: Option<(TileKind, usize)> = None;
+            let held = *hidden;
+            for (discard, _) in held.iter() {
+                if discard == draw {
+                    continue;
+                }
+                hidden.remove(discard);
+                if self.shanten(hidden) == shanten {
+                    let count = self.live(&self.ukeire(hidden, shanten));
+                    if count > best.map(|(_, c)| c).unwrap_or(ukeire_count) {
+                        best = Some((discard, count));
+                    }
+                }
+                hidden.add(discard);
+            }
+            hidden.remove(draw);
+            if let Some((discard, count)) = best {
+                improvements.push(Improvement {
+                    hai: draw.to_hai(),
+                    remaining,
+                    discard: discard.to_hai(),
+                    ukeire_count: count,
+                });
+            }
+        }
+        improvements
+    }
+}
+
+/// Is there a tile within 2 of `k` in the same suit (or the same honor)?
+/// Other tiles can only be isolated after being drawn.
+fn is_connected(hidden: &TileCounts, kind: TileKind) -> bool {
+    if !kind.is_suuhai() {
+        return hidden[kind] > 0;
+    }
+    (-2..=2).any(|n| kind.offset(n).map(|k| hidden[k] > 0).unwrap_or(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::TehaiIndex;
+    use crate::game::Game;
+    use crate::tiles::{hai_list_from_str, Fon};
+
+    #[test]
+    fn test_analyze_waits() {
+        let mut game = Game::default();
+        *game.player_te_mut(Fon::Ton) = "23456m222p678s55s".parse().unwrap();
+        *game.player_te_mut(Fon::Nan) = "7m".parse().unwrap();
+        game.throw_tile(Fon::Nan, TehaiIndex::Tehai(0), false);
+        let view = game.player_view(Fon::Ton);
+        let te = view.te();
+        let waits = analyze_waits(te.hai(), te.fuuro(), &view);
+
+        let hai: Vec<_> = waits.iter().map(|wait| wait.hai).collect();
+        assert_eq!(hai, hai_list_from_str("147m").unwrap());
+        // A 4m in the te, a 7m in the river of 南
+        for (wait, remaining) in waits.iter().zip([4, 3, 3]) {
+            assert_eq!(wait.machi, Machi::Ryanmen);
+            assert_eq!(wait.remaining, remaining);
+            assert!(wait.tsumo.is_agari());
+        }
+        // Tanyao on 4m and 7m only: 1m has no yaku on ron
+        assert!(!waits[0].ron.is_agari());
+        assert!(waits[1].ron.is_agari());
+        assert!(waits[2].ron.is_agari());
+    }
+
+    #[test]
+    fn test_analyze_waits_chiitoitsu() {
+        let game = Game::default();
+        let view = game.player_view(Fon::Ton);
+        let te = hai_list_from_str("1199m2288p3377s5z").unwrap();
+        let waits = analyze_waits(&te, &[], &view);
+        assert_eq!(waits.len(), 1);
+        assert_eq!(waits[0].hai, "5z".parse().unwrap());
+        assert_eq!(waits[0].machi, Machi::Tanki);
+        assert!(waits[0].ron.yaku().contains(&yaku::Yaku::Chiitoitsu));
+    }
+
+    #[test]
+    fn test_analyze_waits_noten() {
+        let game = Game::default();
+        let view = game.player_view(Fon::Ton);
+        let te = hai_list_from_str("1357m2468p1357s1z").unwrap();
+        assert!(analyze_waits(&te, &[], &view).is_empty());
+    }
+
+    #[test]
+    fn test_analyze_discards() {
+        let game = Game::default();
+        let view = game.player_view(Fon::Ton);
+        let te = hai_list_from_str("23456m222p678s55s1z").unwrap();
+        let discards = analyze_discards(&te, &[], &view);
+        assert_eq!(discards.len(), 11);
+        let best = &discards[0];
+        assert_eq!(best.hai, "1z".parse().unwrap());
+        assert_eq!(best.shanten, 0);
+        let ukeire: Vec<_> = best.ukeire.iter().map(|a| a.hai).collect();
+        assert_eq!(ukeire, hai_list_from_str("147m").unwrap());
+        assert!(discards.windows(2).all(|d| d[0].shanten < d[1].shanten
+            || (d[0].shanten == d[1].shanten && d[0].ukeire_count() >= d[1].ukeire_count())));
+    }
+
+    #[test]
+    fn test_improvements() {
+        let game = Game::default();
+        let view = game.player_view(Fon::Ton);
+        let te = hai_list_from_str("13m456p789s111227z").unwrap();
+        let discards = analyze_discards(&te, &[], &view);
+        let best = &discards[0];
+        assert_eq!(best.hai, "7z".parse().unwrap());
+        assert_eq!(best.shanten, 0);
+        // Kanchan wait on 2m
+        assert_eq!(best.ukeire.len(), 1);
+        // Drawing 4m and discarding 1m makes a ryanmen wait
+        let improvement = best
+            .improvements
+            .iter()
+            .find(|i| i.hai == "4m".parse().unwrap())
+            .expect("4m improves the wait");
+        assert_eq!(improvement.discard, "1m".parse().unwrap());
+        assert!(improvement.ukeire_count > best.ukeire_count());
+    }
+}
@@ -0,0 +1,704 @@
+//! Registry of the bots, built from a spec given on the command line:
+//! `name`, `name:key=value,key=value`, `exec:command` or `mjai:command` for
+//! an external bot (`exec,key=value:command` with parameters) or
+//! `@file.ron` for a config file.
+//!
+//! A config file holds the name and parameters of a bot in RON:
+//!
+//! ```ron
+//! (bot: "defense", params: {"push_value": 8000, "riichi": "never"})
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use super::ai::{
+    defense_bot, dump_caller_bot, efficiency_bot, null_bot, AiServer, DefenseParams,
+    EfficiencyParams,
+};
+use super::external::{exec_bot, ExecParams};
+use super::mjai::{mjai_bot, MjaiParams, Transport};
+use super::monte_carlo::{monte_carlo_bot, Budget};
+
+/// Parameter of a bot, for `--list-bots`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParamInfo {
+    pub name: &'static str,
+    pub values: &'static str,
+    pub default: &'static str,
+    pub description: &'static str,
+}
+
+/// Bot of the registry, for `--list-bots`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BotInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub params: &'static [ParamInfo],
+}
+
+const RIICHI: ParamInfo = ParamInfo {
+    name: "riichi",
+    values: "auto|always|never",
+    default: "auto",
+    description: "Riichi on live waits without furiten, whenever possible, or never",
+};
+
+const CALLS: ParamInfo = ParamInfo {
+    name: "calls",
+    values: "true|false",
+    default: "true",
+    description: "Call pon and chi keeping a yaku",
+};
+
+const TIMEOUT_MS: ParamInfo = ParamInfo {
+    name: "timeout_ms",
+    values: "milliseconds",
+    default: "5000",
+    description: "Longest wait for each answer",
+};
+
+const ADDRESS: ParamInfo = ParamInfo {
+    name: "address",
+    values: "host:port",
+    default: "127.0.0.1:11600",
+    description: "Address to listen on",
+};
+
+const WAIT_MS: ParamInfo = ParamInfo {
+    name: "wait_ms",
+    values: "milliseconds",
+    default: "60000",
+    description: "Longest wait for the client to join",
+};
+
+const AGGRESSION: ParamInfo = ParamInfo {
+    name: "aggression",
+    values: "0 to 1|none",
+    default: "none",
+    description: "Fold less as it grows: sets push_shanten to 2×aggression rounded down \
+        and push_value to 8000×(1-aggression), unless they are given. \
+        Without it, efficiency never folds",
+};
+
+/// `push_value` of the defense bot at aggression 0: only a mangan is worth
+/// pushing
+const FOLD_VALUE: f64 = 8000.0;
+
+/// Bots whose whole spec after the colon is a command
+const COMMAND_BOTS: [&str; 2] = ["exec", "mjai"];
+
+/// Every bot, with its parameters
+pub const BOTS: &[BotInfo] = &[
+    BotInfo {
+        name: "null",
+        description: "Discards its drawn tile and never calls",
+        params: &[],
+    },
+    BotInfo {
+        name: "dumb-caller",
+        description: "Calls and riichis whenever possible",
+        params: &[],
+    },
+    BotInfo {
+        name: "efficiency",
+        description: "Plays for the lowest shanten and the most live ukeire",
+        params: &[AGGRESSION, RIICHI, CALLS],
+    },
+    BotInfo {
+        name: "defense",
+        description: "Plays for efficiency, and folds against riichi and threatening calls",
+        params: &[
+            ParamInfo {
+                name: "push_shanten",
+                values: "integer",
+                default: "0",
+                description: "Push against a threat at this shanten or lower",
+            },
+            ParamInfo {
+                name: "push_value",
+                values: "points",
+                default: "2000",
+                description: "Only push a tenpai worth at least this much on ron",
+            },
+            AGGRESSION,
+            RIICHI,
+            CALLS,
+        ],
+    },
+    BotInfo {
+        name: "monte-carlo",
+        description: "Plays out its best discards and calls on randomly dealt hidden tiles",
+        params: &[
+            ParamInfo {
+                name: "playouts",
+                values: "integer",
+                default: "32",
+                description: "Playouts of each candidate decision",
+            },
+            ParamInfo {
+                name: "time_ms",
+                values: "milliseconds|none",
+                default: "none",
+                description: "Time limit of each decision",
+            },
+        ],
+    },
+    BotInfo {
+        name: "exec",
+        description: "External program speaking the JSON lines protocol, e.g. exec:./mybot",
+        params: &[
+            ParamInfo {
+                name: "command",
+                values: "command",
+                default: "",
+                description: "Program and its arguments",
+            },
+            TIMEOUT_MS,
+        ],
+    },
+    BotInfo {
+        name: "mjai",
+        description: "External program speaking MJAI over stdin and stdout, e.g. mjai:./mybot",
+        params: &[
+            ParamInfo {
+                name: "command",
+                values: "command",
+                default: "",
+                description: "Program and its arguments",
+            },
+            TIMEOUT_MS,
+        ],
+    },
+    BotInfo {
+        name: "mjai-tcp",
+        description: "MJAI client joining a local TCP server",
+        params: &[ADDRESS, WAIT_MS, TIMEOUT_MS],
+    },
+];
+
+/// Bot from the registry, with its parameters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bot {
+    Null,
+    DumbCaller,
+    Efficiency(EfficiencyParams),
+    Defense(DefenseParams),
+    MonteCarlo(Budget),
+    Exec(ExecParams),
+    Mjai(MjaiParams),
+}
+
+impl Bot {
+    /// Check the name and parameters of `spec` against the registry
+    pub fn from_spec(spec: &BotSpec) -> Result<Bot, String> {
+        let info = find(&spec.name)
+            .ok_or_else(|| format!("Unknown bot '{}', see --list-bots", spec.name))?;
+        for (key, _) in &spec.params {
+            if !info.params.iter().any(|param| param.name == key) {
+                let expected: Vec<_> = info.params.iter().map(|param| param.name).collect();
+                return Err(format!(
+                    "Unknown parameter '{}' of {}, expected: {}",
+                    key,
+                    info.name,
+                    if expected.is_empty() {
+                        "none".to_owned()
+                    } else {
+                        expected.join(", ")
+                    }
+                ));
+            }
+        }
+
+        let bot = match info.name {
+            "null" => Bot::Null,
+            "dumb-caller" => Bot::DumbCaller,
+            // With an aggression, the efficiency bot folds like the defense
+            // bot
+            "efficiency" => match aggression(spec)? {
+                None => Bot::Efficiency(efficiency_params(spec)?),
+                Some(aggression) => Bot::Defense(defense_params(spec, Some(aggression))?),
+            },
+            "defense" => Bot::Defense(defense_params(spec, aggression(spec)?)?),
+            "monte-carlo" => {
+                let default = Budget::default();
+                let time = match spec.get("time_ms") {
+                    None | Some("none") => None,
+                    Some(_) => Some(Duration::from_millis(spec.param("time_ms", 0)?)),
+                };
+                Bot::MonteCarlo(Budget {
+                    playouts: spec.param("playouts", default.playouts)?,
+                    time,
+                })
+            }
+            "exec" => {
+                let command = spec.get("command").unwrap_or_default().trim();
+                if command.is_empty() {
+                    return Err("exec needs a command, e.g. exec:./mybot".to_owned());
+                }
+                Bot::Exec(ExecParams {
+                    command: command.to_owned(),
+                    timeout: timeout(spec)?,
+                })
+            }
+            "mjai" => {
+                let command = spec.get("command").unwrap_or_default().trim();
+                if command.is_empty() {
+                    return Err("mjai needs a command, e.g. mjai:./mybot".to_owned());
+                }
+                Bot::Mjai(MjaiParams {
+                    transport: Transport::Stdio {
+                        command: command.to_owned(),
+                    },
+                    timeout: timeout(spec)?,
+                })
+            }
+            "mjai-tcp" => Bot::Mjai(MjaiParams {
+                transport: Transport::Tcp {
+                    address: spec.param_or_default(&ADDRESS)?,
+                    wait: Duration::from_millis(spec.param_or_default(&WAIT_MS)?),
+                },
+                timeout: timeout(spec)?,
+            }),
+            name => unreachable!("Bot {} is not built", name),
+        };
+        Ok(bot)
+    }
+
+    /// Does the bot listen on a fixed address, so that only one hanchan can
+    /// play with it at a time
+    pub fn is_exclusive(&self) -> bool {
+        matches!(
+            self,
+            Bot::Mjai(MjaiParams {
+                transport: Transport::Tcp { .. },
+                ..
+            })
+        )
+    }
+
+    pub fn server(&self) -> AiServer {
+        match self.clone() {
+            Bot::Null => null_bot(),
+            Bot::DumbCaller => dump_caller_bot(),
+            Bot::Efficiency(params) => efficiency_bot(params),
+            Bot::Defense(params) => defense_bot(params),
+            Bot::MonteCarlo(budget) => monte_carlo_bot(budget),
+            Bot::Exec(params) => exec_bot(params),
+            Bot::Mjai(params) => mjai_bot(params),
+        }
+    }
+}
+
+fn timeout(spec: &BotSpec) -> Result<Duration, String> {
+    Ok(Duration::from_millis(spec.param_or_default(&TIMEOUT_MS)?))
+}
+
+/// Aggression of the bot, from 0 to 1, if given
+fn aggression(spec: &BotSpec) -> Result<Option<f64>, String> {
+    match spec.get(AGGRESSION.name) {
+        None | Some("none") => Ok(None),
+        Some(value) => {
+            let aggression: f64 = spec.param(AGGRESSION.name, 0.0)?;
+            if !(0.0..=1.0).contains(&aggression) {
+                return Err(format!(
+                    "Bad aggression '{}' for {}: expected 0 to 1",
+                    value, spec.name
+                ));
+            }
+            Ok(Some(aggression))
+        }
+    }
+}
+
+/// Parameters of the defense bot, whose defaults are moved by `aggression`
+fn defense_params(spec: &BotSpec, aggression: Option<f64>) -> Result<DefenseParams, String> {
+    let mut default = DefenseParams::default();
+    if let Some(aggression) = aggression {
+        default.push_shanten = (2.0 * aggression).floor() as isize;
+        default.push_value = (FOLD_VALUE * (1.0 - aggression) / 100.0).round() as usize * 100;
+    }
+    Ok(DefenseParams {
+        push_shanten: spec.param("push_shanten", default.push_shanten)?,
+        push_value: spec.param("push_value", default.push_value)?,
+        efficiency: efficiency_params(spec)?,
+    })
+}
+
+fn efficiency_params(spec: &BotSpec) -> Result<EfficiencyParams, String> {
+    let default = EfficiencyParams::default();
+    Ok(EfficiencyParams {
+        riichi: spec.param("riichi", default.riichi)?,
+        calls: spec.param("calls", default.calls)?,
+    })
+}
+
+/// Bot of the registry named `name`, with or without a `-bot` suffix
+pub fn find(name: &str) -> Option<&'static BotInfo> {
+    let name = name.strip_suffix("-bot").unwrap_or(name);
+    BOTS.iter().find(|info| info.name == name)
+}
+
+/// Name and parameters of a bot, as given on the command line. Parameters
+/// are only checked by [`Bot::from_spec`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BotSpec {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+}
+
+impl BotSpec {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Value of the parameter, or its default in the registry
+    fn param_or_default<T>(&self, info: &ParamInfo) -> Result<T, String>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let value = self.get(info.name).unwrap_or(info.default);
+        value
+            .parse()
+            .map_err(|e| format!("Bad {} '{}' for {}: {}", info.name, value, self.name, e))
+    }
+
+    fn param<T>(&self, key: &str, default: T) -> Result<T, String>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        match self.get(key) {
+            None => Ok(default),
+            Some(value) => value
+                .parse()
+                .map_err(|e| format!("Bad {} '{}' for {}: {}", key, value, self.name, e)),
+        }
+    }
+
+    /// Read a config file
+    pub fn from_file(path: &Path) -> Result<BotSpec, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        let config: BotConfig =
+            ron::from_str(&text).map_err(|e| format!("Bad config {}: {}", path.display(), e))?;
+        let params = config
+            .params
+            .into_iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    ron::Value::String(value) => value,
+                    ron::Value::Bool(value) => value.to_string(),
+                    ron::Value::Number(ron::Number::Integer(value)) => value.to_string(),
+                    ron::Value::Number(ron::Number::Float(value)) => value.get().to_string(),
+                    ron::Value::Option(None) | ron::Value::Unit => "none".to_owned(),
+                    other => {
+                        return Err(format!(
+                            "Bad value {:?} of {} in {}",
+                            other,
+                            key,
+                            path.display()
+                        ))
+                    }
+                };
+                Ok((key, value))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(BotSpec {
+            name: config.bot,
+            params,
+        })
+    }
+}
+
+/// Content of a bot config file
+#[derive(Debug, Deserialize)]
+struct BotConfig {
+    bot: String,
+    #[serde(default)]
+    params: BTreeMap<String, ron::Value>,
+}
+
+impl FromStr for BotSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = s.strip_prefix('@') {
+            return BotSpec::from_file(Path::new(path));
+        }
+        // The command may hold anything, even colons and commas, so other
+        // parameters come before it: `exec,timeout_ms=1000:./mybot`
+        for name in COMMAND_BOTS {
+            let Some(rest) = s.strip_prefix(name) else {
+                continue;
+            };
+            let params = rest.strip_prefix(',').unwrap_or(rest);
+            if let Some((params, command)) = params.split_once(':') {
+                if rest.starts_with([':', ',']) {
+                    let mut params = parse_params(params)?;
+                    params.insert(0, ("command".to_owned(), command.to_owned()));
+                    return Ok(BotSpec {
+                        name: name.to_owned(),
+                        params,
+                    });
+                }
+            }
+        }
+        let (name, params) = match s.split_once(':') {
+            Some((name, params)) => (name, params),
+            None => (s, ""),
+        };
+        if name.is_empty() {
+            return Err(format!("No bot name in '{}'", s));
+        }
+        let params = parse_params(params)?;
+        Ok(BotSpec {
+            name: name.to_owned(),
+            params,
+        })
+    }
+}
+
+/// Parameters `key=value,key=value`
+fn parse_params(params: &str) -> Result<Vec<(String, String)>, String> {
+    params
+        .split(',')
+        .filter(|param| !param.is_empty())
+        .map(|param| match param.split_once('=') {
+            Some((key, value)) => Ok((key.trim().to_owned(), value.trim().to_owned())),
+            None => Err(format!("Expected key=value, got '{}'", param)),
+        })
+        .collect()
+}
+
+/// Writes a spec which parses back to the same spec, with every parameter
+impl fmt::Display for BotSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if COMMAND_BOTS.contains(&self.name.as_str()) {
+            if let Some(command) = self.get("command") {
+                write!(f, "{}", self.name)?;
+                for (key, value) in self.params.iter().filter(|(key, _)| key != "command") {
+                    write!(f, ",{}={}", key, value)?;
+                }
+                return write!(f, ":{}", command);
+            }
+        }
+        write!(f, "{}", self.name)?;
+        for (i, (key, value)) in self.params.iter().enumerate() {
+            let separator = if i == 0 { ':' } else { ',' };
+            write!(f, "{}{}={}", separator, key, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::RiichiPolicy;
+
+    fn bot(s: &str) -> Result<Bot, String> {
+        Bot::from_spec(&s.parse()?)
+    }
+
+    #[test]
+    fn test_parse_spec() {
+        let spec: BotSpec = "defense:push_value=8000,riichi=never".parse().unwrap();
+        assert_eq!(spec.name, "defense");
+        assert_eq!(
+            spec.params,
+            vec![
+                ("push_value".to_owned(), "8000".to_owned()),
+                ("riichi".to_owned(), "never".to_owned())
+            ]
+        );
+        assert_eq!(spec.to_string(), "defense:push_value=8000,riichi=never");
+        assert!("efficiency:riichi".parse::<BotSpec>().is_err());
+        assert!(":riichi=never".parse::<BotSpec>().is_err());
+
+        assert_eq!(
+            bot("defense:push_value=8000,riichi=never"),
+            Ok(Bot::Defense(DefenseParams {
+                push_value: 8000,
+                efficiency: EfficiencyParams {
+                    riichi: RiichiPolicy::Never,
+                    calls: true,
+                },
+                ..Default::default()
+            }))
+        );
+        assert_eq!(
+            bot("defense:aggression=0.7"),
+            Ok(Bot::Defense(DefenseParams {
+                push_shanten: 1,
+                push_value: 2400,
+                ..Default::default()
+            }))
+        );
+        assert_eq!(
+            bot("defense:aggression=0,push_shanten=1"),
+            Ok(Bot::Defense(DefenseParams {
+                push_shanten: 1,
+                push_value: 8000,
+                ..Default::default()
+            }))
+        );
+        assert_eq!(
+            bot("efficiency:aggression=0.7,riichi=always"),
+            Ok(Bot::Defense(DefenseParams {
+                push_shanten: 1,
+                push_value: 2400,
+                efficiency: EfficiencyParams {
+                    riichi: RiichiPolicy::Always,
+                    calls: true,
+                },
+            }))
+        );
+        assert_eq!(
+            bot("efficiency:aggression=none"),
+            Ok(Bot::Efficiency(Default::default()))
+        );
+        assert_eq!(bot("dumb-caller-bot"), Ok(Bot::DumbCaller));
+        assert_eq!(
+            bot("exec:./mybot --level=3,fast"),
+            Ok(Bot::Exec(ExecParams {
+                command: "./mybot --level=3,fast".to_owned(),
+                ..Default::default()
+            }))
+        );
+        let spec: BotSpec = "exec:./mybot --level=3".parse().unwrap();
+        assert_eq!(spec.to_string(), "exec:./mybot --level=3");
+        assert_eq!(
+            bot("mjai:./mybot 127.0.0.1:11600"),
+            Ok(Bot::Mjai(MjaiParams {
+                transport: Transport::Stdio {
+                    command: "./mybot 127.0.0.1:11600".to_owned()
+                },
+                ..Default::default()
+            }))
+        );
+        assert_eq!(
+            bot("mjai-tcp:address=0.0.0.0:11601,wait_ms=1000"),
+            Ok(Bot::Mjai(MjaiParams {
+                transport: Transport::Tcp {
+                    address: "0.0.0.0:11601".to_owned(),
+                    wait: Duration::from_millis(1000),
+                },
+                ..Default::default()
+            }))
+        );
+        assert!(bot("mjai-tcp").unwrap().is_exclusive());
+        assert!(!bot("mjai:./mybot").unwrap().is_exclusive());
+        assert!(!bot("efficiency").unwrap().is_exclusive());
+        assert_eq!(
+            bot("monte-carlo:time_ms=500"),
+            Ok(Bot::MonteCarlo(Budget {
+                time: Some(Duration::from_millis(500)),
+                ..Default::default()
+            }))
+        );
+    }
+
+    #[test]
+    fn test_spec_round_trip() {
+        let specs = [
+            "efficiency",
+            "defense:push_value=8000,riichi=never",
+            "monte-carlo:playouts=8,time_ms=none",
+            "exec:./mybot --level=3,fast",
+            "exec,timeout_ms=1000:./mybot a:b",
+            "mjai,timeout_ms=200:./mybot 127.0.0.1:11600",
+            "mjai-tcp:address=0.0.0.0:11601,wait_ms=1000",
+        ];
+        for s in specs {
+            let spec: BotSpec = s.parse().unwrap();
+            assert_eq!(spec.to_string(), s);
+            assert_eq!(spec.to_string().parse(), Ok(spec));
+        }
+        assert_eq!(
+            bot("exec,timeout_ms=1000:./mybot a:b"),
+            Ok(Bot::Exec(ExecParams {
+                command: "./mybot a:b".to_owned(),
+                timeout: Duration::from_millis(1000),
+            }))
+        );
+        // Parameters of a config file, in any order
+        let spec = BotSpec {
+            name: "mjai".to_owned(),
+            params: vec![
+                ("timeout_ms".to_owned(), "200".to_owned()),
+                ("command".to_owned(), "./mybot".to_owned()),
+            ],
+        };
+        assert_eq!(spec.to_string(), "mjai,timeout_ms=200:./mybot");
+        let parsed: BotSpec = spec.to_string().parse().unwrap();
+        assert_eq!(Bot::from_spec(&parsed), Bot::from_spec(&spec));
+    }
+
+    #[test]
+    fn test_bad_spec() {
+        assert!(bot("genius").unwrap_err().contains("Unknown bot"));
+        assert!(bot("null:riichi=never")
+            .unwrap_err()
+            .contains("Unknown parameter"));
+        assert!(bot("efficiency:push_value=8000")
+            .unwrap_err()
+            .contains("aggression, riichi, calls"));
+        assert!(bot("efficiency:riichi=sometimes").is_err());
+        assert!(bot("defense:push_value=lots").is_err());
+        assert!(bot("defense:aggression=1.5").is_err());
+        assert!(bot("defense:aggression=high").is_err());
+        assert!(bot("exec:").is_err());
+        assert!(bot("mjai").is_err());
+    }
+
+    #[test]
+    fn test_defaults_match_registry() {
+        for info in BOTS {
+            let params = info
+                .params
+                .iter()
+                .map(|param| (param.name.to_owned(), param.default.to_owned()))
+                .collect();
+            let spec = BotSpec {
+                name: info.name.to_owned(),
+                params,
+            };
+            assert_eq!(bot(info.name), Bot::from_spec(&spec), "{}", info.name);
+        }
+    }
+
+    #[test]
+    fn test_config_file() {
+        let path = std::env::temp_dir().join(format!("mahjong-bot-{}.ron", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"(bot: "defense", params: {"push_value": 8000, "calls": false})"#,
+        )
+        .unwrap();
+        let spec: BotSpec = format!("@{}", path.display()).parse().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            Bot::from_spec(&spec),
+            Ok(Bot::Defense(DefenseParams {
+                push_value: 8000,
+                efficiency: EfficiencyParams {
+                    calls: false,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }))
+        );
+        assert!("@/nonexistent/bot.ron".parse::<BotSpec>().is_err());
+    }
+}
@@ -0,0 +1,674 @@
+//! Bots running in another process, talking JSON lines over stdin and
+//! stdout. The protocol is described in the README.
+//!
+//! A bot which crashes, times out or answers something malformed or
+//! illegal is stopped, and its seat plays on as [`null_bot`](super::ai::null_bot)
+//! would: no calls and tsumogiri.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use log::warn;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use super::ai::{channel, AiServer, Call, PossibleCall, TehaiIndex, TurnResult};
+use super::game::{Game, GameRequest, PossibleActions, Request, SuteHai, Te, ThrowableOnRiichi};
+use super::mpsz;
+use super::tiles::{Fon, Hai};
+
+/// Version of the protocol, checked when the bot starts
+pub const PROTOCOL_VERSION: u32 = 1;
+
+const WINDS: [Fon; 4] = [Fon::Ton, Fon::Nan, Fon::Shaa, Fon::Pee];
+
+/// How to run an external bot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecParams {
+    /// Program and its arguments, split on whitespace
+    pub command: String,
+    /// Longest wait for each answer, the handshake included
+    pub timeout: Duration,
+}
+
+impl Default for ExecParams {
+    fn default() -> Self {
+        ExecParams {
+            command: String::new(),
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// AI running `params.command` in a new process for each game
+pub fn exec_bot(params: ExecParams) -> AiServer {
+    let (server, client) = channel();
+    std::thread::spawn(move || {
+        let mut connection = match start(&params) {
+            Ok(connection) => Some(connection),
+            Err(e) => {
+                warn!("Bot '{}' did not start: {}", params.command, e);
+                None
+            }
+        };
+        let command = params.command.as_str();
+        loop {
+            let GameRequest {
+                game,
+                request,
+                player,
+            } = match client.rx.recv() {
+                Ok(request) => request,
+                // The game is over
+                Err(_) => return,
+            };
+            let view = View::new(&game, player);
+            match request {
+                Request::Call(possible_calls) => {
+                    let call = ask(&mut connection, command, |connection| {
+                        ask_call(connection, view, &possible_calls, &game, player)
+                    });
+                    client.tx_call.send(call.flatten()).expect("Sent!");
+                }
+                Request::DoTurn(possible_actions) => {
+                    let result = ask(&mut connection, command, |connection| {
+                        ask_turn(connection, view, &possible_actions, &game, player)
+                    });
+                    let result = result.unwrap_or_else(|| tsumogiri(&game, player));
+                    client.tx_turn.send(result).expect("Sent!");
+                }
+                Request::DeclareTenpai => {
+                    let tenpai = ask(&mut connection, command, |connection| {
+                        connection.send(&Message::Tenpai { view })?;
+                        Ok(connection.receive::<TenpaiAnswer>()?.tenpai)
+                    });
+                    client
+                        .tx_tenpai
+                        .send(tenpai.unwrap_or(true))
+                        .expect("Sent!");
+                }
+                Request::EndGame => {
+                    if let Some(connection) = connection.take() {
+                        connection.close(&Message::EndGame { view });
+                    }
+                    return;
+                }
+                request => {
+                    let message = match request {
+                        Request::RevealTenpai(players) => Message::RevealTenpai {
+                            view,
+                            players: players.into_iter().map(|p| p as usize).collect(),
+                        },
+                        Request::DisplayScore(_) => Message::EndKyoku { view },
+                        _ => Message::Update { view },
+                    };
+                    ask(&mut connection, command, |connection| {
+                        connection.send(&message)
+                    });
+                }
+            }
+        }
+    });
+    server
+}
+
+/// Run `f` on the connection if the bot is still running, stopping it on
+/// any error
+pub(crate) fn ask<T>(
+    connection: &mut Option<Connection>,
+    name: &str,
+    f: impl FnOnce(&mut Connection) -> Result<T, String>,
+) -> Option<T> {
+    let result = f(connection.as_mut()?);
+    result
+        .map_err(|e| {
+            warn!("Bot '{}' stopped: {}", name, e);
+            *connection = None;
+        })
+        .ok()
+}
+
+/// Discard of a stopped bot: the drawn tile, or the last tile after a call
+pub(crate) fn tsumogiri(game: &Game, player: Fon) -> TurnResult {
+    let te = game.player_te_(player);
+    let index = if te.get(TehaiIndex::Tsumohai).is_some() {
+        TehaiIndex::Tsumohai
+    } else {
+        TehaiIndex::Tehai(te.hai().len() - 1)
+    };
+    TurnResult::ThrowHai {
+        index,
+        riichi: false,
+    }
+}
+
+pub(crate) fn tile(hai: Hai) -> String {
+    mpsz::format_hai_list(&[hai])
+}
+
+/// What the player can see of the game, sent with every message. Arrays of
+/// 4 are indexed by seat wind, 0 being east (the oya).
+#[derive(Debug, Clone, Serialize)]
+struct View {
+    seat: usize,
+    bakaze: usize,
+    kyoku: usize,
+    honba: usize,
+    riichi_sticks: usize,
+    /// Tiles left to draw
+    wall: usize,
+    dora_indicators: Vec<String>,
+    scores: [isize; 4],
+    hand: Vec<String>,
+    tsumo: Option<String>,
+    melds: [Vec<String>; 4],
+    /// Discards not called
+    rivers: [Vec<String>; 4],
+    riichi: [bool; 4],
+    /// Index of the riichi tile in the river, unless it was called
+    riichi_discards: [Option<usize>; 4],
+    /// Tile which was just discarded
+    last_discard: Option<String>,
+}
+
+impl View {
+    fn new(game: &Game, player: Fon) -> Self {
+        let view = game.player_view(player);
+        let te = view.te();
+        View {
+            seat: player as usize,
+            bakaze: view.win_context().bakaze as usize,
+            kyoku: game.kyoku(),
+            honba: game.honba(),
+            riichi_sticks: game.riichi_bou_count(),
+            wall: game.remaining_tsumo_count(),
+            dora_indicators: game.dora_indicator().into_iter().map(tile).collect(),
+            scores: WINDS.map(|p| game.player_score(p)),
+            hand: te.hai().iter().map(|hai| tile(*hai)).collect(),
+            tsumo: te.get(TehaiIndex::Tsumohai).map(tile),
+            melds: WINDS.map(|p| view.fuuro(p).iter().map(mpsz::format_fuuro).collect()),
+            rivers: WINDS.map(|p| {
+                view.river(p)
+                    .iter()
+                    .map(|sutehai| tile(sutehai.hai()))
+                    .collect()
+            }),
+            riichi: WINDS.map(|p| view.is_riichi(p)),
+            riichi_discards: WINDS.map(|p| {
+                view.river(p)
+                    .iter()
+                    .position(|sutehai| matches!(sutehai, SuteHai::Riichi(_)))
+            }),
+            last_discard: view.last_discard().map(tile),
+        }
+    }
+}
+
+/// Message to the bot, one per line
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message {
+    Hello { protocol: u32 },
+    Update { view: View },
+    Call { view: View, calls: Vec<CallOption> },
+    Turn { view: View, actions: Actions },
+    Tenpai { view: View },
+    RevealTenpai { view: View, players: Vec<usize> },
+    EndKyoku { view: View },
+    EndGame { view: View },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum CallOption {
+    /// Pairs of tiles of the hand which can make a chi
+    Chi {
+        tiles: Vec<[String; 2]>,
+    },
+    Pon,
+    Kan,
+    Ron,
+}
+
+#[derive(Debug, Serialize)]
+struct Actions {
+    tsumo: bool,
+    kyusyukyuhai: bool,
+    /// Tiles which can be discarded with riichi
+    riichi: Vec<String>,
+    ankan: Vec<String>,
+    kakan: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelloAnswer {
+    protocol: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct CallAnswer {
+    #[serde(default)]
+    call: Option<String>,
+    #[serde(default)]
+    tiles: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TurnAnswer {
+    action: String,
+    #[serde(default)]
+    tile: Option<String>,
+    #[serde(default)]
+    riichi: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct TenpaiAnswer {
+    tenpai: bool,
+}
+
+/// Line-based connection to a bot, either a child process talking over
+/// stdin and stdout or a TCP client. Lines are read on another thread.
+pub(crate) struct Connection {
+    child: Option<Child>,
+    stream: Option<TcpStream>,
+    writer: Option<Box<dyn Write + Send>>,
+    lines: Receiver<String>,
+    timeout: Duration,
+}
+
+impl Connection {
+    /// Start `command`, split on whitespace
+    pub(crate) fn spawn(command: &str, timeout: Duration) -> Result<Connection, String> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or("No command")?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| e.to_string())?;
+        let stdin = child.stdin.take().expect("Piped stdin");
+        let stdout = child.stdout.take().expect("Piped stdout");
+        Ok(Connection {
+            child: Some(child),
+            stream: None,
+            writer: Some(Box::new(stdin)),
+            lines: read_lines(stdout),
+            timeout,
+        })
+    }
+
+    pub(crate) fn tcp(stream: TcpStream, timeout: Duration) -> Result<Connection, String> {
+        // Lines are short and answered one by one
+        stream.set_nodelay(true).map_err(|e| e.to_string())?;
+        let reader = stream.try_clone().map_err(|e| e.to_string())?;
+        let writer = stream.try_clone().map_err(|e| e.to_string())?;
+        Ok(Connection {
+            child: None,
+            stream: Some(stream),
+            writer: Some(Box::new(writer)),
+            lines: read_lines(reader),
+            timeout,
+        })
+    }
+
+    pub(crate) fn send<T: Serialize>(&mut self, message: &T) -> Result<(), String> {
+        let line = serde_json::to_string(message).expect("Serializable message");
+        let writer = self.writer.as_mut().expect("Open connection");
+        writeln!(writer, "{}", line)
+            .and_then(|_| writer.flush())
+            .map_err(|e| format!("Cannot write: {}", e))
+    }
+
+    pub(crate) fn receive<T: DeserializeOwned>(&mut self) -> Result<T, String> {
+        let line = match self.lines.recv_timeout(self.timeout) {
+            Ok(line) => line,
+            Err(RecvTimeoutError::Timeout) => {
+                return Err(format!("No answer in {} ms", self.timeout.as_millis()))
+            }
+            Err(RecvTimeoutError::Disconnected) => match self.child.as_mut() {
+                Some(child) => {
+                    let status = child.wait().map_err(|e| e.to_string())?;
+                    return Err(format!("Exited with {}", status));
+                }
+                None => return Err("Disconnected".to_owned()),
+            },
+        };
+        serde_json::from_str(&line).map_err(|e| format!("Malformed answer '{}': {}", line, e))
+    }
+
+    /// Send a last message, then let a child process exit on its own until
+    /// the timeout
+    pub(crate) fn close<T: Serialize>(mut self, message: &T) {
+        if self.send(message).is_err() {
+            return;
+        }
+        self.writer = None;
+        if let Some(stream) = &self.stream {
+            let _ = stream.shutdown(Shutdown::Write);
+        }
+        let start = Instant::now();
+        while let Some(child) = self.child.as_mut() {
+            if start.elapsed() >= self.timeout {
+                return;
+            }
+            if let Ok(Some(_)) = child.try_wait() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        if let Some(stream) = &self.stream {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        if let Some(child) = self.child.as_mut() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Non-empty lines of `reader`, until it is closed
+fn read_lines(reader: impl Read + Send + 'static) -> Receiver<String> {
+    let (tx, lines) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(reader).lines() {
+            match line {
+                Ok(line) if line.trim().is_empty() => {}
+                Ok(line) => {
+                    if tx.send(line).is_err() {
+                        return;
+                    }
+                }
+                Err(_) => return,
+            }
+        }
+    });
+    lines
+}
+
+/// Start the bot and check its protocol version
+fn start(params: &ExecParams) -> Result<Connection, String> {
+    let mut connection = Connection::spawn(&params.command, params.timeout)?;
+    connection.send(&Message::Hello {
+        protocol: PROTOCOL_VERSION,
+    })?;
+    let hello: HelloAnswer = connection.receive()?;
+    if hello.protocol != PROTOCOL_VERSION {
+        return Err(format!(
+            "Speaks protocol {}, expected {}",
+            hello.protocol, PROTOCOL_VERSION
+        ));
+    }
+    Ok(connection)
+}
+
+fn ask_call(
+    connection: &mut Connection,
+    view: View,
+    possible_calls: &[PossibleCall],
+    game: &Game,
+    player: Fon,
+) -> Result<Option<Call>, String> {
+    let te = game.player_te_(player);
+    let calls = possible_calls
+        .iter()
+        .map(|call| match call {
+            PossibleCall::Chi { indices } => CallOption::Chi {
+                tiles: indices
+                    .iter()
+                    .map(|index| index.map(|i| tile(te.hai()[i])))
+                    .collect(),
+            },
+            PossibleCall::Pon => CallOption::Pon,
+            PossibleCall::Kan => CallOption::Kan,
+            PossibleCall::Ron => CallOption::Ron,
+        })
+        .collect();
+    connection.send(&Message::Call { view, calls })?;
+    let answer: CallAnswer = connection.receive()?;
+    call_result(possible_calls, te, answer.call.as_deref(), &answer.tiles)
+}
+
+/// Check a call answered by a bot: `pon`, `kan`, `ron` or `chi` with the
+/// two tiles of the hand, in mpsz
+pub(crate) fn call_result(
+    possible_calls: &[PossibleCall],
+    te: &Te,
+    call: Option<&str>,
+    tiles: &[String],
+) -> Result<Option<Call>, String> {
+    let call = match call {
+        None => return Ok(None),
+        Some("pon") => PossibleCall::Pon,
+        Some("kan") => PossibleCall::Kan,
+        Some("ron") => PossibleCall::Ron,
+        Some("chi") => {
+            let pair = |index: &[usize; 2]| {
+                let mut pair = index.map(|i| tile(te.hai()[i]));
+                pair.sort();
+                pair
+            };
+            let mut tiles = tiles.to_vec();
+            tiles.sort();
+            let index = possible_calls
+                .iter()
+                .flat_map(|call| match call {
+                    PossibleCall::Chi { indices } => indices.clone(),
+                    _ => vec![],
+                })
+                .find(|index| pair(index)[..] == tiles[..])
+                .ok_or_else(|| format!("Cannot chi with {:?}", tiles))?;
+            return Ok(Some(Call::Chi { index }));
+        }
+        Some(other) => return Err(format!("Unknown call '{}'", other)),
+    };
+    if !possible_calls.contains(&call) {
+        return Err(format!("Cannot call {:?}", call));
+    }
+    Ok(Some(match call {
+        PossibleCall::Pon => Call::Pon,
+        PossibleCall::Kan => Call::Kan,
+        _ => Call::Ron,
+    }))
+}
+
+fn riichi_tile(te: &Te, throwable: &ThrowableOnRiichi) -> String {
+    match throwable {
+        ThrowableOnRiichi::Te(i) => tile(te.hai()[*i]),
+        ThrowableOnRiichi::Tsumohai => tile(te.get(TehaiIndex::Tsumohai).expect("Has tsumohai")),
+    }
+}
+
+fn ask_turn(
+    connection: &mut Connection,
+    view: View,
+    possible_actions: &PossibleActions,
+    game: &Game,
+    player: Fon,
+) -> Result<TurnResult, String> {
+    let te = game.player_te_(player);
+    let actions = Actions {
+        tsumo: possible_actions.can_tsumo,
+        kyusyukyuhai: possible_actions.can_kyusyukyuhai,
+        riichi: possible_actions
+            .can_riichi
+            .iter()
+            .map(|throwable| riichi_tile(te, throwable))
+            .collect(),
+        ankan: possible_actions
+            .can_ankan
+            .iter()
+            .map(|hai| tile(*hai))
+            .collect(),
+        kakan: possible_actions
+            .can_shominkan
+            .iter()
+            .map(|hai| tile(*hai))
+            .collect(),
+    };
+    connection.send(&Message::Turn { view, actions })?;
+    let answer: TurnAnswer = connection.receive()?;
+
+    let hai = match &answer.tile {
+        Some(s) => Some(
+            s.parse::<Hai>()
+                .map_err(|e| format!("Bad tile '{}': {}", s, e))?,
+        ),
+        None => None,
+    };
+    turn_result(
+        possible_actions,
+        game,
+        player,
+        &answer.action,
+        hai,
+        answer.riichi,
+    )
+}
+
+/// Check a turn answered by a bot: `tsumo`, `kyusyukyuhai`, `ankan` or
+/// `kakan` of a tile, or `discard` of a tile, the drawn one if `None`
+pub(crate) fn turn_result(
+    possible_actions: &PossibleActions,
+    game: &Game,
+    player: Fon,
+    action: &str,
+    hai: Option<Hai>,
+    riichi: bool,
+) -> Result<TurnResult, String> {
+    let te = game.player_te_(player);
+    let kan_index = |can: &[Hai]| {
+        let hai = hai.ok_or("No tile")?;
+        if !can.contains(&hai) {
+            return Err(format!("Cannot {} {}", action, tile(hai)));
+        }
+        te.index(hai).ok_or_else(|| "Not in hand".to_owned())
+    };
+    match action {
+        "tsumo" if possible_actions.can_tsumo => Ok(TurnResult::Tsumo),
+        "kyusyukyuhai" if possible_actions.can_kyusyukyuhai => Ok(TurnResult::Kyusyukyuhai),
+        "ankan" => Ok(TurnResult::Ankan {
+            index: kan_index(&possible_actions.can_ankan)?,
+        }),
+        "kakan" => Ok(TurnResult::Kakan {
+            index: kan_index(&possible_actions.can_shominkan)?,
+        }),
+        "discard" => {
+            let mut index = match hai {
+                None => TehaiIndex::Tsumohai,
+                Some(hai) => {
+                    exact_index(te, hai).ok_or_else(|| format!("No {} in hand", tile(hai)))?
+                }
+            };
+            let discarded = te.get(index).ok_or("No drawn tile to discard")?;
+            if game.player_is_riichi(player) && index != TehaiIndex::Tsumohai {
+                return Err("Only the drawn tile can be discarded in riichi".to_owned());
+            }
+            if riichi {
+                let throwable = possible_actions
+                    .can_riichi
+                    .iter()
+                    .find(|throwable| riichi_tile(te, throwable) == tile(discarded))
+                    .ok_or_else(|| format!("Cannot riichi on {}", tile(discarded)))?;
+                index = match throwable {
+                    ThrowableOnRiichi::Te(i) => TehaiIndex::Tehai(*i),
+                    ThrowableOnRiichi::Tsumohai => TehaiIndex::Tsumohai,
+                };
+            }
+            Ok(TurnResult::ThrowHai { index, riichi })
+        }
+        other => Err(format!("Cannot {}", other)),
+    }
+}
+
+/// Index of `hai` in the te, telling red fives apart and preferring the
+/// drawn tile
+fn exact_index(te: &Te, hai: Hai) -> Option<TehaiIndex> {
+    let same = |other: Hai| other == hai && other.is_aka() == hai.is_aka();
+    if te.get(TehaiIndex::Tsumohai).map_or(false, same) {
+        return Some(TehaiIndex::Tsumohai);
+    }
+    te.hai()
+        .iter()
+        .position(|other| same(*other))
+        .map(TehaiIndex::Tehai)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::ai::null_bot;
+    use crate::simulation::play_hanchan;
+
+    /// Stand-in bot playing like `null_bot`, written in shell. Its arguments
+    /// are run after the handshake.
+    const STAND_IN: &str = r#"read hello
+echo '{"protocol":1}'
+eval "$*"
+while read line; do
+    case "$line" in
+        *'"type":"call"'*) echo '{"call":null}' ;;
+        *'"type":"turn"'*) echo '{"action":"discard"}' ;;
+        *'"type":"tenpai"'*) echo '{"tenpai":true}' ;;
+    esac
+done
+"#;
+
+    /// Play a hanchan with the stand-in bot as player 1, which must play
+    /// like a null bot whatever happens to it
+    fn play_with_stand_in(name: &str, after_hello: &str) {
+        let path = std::env::temp_dir().join(format!(
+            "mahjong-stand-in-{}-{}.sh",
+            name,
+            std::process::id()
+        ));
+        std::fs::write(&path, STAND_IN).unwrap();
+        let params = ExecParams {
+            command: format!("sh {} {}", path.display(), after_hello),
+            timeout: Duration::from_millis(500),
+        };
+        let record = play_hanchan(47, [exec_bot(params), null_bot(), null_bot(), null_bot()]);
+        std::fs::remove_file(&path).unwrap();
+        let expected = play_hanchan(47, [null_bot(), null_bot(), null_bot(), null_bot()]);
+        assert_eq!(record, expected);
+    }
+
+    #[test]
+    fn test_exec_bot_plays() {
+        play_with_stand_in("plays", "true");
+    }
+
+    #[test]
+    fn test_exec_bot_crashes() {
+        play_with_stand_in("crashes", "exit");
+    }
+
+    #[test]
+    fn test_exec_bot_times_out() {
+        play_with_stand_in("times-out", "sleep 5");
+    }
+
+    #[test]
+    fn test_exec_bot_malformed() {
+        play_with_stand_in("malformed", "read line;echo oops");
+    }
+
+    #[test]
+    fn test_exec_bot_missing() {
+        let params = ExecParams {
+            command: "/nonexistent/bot".to_owned(),
+            ..Default::default()
+        };
+        let record = play_hanchan(47, [exec_bot(params), null_bot(), null_bot(), null_bot()]);
+        assert!(!record.kyoku.is_empty());
+    }
+}
@@ -1,4 +1,5 @@
 use std::fmt;
+use std::str::FromStr;
 
 use log::{debug, info, trace};
 use rand::distributions::{Distribution, Standard};
@@ -6,12 +7,16 @@ use rand::seq::SliceRandom;
 use rand::Rng;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::points::{points_ron_ko, points_ron_oya, points_tsumo_ko, points_tsumo_oya};
+use crate::points::{points_tsumo_ko, points_tsumo_oya};
 
-use super::ai::{AiServer, Call, PossibleCall, TehaiIndex, TurnResult};
+use super::ai::{AiServer, Bots, Call, PossibleCall, TehaiIndex, TurnResult};
 use super::list::OrderedList;
-use super::tiles::{make_all_tiles, Fon, Hai, SuuHai, Values};
-use super::yaku::{AgariTe, WinningMethod, Yaku};
+use super::locale::{Locale, Localize};
+use super::mpsz;
+use super::rules::{ChomboRule, Penalty, Rules};
+use super::shanten;
+use super::tiles::{make_all_tiles, Fon, Hai, ParseHaiError, TileCounts, TileKind};
+use super::yaku::{AgariTe, ScoreBreakdown, WinContext, WinningMethod, Yaku, YakuValue};
 
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub enum Dice {
@@ -60,12 +65,20 @@ pub struct Game {
     dice: [Dice; 2],
     /// Score of each player, indexed by Ton/Nan/Sha/Pee
     score: [Score; 4],
+    /// Riichi declared on the last thrown tile (立直宣言牌).
+    /// The declaration is only established once this tile passes without
+    /// being ronned.
+    riichi_sengen: Option<(Fon, Riichi)>,
+    rules: Rules,
 }
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 struct Score {
     riichi_bou: usize,
     score: isize,
+    /// Bonus chips (祝儀) won or lost since the start of the hanchan
+    #[serde(default)]
+    chips: isize,
 }
 
 impl Default for Game {
@@ -94,7 +107,10 @@ impl Default for Game {
             score: [Score {
                 riichi_bou: 0,
                 score: 25000,
+                chips: 0,
             }; 4],
+            riichi_sengen: None,
+            rules: Default::default(),
         }
     }
 }
@@ -116,14 +132,22 @@ impl fmt::Debug for Game {
             .field("yama", &yama)
             .field("hoo", &self.hoo)
             .field("dice", &self.dice)
+            .field("riichi_sengen", &self.riichi_sengen)
+            .field("rules", &self.rules)
             .finish()
     }
 }
 
 impl fmt::Display for Game {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let title = self.title_repr();
-        let score = self.score_repr();
+        self.fmt_localized(f, Locale::Japanese)
+    }
+}
+
+impl Localize for Game {
+    fn fmt_localized(&self, f: &mut fmt::Formatter<'_>, locale: Locale) -> fmt::Result {
+        let title = self.title_repr(locale);
+        let score = self.score_repr(locale);
         let board = self.to_string_repr();
         write!(f, "{}\n{}\n\n{}", title, score, board)
     }
@@ -141,6 +165,10 @@ struct GameSerde {
     hoo: [Hoo; 4],
     dice: [Dice; 2],
     score: [Score; 4],
+    #[serde(default)]
+    riichi_sengen: Option<(Fon, Riichi)>,
+    #[serde(default)]
+    rules: Rules,
 }
 
 impl Serialize for Game {
@@ -156,6 +184,8 @@ impl Serialize for Game {
             hoo: self.hoo.clone(),
             dice: self.dice,
             score: self.score,
+            riichi_sengen: self.riichi_sengen.clone(),
+            rules: self.rules.clone(),
         };
         game.serialize(serializer)
     }
@@ -183,6 +213,8 @@ impl<'de> Deserialize<'de> for Game {
             hoo: game.hoo,
             dice: game.dice,
             score: game.score,
+            riichi_sengen: game.riichi_sengen,
+            rules: game.rules,
         })
     }
 }
@@ -209,6 +241,10 @@ pub enum Request {
     Refresh,
     Call(Vec<PossibleCall>),
     DoTurn(PossibleActions),
+    /// Ask a tempai player whether they declare tempai at ryukyoku (聴牌宣言).
+    DeclareTenpai,
+    /// Players who declared tempai at ryukyoku show their te.
+    RevealTenpai(Vec<Fon>),
     DisplayScore(KyokuResult),
     EndGame,
 }
@@ -228,13 +264,48 @@ pub struct PossibleActions {
 #[derive(Debug, Clone)]
 pub enum KyokuResult {
     Agari {
-        /// List of winners with their respective Yaku.
-        winners: Vec<(Fon, Vec<Yaku>)>,
+        /// List of winners with the breakdown of their score.
+        winners: Vec<(Fon, ScoreBreakdown)>,
         oya_agari: bool,
+        /// Bonus chips won or lost by each player in this kyoku
+        chips: [isize; 4],
     },
     Ryukyoku {
         oya_tempai: bool,
     },
+    /// Kyoku ended by a chombo (錯和)
+    Chombo {
+        /// List of players who committed a chombo
+        offenders: Vec<(Fon, ChomboReason)>,
+        /// Penalty paid by each offender
+        penalty: Penalty,
+        /// The kyoku is played again with the same oya and honba
+        redo: bool,
+        /// Only used if the kyoku is not played again
+        oya_tempai: bool,
+    },
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ChomboReason {
+    /// Riichi player found not tempai at ryukyoku (ノーテン立直)
+    NotenRiichi,
+    /// Called tsumo without a winning te (誤ツモ)
+    InvalidTsumo,
+    /// Called ron without a winning te (誤ロン)
+    InvalidRon,
+}
+
+impl ChomboReason {
+    pub fn name(self, locale: Locale) -> &'static str {
+        match self {
+            ChomboReason::NotenRiichi => {
+                locale.pick("ノーテン立直", "Noten riichi", "Riichi without tenpai")
+            }
+            ChomboReason::InvalidTsumo => locale.pick("誤ツモ", "Go-tsumo", "Invalid tsumo"),
+            ChomboReason::InvalidRon => locale.pick("誤ロン", "Go-ron", "Invalid ron"),
+        }
+    }
 }
 
 impl Game {
@@ -250,16 +321,25 @@ impl Game {
     }
 
     pub fn next_tsumohai_index(&self) -> Option<usize> {
-        let break_point = self.wall_break_index();
-        let tsumo_cnt_max = 16 * 4 + 1 - self.kan_count();
-        if self.tsumo_cnt > tsumo_cnt_max {
+        if self.remaining_tsumo_count() == 0 {
             None
         } else {
+            let break_point = self.wall_break_index();
             let tsumohai_i = (break_point + 4 * 13 + self.tsumo_cnt) % 136;
             Some(tsumohai_i)
         }
     }
 
+    /// Number of tiles that can still be drawn from the live wall (残り枚数).
+    ///
+    /// 136 tiles, minus 14 in the dead wall (王牌) and 13 dealt to each
+    /// player leaves 70 tiles. Each kan moves one tile from the live wall to
+    /// the dead wall.
+    pub fn remaining_tsumo_count(&self) -> usize {
+        let live_wall = 136 - 14 - 4 * 13 - self.kan_count();
+        live_wall.saturating_sub(self.tsumo_cnt)
+    }
+
     /// Return total number of kans
     fn kan_count(&self) -> usize {
         self.players.iter().fold(0, |acc, p| acc + p.te.kan_count())
@@ -315,11 +395,34 @@ impl Game {
         self.wind > Fon::Nan || self.score.iter().any(|s| s.score < 0)
     }
 
-    pub fn play_hanchan<R: Rng>(&mut self, mut channels: [AiServer; 4], rng: &mut R) {
+    pub fn play_hanchan<R: Rng>(&mut self, channels: [AiServer; 4], rng: &mut R) {
+        self.play_hanchan_with(channels, rng, |_, _| {})
+    }
+
+    /// Play a hanchan, calling `on_kyoku` at the end of each kyoku with the
+    /// game as it ended and its result
+    pub fn play_hanchan_with<R, F>(
+        &mut self,
+        mut channels: [AiServer; 4],
+        rng: &mut R,
+        mut on_kyoku: F,
+    ) where
+        R: Rng,
+        F: FnMut(&Game, &KyokuResult),
+    {
         loop {
-            let result = self.play(&channels);
+            let result = self.play(&mut channels);
+            on_kyoku(self, &result);
             match result {
-                KyokuResult::Ryukyoku { oya_tempai } => {
+                KyokuResult::Chombo { redo: true, .. } => {
+                    // Play the same kyoku again
+                }
+                KyokuResult::Ryukyoku { oya_tempai }
+                | KyokuResult::Chombo {
+                    redo: false,
+                    oya_tempai,
+                    ..
+                } => {
                     self.honba += 1;
                     if !oya_tempai {
                         // Move players if oya is not tempai
@@ -359,11 +462,27 @@ impl Game {
     }
 
     /// Play a kyoku
-    pub fn play(&mut self, channels: &[AiServer; 4]) -> KyokuResult {
+    pub fn play(&mut self, bots: &mut dyn Bots) -> KyokuResult {
         self.deal();
+        self.play_on(bots)
+    }
+
+    /// Play the rest of the kyoku from a state sent to a player with
+    /// `request`, e.g. on a clone of the game given to a bot.
+    ///
+    /// A drawn tile is never considered as a rinshan tile (嶺上開花).
+    pub fn play_from(&mut self, request: &Request, bots: &mut dyn Bots) -> KyokuResult {
+        if let Request::DoTurn(_) = request {
+            if let Some(result) = self.do_turn(bots, false) {
+                return result;
+            }
+        }
+        self.play_on(bots)
+    }
 
+    fn play_on(&mut self, bots: &mut dyn Bots) -> KyokuResult {
         loop {
-            if let Some(result) = self.next_turn(channels) {
+            if let Some(result) = self.next_turn(bots) {
                 // End a kyoku
                 return result;
             }
@@ -385,6 +504,91 @@ impl Game {
         self.yama = new_game.yama;
         self.hoo = new_game.hoo;
         self.dice = new_game.dice;
+        self.riichi_sengen = new_game.riichi_sengen;
+    }
+
+    /// Copy of the game where everything `player` cannot see is dealt again
+    /// at random: the hidden tiles of the other players and the wall, dora
+    /// indicators aside.
+    ///
+    /// Players in riichi get a te which is tenpai and not furiten, if one
+    /// can be found among the hidden tiles.
+    pub fn determinize<R: Rng>(&self, player: Fon, rng: &mut R) -> Game {
+        let mut game = self.clone();
+        let break_point = game.wall_break_index();
+        let dora_indices: Vec<usize> = (0..=game.kan_count())
+            .map(|i| (break_point + 136 - (2 * i + 5)) % 136)
+            .collect();
+
+        let mut wall_indices = vec![];
+        let mut pool = vec![];
+        for (i, slot) in game.yama.iter_mut().enumerate() {
+            if dora_indices.contains(&i) {
+                continue;
+            }
+            if let Some(hai) = slot.take() {
+                wall_indices.push(i);
+                pool.push(hai);
+            }
+        }
+        let others: Vec<Fon> = [Fon::Ton, Fon::Nan, Fon::Shaa, Fon::Pee]
+            .into_iter()
+            .filter(|p| *p != player)
+            .collect();
+        let mut sizes = [(0, false); 4];
+        for p in &others {
+            let te = &mut game.players[*p as usize].te;
+            sizes[*p as usize] = (te.hai.len(), te.tsumo.is_some());
+            pool.extend(std::mem::take(&mut te.hai).iter().copied());
+            pool.extend(te.tsumo.take());
+        }
+        pool.shuffle(rng);
+
+        // Riichi players first, while the pool still has every tile
+        let mut others_riichi_first = others;
+        others_riichi_first.sort_by_key(|p| game.players[*p as usize].riichi.is_none());
+        for p in others_riichi_first {
+            let (size, has_tsumo) = sizes[p as usize];
+            let melds = game.players[p as usize].te.fuuro.len();
+            let riichi = game.players[p as usize].riichi.is_some();
+            let tenpai = if riichi {
+                // Called tiles make furiten as well
+                let river: Vec<TileKind> = game.hoo[p as usize]
+                    .discards
+                    .iter()
+                    .map(|discard| discard.hai.into())
+                    .collect();
+                (0..20).find_map(|_| sample_tenpai(&pool, size, melds, &river, rng))
+            } else {
+                None
+            };
+
+            let te = &mut game.players[p as usize].te;
+            for kind in tenpai.unwrap_or_default() {
+                let i = pool
+                    .iter()
+                    .position(|hai| TileKind::from(*hai) == kind)
+                    .expect("Sampled from pool");
+                te.hai.insert(pool.swap_remove(i));
+            }
+            while te.hai.len() < size {
+                te.hai.insert(pool.pop().expect("Enough tiles"));
+            }
+            if has_tsumo {
+                te.tsumo = pool.pop();
+            }
+            if let Some(riichi) = game.players[p as usize].riichi.as_mut() {
+                riichi.machi = find_machi(game.players[p as usize].te.hai());
+            }
+        }
+        if let Some((p, riichi)) = game.riichi_sengen.as_mut() {
+            riichi.machi = find_machi(game.players[*p as usize].te.hai());
+        }
+
+        for (i, hai) in wall_indices.into_iter().zip(pool) {
+            game.yama[i] = Some(hai);
+        }
+        game
     }
 
     fn deal(&mut self) {
@@ -438,21 +642,16 @@ impl Game {
         self.players[p as usize].te.set_tsumohai(tsumohai);
     }
 
-    fn tx_refresh(&self, channels: &[AiServer; 4]) {
-        let mut player = Fon::Ton;
-        for channel in channels {
-            channel
-                .tx
-                .send(GameRequest::new(self, Request::Refresh, player))
-                .expect("Sent!");
-            player = player.next();
+    fn tx_refresh(&self, bots: &mut dyn Bots) {
+        for player in [Fon::Ton, Fon::Nan, Fon::Shaa, Fon::Pee] {
+            bots.notify(self, player, Request::Refresh);
         }
     }
 
     /// Plays a turn.
     /// Returns `Some(KyokuResult)` if this was the last turn, `None` otherwise.
-    fn next_turn(&mut self, channels: &[AiServer; 4]) -> Option<KyokuResult> {
-        self.tx_refresh(channels);
+    fn next_turn(&mut self, bots: &mut dyn Bots) -> Option<KyokuResult> {
+        self.tx_refresh(bots);
 
         // Listen for chi/pon/kan/ron
         let mut call1 = None;
@@ -462,18 +661,7 @@ impl Game {
         let allowed_calls1 = self.allowed_calls(self.turn);
         if !allowed_calls1.is_empty() {
             trace!("1. Player {} can {:?}!", self.turn as usize, allowed_calls1);
-            channels[self.turn as usize]
-                .tx
-                .send(GameRequest::new(
-                    self,
-                    Request::Call(allowed_calls1),
-                    self.turn,
-                ))
-                .expect("Sent!");
-            call1 = channels[self.turn as usize]
-                .rx_call
-                .recv()
-                .expect("Received!");
+            call1 = bots.call(self, self.turn, allowed_calls1);
         }
 
         let allowed_calls2 = self.allowed_calls(self.turn.next());
@@ -483,18 +671,7 @@ impl Game {
                 self.turn.next() as usize,
                 allowed_calls2
             );
-            channels[self.turn.next() as usize]
-                .tx
-                .send(GameRequest::new(
-                    self,
-                    Request::Call(allowed_calls2),
-                    self.turn.next(),
-                ))
-                .expect("Sent!");
-            call2 = channels[self.turn.next() as usize]
-                .rx_call
-                .recv()
-                .expect("Received!");
+            call2 = bots.call(self, self.turn.next(), allowed_calls2);
         }
 
         let allowed_calls3 = self.allowed_calls(self.turn.next().next());
@@ -504,26 +681,42 @@ impl Game {
                 self.turn.next().next() as usize,
                 allowed_calls3
             );
-            channels[self.turn.next().next() as usize]
-                .tx
-                .send(GameRequest::new(
-                    self,
-                    Request::Call(allowed_calls3),
-                    self.turn.next().next(),
-                ))
-                .expect("Sent!");
-            call3 = channels[self.turn.next().next() as usize]
-                .rx_call
-                .recv()
-                .expect("Received!");
+            call3 = bots.call(self, self.turn.next().next(), allowed_calls3);
+        }
+
+        let calls = [call1, call2, call3];
+        trace!("Calls: {:?}", &calls);
+
+        // Players who called ron without a winning te commit a chombo
+        let invalid_ron: Vec<_> = calls
+            .iter()
+            .enumerate()
+            .filter_map(|(i, call)| {
+                let player = self.turn.next_nth(i);
+                if call == &Some(Call::Ron) && !self.can_ron(player) {
+                    Some((player, ChomboReason::InvalidRon))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        if !invalid_ron.is_empty() {
+            let result = self.chombo(invalid_ron, false);
+            self.send_game_result(result.clone(), bots);
+            return Some(result);
+        }
+
+        // A riichi declaration tile that is not ronned establishes the riichi.
+        // It must be done before any chi/pon/kan so that the call cancels
+        // ippatsu.
+        if !calls.contains(&Some(Call::Ron)) {
+            self.establish_riichi();
         }
 
         // Check furiten by checking sutehai before they are moved by a
         // potential call.
         self.riichi_furiten_check_on_last_thrown_tile();
 
-        let calls = [call1, call2, call3];
-        trace!("Calls: {:?}", &calls);
         match calls {
             [None, None, None] => {
                 if self.is_tochu_ryuukyoku() {
@@ -531,11 +724,11 @@ impl Game {
                 }
 
                 if !self.draw() {
-                    let result = self.ryukyoku();
-                    self.send_game_result(result.clone(), channels);
+                    let result = self.ryukyoku(bots);
+                    self.send_game_result(result.clone(), bots);
                     return Some(result);
                 }
-                self.do_turn(channels, false)
+                self.do_turn(bots, false)
             }
             _ => {
                 // If any has Ron do single, double or triple ron score calculation.
@@ -554,7 +747,7 @@ impl Game {
                     .collect();
                 if !ron_calls.is_empty() {
                     let result = self.agari(ron_calls, WinningMethod::Ron, None, false);
-                    self.send_game_result(result.clone(), channels);
+                    self.send_game_result(result.clone(), bots);
                     Some(result)
                 } else if let Some(pon_kan_player_i) = calls
                     .iter()
@@ -568,14 +761,14 @@ impl Game {
                     match calls[pon_kan_player_i] {
                         Some(Call::Pon) => {
                             self.call_pon(caller);
-                            self.do_turn(channels, false)
+                            self.do_turn(bots, false)
                         }
-                        Some(Call::Kan) => self.call_kan(caller, channels),
+                        Some(Call::Kan) => self.call_kan(caller, bots),
                         _ => unreachable!("Expect kan or pon"),
                     }
                 } else if let Some(Call::Chi { index }) = call1 {
                     self.call_chi(self.turn, index);
-                    self.do_turn(channels, false)
+                    self.do_turn(bots, false)
                 } else {
                     unreachable!("Impossible state!");
                 }
@@ -583,51 +776,37 @@ impl Game {
         }
     }
 
-    fn send_game_result(&self, result: KyokuResult, channels: &[AiServer; 4]) {
-        let mut player = Fon::Ton;
-        for ch in channels {
-            ch.tx
-                .send(GameRequest::new(
-                    self,
-                    Request::DisplayScore(result.clone()),
-                    player,
-                ))
-                .expect("Sent!");
-            player = player.next();
+    fn send_game_result(&self, result: KyokuResult, bots: &mut dyn Bots) {
+        for player in [Fon::Ton, Fon::Nan, Fon::Shaa, Fon::Pee] {
+            bots.notify(self, player, Request::DisplayScore(result.clone()));
         }
     }
 
     /// Ask client for what to do then do it.
     ///
     /// Returns end game results if this turn ends the kyoku
-    fn do_turn(&mut self, channels: &[AiServer; 4], rinshankaihou: bool) -> Option<KyokuResult> {
-        channels[self.turn as usize]
-            .tx
-            .send(GameRequest::new(
-                self,
-                Request::DoTurn(PossibleActions {
-                    can_tsumo: self.can_tsumo(),
-                    can_riichi: self.can_riichi(),
-                    can_kyusyukyuhai: self.can_kyusyukyuhai(),
-                    can_shominkan: self.can_shominkan(),
-                    can_ankan: self.can_ankan(),
-                }),
-                self.turn,
-            ))
-            .expect("Sent!");
-        let result = channels[self.turn as usize]
-            .rx_turn
-            .recv()
-            .expect("Received!");
+    fn do_turn(&mut self, bots: &mut dyn Bots, rinshankaihou: bool) -> Option<KyokuResult> {
+        let possible_actions = PossibleActions {
+            can_tsumo: self.can_tsumo(rinshankaihou),
+            can_riichi: self.can_riichi(),
+            can_kyusyukyuhai: self.can_kyusyukyuhai(),
+            can_shominkan: self.can_shominkan(),
+            can_ankan: self.can_ankan(),
+        };
+        let result = bots.turn(self, self.turn, possible_actions);
         match result {
             TurnResult::Tsumo => {
-                let result = self.agari(vec![self.turn], WinningMethod::Tsumo, None, rinshankaihou);
-                self.send_game_result(result.clone(), channels);
+                let result = if self.can_tsumo(rinshankaihou) {
+                    self.agari(vec![self.turn], WinningMethod::Tsumo, None, rinshankaihou)
+                } else {
+                    self.chombo(vec![(self.turn, ChomboReason::InvalidTsumo)], false)
+                };
+                self.send_game_result(result.clone(), bots);
                 Some(result)
             }
-            TurnResult::Kyusyukyuhai => Some(self.ryukyoku()),
-            TurnResult::Ankan { index } => self.announce_ankan(index, channels),
-            TurnResult::Kakan { index } => self.announce_kakan(index, channels),
+            TurnResult::Kyusyukyuhai => Some(self.tochu_ryuukyoku()),
+            TurnResult::Ankan { index } => self.announce_ankan(index, bots),
+            TurnResult::Kakan { index } => self.announce_kakan(index, bots),
             TurnResult::ThrowHai { index, riichi } => {
                 self.throw_tile(self.turn, index, riichi);
                 self.change_turn(self.turn.next());
@@ -680,22 +859,71 @@ impl Game {
         }
     }
 
-    fn ryukyoku(&mut self) -> KyokuResult {
+    /// Ask each tempai player, starting from oya, whether they declare
+    /// tempai. Riichi players always declare tempai.
+    fn declare_tempai(&self, bots: &mut dyn Bots) -> [bool; 4] {
+        let mut tempai = [false; 4];
+        for p in [Fon::Ton, Fon::Nan, Fon::Shaa, Fon::Pee] {
+            let player = &self.players[p as usize];
+            if !is_tempai(player.te.hai()) {
+                continue;
+            }
+            tempai[p as usize] = if player.riichi.is_some() {
+                true
+            } else {
+                bots.declare_tenpai(self, p)
+            };
+            debug!(
+                "Player {} declared {}",
+                p as usize,
+                if tempai[p as usize] {
+                    "tempai"
+                } else {
+                    "noten"
+                }
+            );
+        }
+        tempai
+    }
+
+    fn ryukyoku(&mut self, bots: &mut dyn Bots) -> KyokuResult {
         // Check Nagashimangan
         if let Some(players) = self.is_nagashi_mangan() {
             return self.agari(players, WinningMethod::Nagashimangan, None, false);
         }
 
         // Check tempai
-        let mut tempai = [false; 4];
-        let mut oya_tempai = false;
-        for (i, p) in self.players.iter().enumerate() {
-            tempai[i] = is_tempai(p.te.hai());
-            if p.wind == Fon::Ton && tempai[i] {
-                oya_tempai = true;
+        let tempai = self.declare_tempai(bots);
+        let oya_tempai = tempai[Fon::Ton as usize];
+
+        // Show te of tempai players
+        let revealed: Vec<_> = [Fon::Ton, Fon::Nan, Fon::Shaa, Fon::Pee]
+            .into_iter()
+            .filter(|p| tempai[*p as usize])
+            .collect();
+        for player in [Fon::Ton, Fon::Nan, Fon::Shaa, Fon::Pee] {
+            bots.notify(self, player, Request::RevealTenpai(revealed.clone()));
+        }
+
+        // Riichi players who are not tempai committed a chombo
+        let noten_riichi: Vec<_> = [Fon::Ton, Fon::Nan, Fon::Shaa, Fon::Pee]
+            .into_iter()
+            .filter(|p| self.players[*p as usize].riichi.is_some() && !tempai[*p as usize])
+            .map(|p| (p, ChomboReason::NotenRiichi))
+            .collect();
+        if !noten_riichi.is_empty() {
+            if !self.rules.chombo.redo {
+                self.noten_bappu(tempai);
             }
+            return self.chombo(noten_riichi, oya_tempai);
         }
-        // Move points from non-tempai players to tempai players
+
+        self.noten_bappu(tempai);
+        KyokuResult::Ryukyoku { oya_tempai }
+    }
+
+    /// Move points from non-tempai players to tempai players (ノーテン罰符)
+    fn noten_bappu(&mut self, tempai: [bool; 4]) {
         let tempai_count = tempai.into_iter().filter(|&t| t).count();
         match tempai_count {
             0 | 4 => { /* Do nothing */ }
@@ -722,8 +950,66 @@ impl Game {
             }
             _ => unreachable!("tempai_count cannot be any other value"),
         }
+    }
 
-        KyokuResult::Ryukyoku { oya_tempai }
+    /// Ends a kyoku with a chombo. Each offender pays the penalty defined in
+    /// the rules.
+    ///
+    /// `oya_tempai` is only used if the kyoku is not played again.
+    fn chombo(&mut self, offenders: Vec<(Fon, ChomboReason)>, oya_tempai: bool) -> KyokuResult {
+        let ChomboRule { penalty, redo } = self.rules.chombo;
+        for (p, reason) in &offenders {
+            info!("Player {} committed chombo: {:?}", *p as usize, reason);
+            self.pay_penalty(*p, penalty);
+        }
+        // A pending riichi declaration is void
+        self.riichi_sengen = None;
+        if redo {
+            // The kyoku is void: riichi sticks put down in it go back to
+            // their owners, the ones left from previous kyoku stay
+            for (player, score) in self.players.iter().zip(self.score.iter_mut()) {
+                if player.riichi.is_some() && score.riichi_bou > 0 {
+                    score.riichi_bou -= 1;
+                    score.score += 1000;
+                }
+            }
+        }
+
+        KyokuResult::Chombo {
+            offenders,
+            penalty,
+            redo,
+            oya_tempai,
+        }
+    }
+
+    fn pay_penalty(&mut self, offender: Fon, penalty: Penalty) {
+        match penalty {
+            Penalty::Mangan => {
+                // Pay as if all other players won a mangan by tsumo
+                let mangan = YakuValue::Han(5);
+                for p in [Fon::Ton, Fon::Nan, Fon::Shaa, Fon::Pee] {
+                    if p == offender {
+                        continue;
+                    }
+                    let points = if offender == Fon::Ton {
+                        points_tsumo_oya(mangan, 0, &self.rules)
+                    } else {
+                        let (oya_points, ko_points) = points_tsumo_ko(mangan, 0, &self.rules);
+                        if p == Fon::Ton {
+                            oya_points
+                        } else {
+                            ko_points
+                        }
+                    };
+                    self.score[p as usize].score += points;
+                    self.score[offender as usize].score -= points;
+                }
+            }
+            Penalty::Fixed(points) => {
+                self.score[offender as usize].score -= points;
+            }
+        }
     }
 
     fn is_tochu_ryuukyoku(&self) -> bool {
@@ -759,7 +1045,7 @@ impl Game {
 
     /// 途中流局
     ///
-    /// スーカン流れ、四風連打などの場合。点数のやり取りはありません。
+    /// スーカン流れ、四風連打、九種九牌などの場合。点数のやり取りはありません。
     fn tochu_ryuukyoku(&self) -> KyokuResult {
         KyokuResult::Ryukyoku { oya_tempai: false }
     }
@@ -782,26 +1068,41 @@ impl Game {
         // Do not allow rinshankaihou and chankan flags to be set at the same time
         assert!(!(rinshankaihou && chankan.is_some()));
 
+        // If the riichi declaration tile is ronned, the riichi is void and
+        // no riichi bou is put on the table.
+        if let Some((p, _)) = self.riichi_sengen.take() {
+            debug!("Riichi of player {} is void", p as usize);
+        }
+
         // Give all riichi bou on the boards to the winner (上家 only if several
         // winners)
         let kamicha = players[0];
-        let mut riichi_bou_count = 0;
+        let riichi_bou_count = self.riichi_bou_count();
         for score in self.score.iter_mut() {
-            riichi_bou_count += score.riichi_bou;
             score.riichi_bou = 0;
         }
-        self.score[kamicha as usize].score += riichi_bou_count as isize * 1000;
 
-        // Move points from winner(s) to loser(s)
         let loser = self.turn.prev();
         let mut winners = vec![];
         let mut oya_agari = false;
+        let mut chips = [0; 4];
         for winner in players {
             let p = &self.players[winner as usize];
-            let points = if winning_method == WinningMethod::Nagashimangan {
-                let yaku = Yaku::Nagashimangan;
-                let han = yaku.han(p.te.fuuro().is_empty());
-                (vec![yaku], han, 0)
+            let riichi_bou = if winner == kamicha {
+                riichi_bou_count
+            } else {
+                0
+            };
+            let breakdown = if winning_method == WinningMethod::Nagashimangan {
+                ScoreBreakdown::nagashimangan(
+                    &WinContext {
+                        jikaze: winner,
+                        honba: self.honba,
+                        riichi_bou,
+                        ..Default::default()
+                    },
+                    &self.rules,
+                )
             } else {
                 let hupai = if let Some(hai) = chankan {
                     hai
@@ -821,63 +1122,58 @@ impl Game {
                     winner as usize,
                     hupai.to_char()
                 );
-                AgariTe::from_te(&p.te, self, hupai, winning_method, winner)
+                let agari_te = AgariTe::from_te(&p.te, self, hupai, winning_method, winner)
                     .chankan(chankan.is_some())
-                    .rinshankaihou(rinshankaihou)
-                    .points()
+                    .rinshankaihou(rinshankaihou);
+                let mut context = agari_te.context();
+                context.riichi_bou = riichi_bou;
+                agari_te.score_with_context(&context)
             };
-            trace!("Points: {:?}", &points);
-            let han = points.1;
-            let fu = points.2;
-            winners.push((winner, points.0));
+            trace!("Points: {:?}", &breakdown);
 
             // Move points from loser(s) to winner
-            let honba_points = self.honba as isize * 100;
-            match winning_method {
-                WinningMethod::Ron => {
-                    let points = if winner == Fon::Ton {
-                        points_ron_oya(han, fu)
-                    } else {
-                        points_ron_ko(han, fu)
-                    };
-                    let total = points + honba_points * 3;
-                    self.score[winner as usize].score += total;
-                    self.score[loser as usize].score -= total;
-                }
-                WinningMethod::Tsumo | WinningMethod::Nagashimangan => {
-                    if winner == Fon::Ton {
-                        let points = points_tsumo_oya(han, fu);
-                        let total = points + honba_points;
-                        for (i, score) in self.score.iter_mut().enumerate() {
-                            if i == winner as usize {
-                                score.score += 3 * total;
-                            } else {
-                                score.score -= total;
-                            }
-                        }
+            for (score, payment) in self.score.iter_mut().zip(breakdown.payments) {
+                score.score += payment;
+            }
+
+            // Move chips from loser(s) to winner
+            let shuugi = self.rules.shuugi;
+            let mut winner_chips =
+                shuugi.aka * breakdown.aka as isize + shuugi.uradora * breakdown.uradora as isize;
+            if breakdown.yaku().contains(&Yaku::Ippatsu) {
+                winner_chips += shuugi.ippatsu;
+            }
+            if let YakuValue::Yakuman(yakuman) = breakdown.han {
+                winner_chips += shuugi.yakuman * yakuman as isize;
+            }
+            if winning_method == WinningMethod::Ron {
+                chips[winner as usize] += winner_chips;
+                chips[loser as usize] -= winner_chips;
+            } else {
+                for (i, c) in chips.iter_mut().enumerate() {
+                    if i == winner as usize {
+                        *c += 3 * winner_chips;
                     } else {
-                        let (oya_points, ko_points) = points_tsumo_ko(han, fu);
-                        for (i, score) in self.score.iter_mut().enumerate() {
-                            if i == winner as usize {
-                                score.score += oya_points + ko_points * 2 + honba_points * 3;
-                            } else {
-                                score.score -= if i == Fon::Ton as usize {
-                                    oya_points
-                                } else {
-                                    ko_points
-                                } + honba_points;
-                            }
-                        }
-                    };
+                        *c -= winner_chips;
+                    }
                 }
             }
 
             if winner == Fon::Ton {
                 oya_agari = true;
             }
+            winners.push((winner, breakdown));
+        }
+
+        for (score, c) in self.score.iter_mut().zip(chips) {
+            score.chips += c;
         }
 
-        KyokuResult::Agari { winners, oya_agari }
+        KyokuResult::Agari {
+            winners,
+            oya_agari,
+            chips,
+        }
     }
 
     fn last_thrown_tile(&self) -> Option<Hai> {
@@ -891,25 +1187,31 @@ impl Game {
 
     fn remove_last_thrown_tile(&mut self) -> Hai {
         let player_who_threw_last_tile = self.turn.prev();
-        let player_index = player_who_threw_last_tile as usize;
-        self.hoo[player_index]
-            .river
-            .pop()
-            .expect("Has last thrown tile")
-            .hai()
+        let hoo = &mut self.hoo[player_who_threw_last_tile as usize];
+        if let Some(discard) = hoo.discards.last_mut() {
+            discard.called = true;
+        }
+        hoo.river.pop().expect("Has last thrown tile").hai()
     }
 
+    /// Throw a tile.
+    ///
+    /// If `riichi` is true, the riichi is only declared. It is established
+    /// (and the riichi bou is paid) by [`Game::establish_riichi`] once the
+    /// thrown tile is not ronned.
     pub fn throw_tile(&mut self, p: Fon, i: TehaiIndex, riichi: bool) {
         let hai = self.players[p as usize].te.throw_and_insert(i);
-        self.hoo[p as usize].river.push(if riichi {
-            self.players[p as usize].riichi = Some(Riichi {
-                ippatsu: true,
-                double: self.first_uninterrupted_turn(),
-                furiten: self.is_furiten(p),
-                machi: find_machi(self.players[p as usize].te.hai()),
-            });
-            self.score[p as usize].riichi_bou += 1;
-            self.score[p as usize].score -= 1000;
+        let order = self.hoo.iter().map(|hoo| hoo.discards.len()).sum();
+        let sutehai = if riichi {
+            self.riichi_sengen = Some((
+                p,
+                Riichi {
+                    ippatsu: true,
+                    double: self.first_uninterrupted_turn(),
+                    furiten: self.is_furiten(p),
+                    machi: find_machi(self.players[p as usize].te.hai()),
+                },
+            ));
             SuteHai::Riichi(hai)
         } else {
             if let Some(riichi) = self.players[p as usize].riichi.as_mut() {
@@ -917,7 +1219,20 @@ impl Game {
                 riichi.ippatsu = false;
             }
             SuteHai::Normal(hai)
-        })
+        };
+        let tedashi = !matches!(i, TehaiIndex::Tsumohai);
+        self.hoo[p as usize].throw(sutehai, order, tedashi);
+    }
+
+    /// Establish the pending riichi declaration (立直成立), if any.
+    /// The riichi bou is paid only at this point.
+    fn establish_riichi(&mut self) {
+        if let Some((p, riichi)) = self.riichi_sengen.take() {
+            debug!("Riichi of player {} is established", p as usize);
+            self.players[p as usize].riichi = Some(riichi);
+            self.score[p as usize].riichi_bou += 1;
+            self.score[p as usize].score -= 1000;
+        }
     }
 
     /// Set ippatsu boolean to false.
@@ -1013,7 +1328,7 @@ impl Game {
     }
 
     /// Returns a boolean whose value is false if this is the last turn
-    pub fn call_kan(&mut self, p: Fon, channels: &[AiServer; 4]) -> Option<KyokuResult> {
+    pub fn call_kan(&mut self, p: Fon, bots: &mut dyn Bots) -> Option<KyokuResult> {
         let hai = self.remove_last_thrown_tile();
         debug!(
             "Kan called by player {}. Last thrown tile: {}, thrown by player {}",
@@ -1035,15 +1350,11 @@ impl Game {
         };
         te.daikantsu(hai, direction);
         self.remove_ippatsu();
-        self.kan_after(p, channels)
+        self.kan_after(p, bots)
     }
 
     /// Returns a boolean whose value is false if this is the last turn
-    pub fn announce_ankan(
-        &mut self,
-        i: TehaiIndex,
-        channels: &[AiServer; 4],
-    ) -> Option<KyokuResult> {
+    pub fn announce_ankan(&mut self, i: TehaiIndex, bots: &mut dyn Bots) -> Option<KyokuResult> {
         // Retrieve chankan tile
         let hai = self.players[self.turn as usize]
             .te
@@ -1061,52 +1372,17 @@ impl Game {
         let mut call2 = None;
         let mut call3 = None;
         if chankan1 {
-            channels[self.turn.next() as usize]
-                .tx
-                .send(GameRequest::new(
-                    self,
-                    Request::Call(vec![PossibleCall::Ron]),
-                    self.turn.next(),
-                ))
-                .expect("Sent!");
+            call1 = bots.call(self, self.turn.next(), vec![PossibleCall::Ron]);
         }
         if chankan2 {
-            channels[self.turn.next().next() as usize]
-                .tx
-                .send(GameRequest::new(
-                    self,
-                    Request::Call(vec![PossibleCall::Ron]),
-                    self.turn.next().next(),
-                ))
-                .expect("Sent!");
+            call2 = bots.call(self, self.turn.next().next(), vec![PossibleCall::Ron]);
         }
         if chankan3 {
-            channels[self.turn.next().next().next() as usize]
-                .tx
-                .send(GameRequest::new(
-                    self,
-                    Request::Call(vec![PossibleCall::Ron]),
-                    self.turn.next().next().next(),
-                ))
-                .expect("Sent!");
-        }
-        if chankan1 {
-            call1 = channels[self.turn.next() as usize]
-                .rx_call
-                .recv()
-                .expect("Received!");
-        }
-        if chankan2 {
-            call2 = channels[self.turn.next().next() as usize]
-                .rx_call
-                .recv()
-                .expect("Received!");
-        }
-        if chankan3 {
-            call3 = channels[self.turn.next().next().next() as usize]
-                .rx_call
-                .recv()
-                .expect("Received!");
+            call3 = bots.call(
+                self,
+                self.turn.next().next().next(),
+                vec![PossibleCall::Ron],
+            );
         }
         // NB: If a riichi player did not call possible ron on a chankan,
         // they will be in furiten.
@@ -1126,11 +1402,11 @@ impl Game {
             // Abort ankan!
             self.players[self.turn as usize].te.abort_ankan(hai);
             let result = self.agari(ron_calls, WinningMethod::Ron, Some(hai), false);
-            self.send_game_result(result.clone(), channels);
+            self.send_game_result(result.clone(), bots);
             return Some(result);
         }
 
-        self.kan_after(self.turn, channels)
+        self.kan_after(self.turn, bots)
     }
 
     fn can_chankan(&self, player: Fon, hai: Hai) -> bool {
@@ -1145,8 +1421,7 @@ impl Game {
                 player,
             )
             .chankan(true);
-            let (yaku, _, _) = agari_te.points();
-            !yaku.is_empty()
+            agari_te.score().is_agari()
         }
     }
 
@@ -1163,16 +1438,12 @@ impl Game {
             player,
         )
         .chankan(true);
-        let (yaku, _, _) = agari_te.points();
+        let yaku = agari_te.score().yaku();
         yaku.contains(&Yaku::Kokushimusou) || yaku.contains(&Yaku::KokushimusouJuusanmen)
     }
 
     /// Returns a boolean whose value is false if this is the last turn
-    pub fn announce_kakan(
-        &mut self,
-        i: TehaiIndex,
-        channels: &[AiServer; 4],
-    ) -> Option<KyokuResult> {
+    pub fn announce_kakan(&mut self, i: TehaiIndex, bots: &mut dyn Bots) -> Option<KyokuResult> {
         // Retrieve chankan tile
         let hai = self.players[self.turn as usize]
             .te
@@ -1190,52 +1461,17 @@ impl Game {
         let mut call2 = None;
         let mut call3 = None;
         if chankan1 {
-            channels[self.turn.next() as usize]
-                .tx
-                .send(GameRequest::new(
-                    self,
-                    Request::Call(vec![PossibleCall::Ron]),
-                    self.turn.next(),
-                ))
-                .expect("Sent!");
-        }
-        if chankan2 {
-            channels[self.turn.next().next() as usize]
-                .tx
-                .send(GameRequest::new(
-                    self,
-                    Request::Call(vec![PossibleCall::Ron]),
-                    self.turn.next().next(),
-                ))
-                .expect("Sent!");
-        }
-        if chankan3 {
-            channels[self.turn.next().next().next() as usize]
-                .tx
-                .send(GameRequest::new(
-                    self,
-                    Request::Call(vec![PossibleCall::Ron]),
-                    self.turn.next().next().next(),
-                ))
-                .expect("Sent!");
-        }
-        if chankan1 {
-            call1 = channels[self.turn.next() as usize]
-                .rx_call
-                .recv()
-                .expect("Received!");
+            call1 = bots.call(self, self.turn.next(), vec![PossibleCall::Ron]);
         }
         if chankan2 {
-            call2 = channels[self.turn.next().next() as usize]
-                .rx_call
-                .recv()
-                .expect("Received!");
+            call2 = bots.call(self, self.turn.next().next(), vec![PossibleCall::Ron]);
         }
         if chankan3 {
-            call3 = channels[self.turn.next().next().next() as usize]
-                .rx_call
-                .recv()
-                .expect("Received!");
+            call3 = bots.call(
+                self,
+                self.turn.next().next().next(),
+                vec![PossibleCall::Ron],
+            );
         }
         // NB: If a riichi player did not call possible ron on a chankan,
         // they will be in furiten.
@@ -1255,16 +1491,16 @@ impl Game {
             // Abort kakan!
             self.players[self.turn as usize].te.abort_kakan(hai);
             let result = self.agari(ron_calls, WinningMethod::Ron, Some(hai), false);
-            self.send_game_result(result.clone(), channels);
+            self.send_game_result(result.clone(), bots);
             return Some(result);
         }
 
         // After kan
-        self.kan_after(self.turn, channels)
+        self.kan_after(self.turn, bots)
     }
 
     /// Returns a boolean whose value is false if this is the last turn
-    pub fn kan_after(&mut self, p: Fon, channels: &[AiServer; 4]) -> Option<KyokuResult> {
+    pub fn kan_after(&mut self, p: Fon, bots: &mut dyn Bots) -> Option<KyokuResult> {
         self.remove_ippatsu();
         let te = &mut self.players[p as usize].te;
         // Insert tsumohai in te, if any
@@ -1274,7 +1510,7 @@ impl Game {
         // Draw from mont intouchable and do a standard turn
         self.draw_from_rinshan(p);
         self.change_turn(p);
-        self.do_turn(channels, true)
+        self.do_turn(bots, true)
     }
 
     pub fn to_string_repr(&self) -> String {
@@ -1614,6 +1850,13 @@ impl Game {
         out
     }
 
+    /// View of the game from the point of view of player `p`
+    pub fn player_view(&self, p: Fon) -> PlayerView<'_> {
+        PlayerView {
+            game: self,
+            player: p,
+        }
+    }
     pub fn player_te(&self, p: Fon) -> impl Iterator<Item = &Hai> {
         self.players[p as usize].te.hai.iter()
     }
@@ -1636,12 +1879,45 @@ impl Game {
     pub fn tsumo_cnt(&self) -> usize {
         self.tsumo_cnt
     }
+    pub fn honba(&self) -> usize {
+        self.honba
+    }
+    /// Kyoku number in the current wind, from 0.
+    ///
+    /// Players move one seat each time the oya changes, so the first oya
+    /// (起家) is at index `kyoku`.
+    pub fn kyoku(&self) -> usize {
+        self.kyoku
+    }
+    pub fn player_score(&self, p: Fon) -> isize {
+        self.score[p as usize].score
+    }
+    /// Bonus chips (祝儀) won or lost since the start of the hanchan
+    pub fn player_chips(&self, p: Fon) -> isize {
+        self.score[p as usize].chips
+    }
+    /// Number of riichi sticks on the table (供託)
+    pub fn riichi_bou_count(&self) -> usize {
+        self.score.iter().map(|score| score.riichi_bou).sum()
+    }
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+    /// Play with `rules` instead of the default ones. Only meant for a new
+    /// game.
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.rules = rules;
+    }
     #[cfg(test)]
     pub fn tsumo_cnt_mut(&mut self) -> &mut usize {
         &mut self.tsumo_cnt
     }
+    #[cfg(test)]
+    pub fn honba_mut(&mut self) -> &mut usize {
+        &mut self.honba
+    }
 
-    fn dora_indicator(&self) -> Vec<Hai> {
+    pub fn dora_indicator(&self) -> Vec<Hai> {
         (0..=self.kan_count())
             .map(|i| {
                 let break_point = self.wall_break_index();
@@ -1659,7 +1935,7 @@ impl Game {
             })
             .collect()
     }
-    fn uradora_indicator(&self) -> Vec<Hai> {
+    pub fn uradora_indicator(&self) -> Vec<Hai> {
         (0..=self.kan_count())
             .map(|i| {
                 let break_point = self.wall_break_index();
@@ -1687,26 +1963,63 @@ impl Game {
             .collect()
     }
 
-    pub fn title_repr(&self) -> String {
-        let kyoku = format!("{}{}局", self.wind.to_kanji(), self.kyoku + 1);
-
-        let kyoku = if self.honba == 0 {
-            kyoku
-        } else {
-            format!("{}{}本場", kyoku, self.honba)
-        };
-
-        let turn = format!(
-            "{:>2}巡目 {}家 ({:>2})",
-            self.jun,
-            self.turn.to_kanji(),
-            self.tsumo_cnt
-        );
-
-        format!("{kyoku} {turn}")
+    pub fn title_repr(&self, locale: Locale) -> String {
+        let wind = self.wind.name(locale);
+        let kyoku = self.kyoku + 1;
+        let remaining = self.remaining_tsumo_count();
+        match locale {
+            Locale::Japanese => {
+                let kyoku = format!("{}{}局", wind, kyoku);
+                let kyoku = if self.honba == 0 {
+                    kyoku
+                } else {
+                    format!("{}{}本場", kyoku, self.honba)
+                };
+                let turn = format!(
+                    "{:>2}巡目 {}家 ({:>2}) 残り{:>2}枚",
+                    self.jun,
+                    self.turn.name(locale),
+                    self.tsumo_cnt,
+                    remaining
+                );
+                format!("{kyoku} {turn}")
+            }
+            Locale::Romaji => {
+                let kyoku = format!("{} {} kyoku", wind, kyoku);
+                let kyoku = if self.honba == 0 {
+                    kyoku
+                } else {
+                    format!("{} {} honba", kyoku, self.honba)
+                };
+                let turn = format!(
+                    "{:>2} junme {}-cha ({:>2}) nokori {:>2} mai",
+                    self.jun,
+                    self.turn.name(locale),
+                    self.tsumo_cnt,
+                    remaining
+                );
+                format!("{kyoku}, {turn}")
+            }
+            Locale::English => {
+                let kyoku = format!("{} {}", wind, kyoku);
+                let kyoku = if self.honba == 0 {
+                    kyoku
+                } else {
+                    format!("{} ({} honba)", kyoku, self.honba)
+                };
+                let turn = format!(
+                    "turn {:>2}, {} to play ({:>2}), {:>2} tiles left",
+                    self.jun,
+                    self.turn.name(locale),
+                    self.tsumo_cnt,
+                    remaining
+                );
+                format!("{kyoku}, {turn}")
+            }
+        }
     }
 
-    pub fn score_repr(&self) -> String {
+    pub fn score_repr(&self, locale: Locale) -> String {
         fn riichi_bou_repr(n: usize) -> String {
             let mut out = String::with_capacity(n);
             for _ in 0..n {
@@ -1714,19 +2027,36 @@ impl Game {
             }
             out
         }
+        let shuugi_enabled = self.rules.shuugi.is_enabled();
+        let chips_repr = |fon: Fon| {
+            if shuugi_enabled {
+                let chips = self.score[fon as usize].chips;
+                match locale {
+                    Locale::Japanese => format!("  ({:+}枚)", chips),
+                    Locale::Romaji => format!("  ({:+} mai)", chips),
+                    Locale::English => format!("  ({:+} chips)", chips),
+                }
+            } else {
+                String::new()
+            }
+        };
         format!(
-            "{}: {}  {}\n{}: {}  {}\n{}: {}  {}\n{}: {}  {}",
-            Fon::Ton.to_kanji(),
+            "{}: {}{}  {}\n{}: {}{}  {}\n{}: {}{}  {}\n{}: {}{}  {}",
+            Fon::Ton.name(locale),
             self.score[Fon::Ton as usize].score,
+            chips_repr(Fon::Ton),
             riichi_bou_repr(self.score[Fon::Ton as usize].riichi_bou),
-            Fon::Nan.to_kanji(),
+            Fon::Nan.name(locale),
             self.score[Fon::Nan as usize].score,
+            chips_repr(Fon::Nan),
             riichi_bou_repr(self.score[Fon::Nan as usize].riichi_bou),
-            Fon::Shaa.to_kanji(),
+            Fon::Shaa.name(locale),
             self.score[Fon::Shaa as usize].score,
+            chips_repr(Fon::Shaa),
             riichi_bou_repr(self.score[Fon::Shaa as usize].riichi_bou),
-            Fon::Pee.to_kanji(),
+            Fon::Pee.name(locale),
             self.score[Fon::Pee as usize].score,
+            chips_repr(Fon::Pee),
             riichi_bou_repr(self.score[Fon::Pee as usize].riichi_bou),
         )
     }
@@ -1735,6 +2065,36 @@ impl Game {
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct Hoo {
     river: Vec<SuteHai>,
+    /// Every tile thrown in the kyoku, called ones included
+    #[serde(default)]
+    discards: Vec<Discard>,
+}
+
+impl Hoo {
+    fn throw(&mut self, sutehai: SuteHai, order: usize, tedashi: bool) {
+        self.river.push(sutehai);
+        self.discards.push(Discard {
+            hai: sutehai.hai(),
+            order,
+            tedashi,
+            riichi: matches!(sutehai, SuteHai::Riichi(_)),
+            called: false,
+        });
+    }
+}
+
+/// A tile thrown by a player, which stays here when it is called
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Discard {
+    pub hai: Hai,
+    /// Number of tiles thrown by every player in the kyoku before this one
+    pub order: usize,
+    /// Thrown from the te, not the drawn tile (手出し)
+    pub tedashi: bool,
+    /// Riichi declaration tile (立直宣言牌)
+    pub riichi: bool,
+    /// Taken by a call of another player
+    pub called: bool,
 }
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
@@ -1776,6 +2136,100 @@ impl Player {
     }
 }
 
+/// What a player can see of the game: their own te, every river, every
+/// fuuro and the dora indicators.
+#[derive(Debug, Copy, Clone)]
+pub struct PlayerView<'g> {
+    game: &'g Game,
+    player: Fon,
+}
+
+impl<'g> PlayerView<'g> {
+    pub fn player(&self) -> Fon {
+        self.player
+    }
+    pub fn te(&self) -> &'g Te {
+        &self.game.players[self.player as usize].te
+    }
+    pub fn rules(&self) -> &'g Rules {
+        &self.game.rules
+    }
+    /// Tiles discarded by `p` and not called
+    pub fn river(&self, p: Fon) -> &'g [SuteHai] {
+        &self.game.hoo[p as usize].river
+    }
+
+    /// Every tile thrown by `p` in the kyoku, called ones included
+    pub fn discards(&self, p: Fon) -> &'g [Discard] {
+        &self.game.hoo[p as usize].discards
+    }
+    /// Calls of `p`, ankan included
+    pub fn fuuro(&self, p: Fon) -> &'g [Fuuro] {
+        self.game.players[p as usize].te.fuuro()
+    }
+    pub fn is_riichi(&self, p: Fon) -> bool {
+        self.game.player_is_riichi(p)
+    }
+    /// Whether the player is in riichi and let one of their winning tiles
+    /// go: they cannot ron for the rest of the kyoku
+    pub fn is_riichi_furiten(&self) -> bool {
+        self.game
+            .player_riichi(self.player)
+            .map_or(false, |riichi| riichi.furiten)
+    }
+    /// Tile which was just discarded, that the player may call
+    pub fn last_discard(&self) -> Option<Hai> {
+        self.game.last_thrown_tile()
+    }
+
+    /// Number of copies of `hai` visible outside of the player's own te:
+    /// in the rivers, in the fuuro of the other players and as dora
+    /// indicators.
+    pub fn visible_count(&self, hai: Hai) -> usize {
+        let in_rivers = self
+            .game
+            .hoo
+            .iter()
+            .flat_map(|hoo| hoo.river.iter())
+            .filter(|sutehai| sutehai.hai() == hai)
+            .count();
+        let in_fuuro = self
+            .game
+            .players
+            .iter()
+            .filter(|p| p.wind != self.player)
+            .flat_map(|p| p.te.fuuro.iter())
+            .flat_map(Fuuro::hai)
+            .filter(|h| *h == hai)
+            .count();
+        let in_dora_indicators = self
+            .game
+            .dora_indicator()
+            .into_iter()
+            .filter(|h| *h == hai)
+            .count();
+        in_rivers + in_fuuro + in_dora_indicators
+    }
+
+    /// Context of a win of the player, as far as they can know it: ura
+    /// dora and the player who deals in are unknown, and no situational
+    /// yaku (haitei, rinshan, chankan...) is assumed.
+    pub fn win_context(&self) -> WinContext {
+        let riichi = self.game.player_riichi(self.player);
+        WinContext {
+            bakaze: self.game.wind,
+            jikaze: self.player,
+            dora_indicators: self.game.dora_indicator(),
+            riichi: riichi.is_some(),
+            double_riichi: riichi.map(|riichi| riichi.double).unwrap_or(false),
+            ippatsu: riichi.map(|riichi| riichi.ippatsu).unwrap_or(false),
+            honba: self.game.honba,
+            riichi_bou: self.game.riichi_bou_count(),
+            ..Default::default()
+        }
+    }
+}
+
 #[derive(Default, Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Te {
     pub hai: OrderedList<Hai>,
@@ -1783,7 +2237,30 @@ pub struct Te {
     tsumo: Option<Hai>,
 }
 
+impl FromStr for Te {
+    type Err = ParseHaiError;
+
+    /// Parse a te in MPSZ notation, e.g. `123m456p789s11z 1z [1-23m]`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (hai, tsumo, fuuro) = mpsz::parse_te(s)?;
+        let mut te = Te {
+            hai: Default::default(),
+            fuuro,
+            tsumo,
+        };
+        for hai in hai {
+            te.hai.insert(hai);
+        }
+        Ok(te)
+    }
+}
+
 impl Te {
+    /// Write this te in MPSZ notation
+    pub fn to_mpsz(&self) -> String {
+        mpsz::format_te(self.hai.as_ref(), self.tsumo, &self.fuuro)
+    }
+
     pub fn index(&self, hai: Hai) -> Option<TehaiIndex> {
         self.hai.index(&hai).map(TehaiIndex::Tehai).or_else(|| {
             if self.tsumo == Some(hai) {
@@ -1979,11 +2456,17 @@ impl Te {
     pub fn hai(&self) -> &[Hai] {
         self.hai.as_ref()
     }
+    /// Counts of the hidden tiles, with the drawn tile
+    pub fn counts(&self) -> TileCounts {
+        self.hai.iter().chain(&self.tsumo).copied().collect()
+    }
     pub fn fuuro(&self) -> &[Fuuro] {
         self.fuuro.as_ref()
     }
 }
 
+/// Where the caller of a fuuro sits, seen from the discarder. A chi is
+/// always from the right: the discarder is the 上家 of the caller.
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub enum Direction {
     Left,
@@ -2006,7 +2489,37 @@ pub enum Fuuro {
     Kantsu(KantsuInner),
 }
 
+impl FromStr for Fuuro {
+    type Err = ParseHaiError;
+
+    /// Parse a fuuro in MPSZ notation, e.g. `[1-23m]`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        mpsz::parse_fuuro(s)
+    }
+}
+
 impl Fuuro {
+    /// Write this fuuro in MPSZ notation
+    pub fn to_mpsz(&self) -> String {
+        mpsz::format_fuuro(self)
+    }
+
+    /// All the tiles of this fuuro
+    pub fn hai(&self) -> Vec<Hai> {
+        match self {
+            Fuuro::Shuntsu { own, taken, .. } | Fuuro::Kootsu { own, taken, .. } => {
+                vec![own[0], own[1], *taken]
+            }
+            Fuuro::Kantsu(KantsuInner::Ankan { own }) => own.to_vec(),
+            Fuuro::Kantsu(KantsuInner::DaiMinkan { own, taken, .. }) => {
+                vec![own[0], own[1], own[2], *taken]
+            }
+            Fuuro::Kantsu(KantsuInner::ShouMinkan {
+                own, taken, added, ..
+            }) => vec![own[0], own[1], *added, *taken],
+        }
+    }
+
     fn direction(&self) -> Option<Direction> {
         match self {
             Fuuro::Shuntsu { from, .. }
@@ -2044,36 +2557,23 @@ impl Game {
             return vec![];
         }
         if let Some(hai) = self.last_thrown_tile() {
-            match hai {
-                Hai::Suu(SuuHai { value, .. }) => {
-                    // FIXME: Take into account Kuikae
-                    let right = [hai.prev().prev(), hai.prev()];
-                    let middle = [hai.prev(), hai.next()];
-                    let left = [hai.next(), hai.next().next()];
-
-                    let possible_patterns = match value {
-                        Values::Ii => vec![left],
-                        Values::Ryan => vec![middle, left],
-                        Values::Paa => vec![right, middle],
-                        Values::Kyuu => vec![right],
-                        _ => vec![right, middle, left],
-                    };
-
-                    let mut out = vec![];
-                    for pattern in possible_patterns {
-                        let pos1 = self.players[self.turn as usize].te.hai.index(&pattern[0]);
-                        let pos2 = self.players[self.turn as usize].te.hai.index(&pattern[1]);
-                        if let (Some(p1), Some(p2)) = (pos1, pos2) {
-                            let new_match = [p1, p2];
-                            if !out.contains(&new_match) {
-                                out.push(new_match);
-                            }
-                        }
+            let kind = TileKind::from(hai);
+            let te = &self.players[self.turn as usize].te;
+            let counts = TileCounts::from_hai(te.hai());
+            let mut out = vec![];
+            // FIXME: Take into account Kuikae
+            for pattern in [[-2, -1], [-1, 1], [1, 2]] {
+                if let (Some(kind1), Some(kind2)) =
+                    (kind.offset(pattern[0]), kind.offset(pattern[1]))
+                {
+                    if counts[kind1] > 0 && counts[kind2] > 0 {
+                        let p1 = te.hai.index(&kind1.to_hai()).expect("Has tile");
+                        let p2 = te.hai.index(&kind2.to_hai()).expect("Has tile");
+                        out.push([p1, p2]);
                     }
-                    out
                 }
-                Hai::Ji(..) => vec![],
             }
+            out
         } else {
             vec![]
         }
@@ -2085,13 +2585,7 @@ impl Game {
             return false;
         }
         if let Some(hai) = self.last_thrown_tile() {
-            let mut cnt = 0;
-            for tehai in self.players[player as usize].te.hai.iter() {
-                if tehai == &hai {
-                    cnt += 1;
-                }
-            }
-            cnt >= 2
+            TileCounts::from_hai(self.players[player as usize].te.hai())[hai.into()] >= 2
         } else {
             false
         }
@@ -2104,13 +2598,10 @@ impl Game {
             if self.players[player as usize].riichi.is_some() {
                 return false;
             }
-            let mut cnt = 0;
-            for tehai in self.players[player as usize].te.hai.iter() {
-                if tehai == &hai {
-                    cnt += 1;
-                }
+            if !self.can_draw_rinshan() {
+                return false;
             }
-            cnt >= 3
+            TileCounts::from_hai(self.players[player as usize].te.hai())[hai.into()] >= 3
         } else {
             false
         }
@@ -2128,8 +2619,7 @@ impl Game {
                     WinningMethod::Ron,
                     player,
                 );
-                let (yaku, _, _) = agari_te.points();
-                !yaku.is_empty()
+                agari_te.score().is_agari()
             }
         } else {
             false
@@ -2142,21 +2632,21 @@ impl Game {
                 return true;
             }
         }
-        let machi = find_machi(self.players[player as usize].te.hai());
-        for sutehai in &self.hoo[player as usize].river {
-            if machi.contains(&sutehai.hai()) {
-                return true;
-            }
-        }
-        false
+        let te = self.players[player as usize].te.hai();
+        let machi = shanten::machi(&TileCounts::from_hai(te), (14 - te.len()) / 3);
+        // Tiles called by other players count as well
+        self.hoo[player as usize]
+            .discards
+            .iter()
+            .any(|discard| machi.contains(&discard.hai.into()))
     }
 
-    fn can_tsumo(&self) -> bool {
+    fn can_tsumo(&self, rinshankaihou: bool) -> bool {
         let te = &self.players[self.turn as usize].te;
         if let Some(tsumo_hai) = te.tsumo {
-            let agari_te = AgariTe::from_te(te, self, tsumo_hai, WinningMethod::Tsumo, self.turn);
-            let (yaku, _, _) = agari_te.points();
-            !yaku.is_empty()
+            let agari_te = AgariTe::from_te(te, self, tsumo_hai, WinningMethod::Tsumo, self.turn)
+                .rinshankaihou(rinshankaihou);
+            agari_te.score().is_agari()
         } else {
             false
         }
@@ -2167,18 +2657,24 @@ impl Game {
 
         let player = &self.players[self.turn as usize];
         let enough_point = self.score[self.turn as usize].score >= 1000;
-        if enough_point && player.riichi.is_none() && player.te.fuuro.is_empty() {
+        // The riichi player must be able to draw at least one more time
+        let enough_tiles = self.remaining_tsumo_count() >= 4;
+        if enough_point && enough_tiles && player.riichi.is_none() && player.te.fuuro.is_empty() {
             if let Some(tsumohai) = player.te.tsumo {
-                let mut te = vec![];
-                te.extend(player.te.hai.iter().cloned());
-                te.push(tsumohai);
-
-                if is_tempai(&te) {
+                let mut counts = player.te.counts();
+                if shanten::shanten_counts(&counts, 0) <= 0 {
                     // Find tiles which can be thrown on saying riichi
-                    for i in 0..te.len() {
-                        let mut te_ = te.clone();
-                        te_.swap_remove(i);
-                        if is_tempai(&te_) {
+                    let mut tempai_after = [None; TileKind::COUNT];
+                    let te = player.te.hai.iter().chain(Some(&tsumohai));
+                    for (i, hai) in te.enumerate() {
+                        let kind = TileKind::from(*hai);
+                        let tempai = *tempai_after[kind.index()].get_or_insert_with(|| {
+                            counts.remove(kind);
+                            let tempai = shanten::shanten_counts(&counts, 0) <= 0;
+                            counts.add(kind);
+                            tempai
+                        });
+                        if tempai {
                             throwable_tiles.push(if i == player.te.hai.len() {
                                 ThrowableOnRiichi::Tsumohai
                             } else {
@@ -2203,18 +2699,12 @@ impl Game {
             }
         }
         if first_turn && no_fuuro {
-            let mut set = std::collections::BTreeSet::new();
-            for hai in self.players[self.turn as usize].te.hai.iter() {
-                if hai.is_jihai_or_1_9() {
-                    set.insert(*hai);
-                }
-            }
-            if let Some(tsumohai) = self.players[self.turn as usize].te.tsumo {
-                if tsumohai.is_jihai_or_1_9() {
-                    set.insert(tsumohai);
-                }
-            }
-            set.len() >= 9
+            let counts = self.players[self.turn as usize].te.counts();
+            let yaochuu = counts
+                .iter()
+                .filter(|(kind, _)| kind.is_jihai_or_1_9())
+                .count();
+            yaochuu >= 9
         } else {
             false
         }
@@ -2234,6 +2724,9 @@ impl Game {
         }
 
         let mut candidates = vec![];
+        if !self.can_draw_rinshan() {
+            return candidates;
+        }
         let te = &self.players[self.turn as usize].te;
         for hai in te.hai.iter() {
             if can_make_shominkan(&te.fuuro, *hai) {
@@ -2250,38 +2743,35 @@ impl Game {
 
     /// Can call Kan on one of these tiles during one's own turn
     fn can_ankan(&self) -> Vec<Hai> {
-        use std::collections::{btree_map::Entry, BTreeMap};
-
-        fn count(cnt_map: &mut BTreeMap<Hai, usize>, hai: Hai) {
-            match cnt_map.entry(hai) {
-                Entry::Vacant(cnt) => {
-                    cnt.insert(1);
-                }
-                Entry::Occupied(mut cnt) => {
-                    *cnt.get_mut() += 1;
-                }
-            }
-        }
-
-        let mut cnt_map = BTreeMap::new();
-        for hai in self.players[self.turn as usize].te.hai.iter() {
-            count(&mut cnt_map, *hai);
-        }
-        if let Some(hai) = self.players[self.turn as usize].te.tsumo {
-            count(&mut cnt_map, hai);
+        if !self.can_draw_rinshan() {
+            return vec![];
         }
-
         // FIXME: Cannot call ankan on special case when player called riichi
         // and calling ankan would change their machi.
-        cnt_map
+        self.players[self.turn as usize]
+            .te
+            .counts()
             .iter()
-            .filter(|(_, &cnt)| cnt == 4)
-            .map(|(hai, _)| *hai)
+            .filter(|(_, count)| *count == 4)
+            .map(|(kind, _)| kind.to_hai())
             .collect()
     }
 
+    /// A kan is only allowed if a rinshan tile can be drawn and the haitei
+    /// tile is not drawn yet.
+    fn can_draw_rinshan(&self) -> bool {
+        self.kan_count() < 4 && self.remaining_tsumo_count() > 0
+    }
+
     fn allowed_calls(&self, player: Fon) -> Vec<PossibleCall> {
         let mut allowed_calls = Vec::with_capacity(4);
+        // Only ron is allowed on the last tile (河底)
+        if self.remaining_tsumo_count() == 0 {
+            if self.can_ron(player) {
+                allowed_calls.push(PossibleCall::Ron);
+            }
+            return allowed_calls;
+        }
         if self.turn == player {
             let possible_chi = self.can_chi();
             if !possible_chi.is_empty() {
@@ -2310,11 +2800,14 @@ pub enum ThrowableOnRiichi {
 }
 
 fn is_tempai(te: &[Hai]) -> bool {
-    count_shanten(te) == 0
+    shanten::shanten(te) <= 0
 }
 
+/// Shanten with the tree solver, kept to check [`shanten`] against it.
+///
 /// Thanks https://qiita.com/tomo_hxx/items/75b5f771285e1334c0a5 !
 /// http://ara.moo.jp/mjhmr/shanten.htm
+#[cfg(test)]
 fn count_shanten(te: &[Hai]) -> usize {
     let some_chi = count_chitoitsu_shanten(te);
     let some_koku = count_kokushimuso_shanten(te);
@@ -2326,6 +2819,7 @@ fn count_shanten(te: &[Hai]) -> usize {
         (Some(chi), Some(koku)) => koku.min(chi).min(normal),
     }
 }
+#[cfg(test)]
 /// Only works for closed hands
 fn count_chitoitsu_shanten(te: &[Hai]) -> Option<usize> {
     if te.len() == 13 || te.len() == 14 {
@@ -2357,6 +2851,7 @@ fn count_chitoitsu_shanten(te: &[Hai]) -> Option<usize> {
         None
     }
 }
+#[cfg(test)]
 /// Only works for closed hands
 fn count_kokushimuso_shanten(te: &[Hai]) -> Option<usize> {
     if te.len() == 13 || te.len() == 14 {
@@ -2376,6 +2871,7 @@ fn count_kokushimuso_shanten(te: &[Hai]) -> Option<usize> {
     }
 }
 
+#[cfg(test)]
 fn count_normal_shanten(te: &[Hai]) -> usize {
     let open_mentsu_count = (14 - te.len()) / 3;
     let root = solver::GroupTree::generate(te, open_mentsu_count, 4, open_mentsu_count, 0);
@@ -2386,7 +2882,67 @@ fn count_normal_shanten(te: &[Hai]) -> usize {
     solver::GroupTree::shanten(&root)
 }
 
+/// Random te of `size` tiles with `melds` fuuro taken from `pool`, which
+/// is tenpai without waiting on a tile of `river` (振聴)
+fn sample_tenpai<R: Rng>(
+    pool: &[Hai],
+    size: usize,
+    melds: usize,
+    river: &[TileKind],
+    rng: &mut R,
+) -> Option<Vec<TileKind>> {
+    let mut counts: TileCounts = pool.iter().copied().collect();
+    let mut te = vec![];
+    let mut take = |counts: &mut TileCounts, kinds: &[TileKind]| {
+        for kind in kinds {
+            counts.remove(*kind);
+            te.push(*kind);
+        }
+    };
+
+    // A complete te, then a tile is taken out
+    let heads: Vec<_> = counts.iter().filter(|(_, n)| *n >= 2).collect();
+    let (head, _) = *heads.choose(rng)?;
+    take(&mut counts, &[head, head]);
+    for _ in melds..4 {
+        let mut mentsu = vec![];
+        for (kind, n) in counts.iter() {
+            if n >= 3 {
+                mentsu.push([kind; 3]);
+            }
+            if let (Some(second), Some(third)) = (kind.offset(1), kind.offset(2)) {
+                if counts[second] > 0 && counts[third] > 0 {
+                    mentsu.push([kind, second, third]);
+                }
+            }
+        }
+        take(&mut counts, mentsu.choose(rng)?);
+    }
+    te.remove(rng.gen_range(0..te.len()));
+    if te.len() != size {
+        return None;
+    }
+
+    let machi = shanten::machi(&te.iter().map(|kind| kind.to_hai()).collect(), melds);
+    if machi.is_empty() || machi.iter().any(|kind| river.contains(kind)) {
+        None
+    } else {
+        Some(te)
+    }
+}
+
+/// Waits of a tenpai te (hidden tiles, 3 less per fuuro)
 fn find_machi(te: &[Hai]) -> Vec<Hai> {
+    let melds = (14 - te.len()) / 3;
+    shanten::machi(&TileCounts::from_hai(te), melds)
+        .into_iter()
+        .map(TileKind::to_hai)
+        .collect()
+}
+
+/// Waits with the tree solver, kept to check [`find_machi`] against it.
+#[cfg(test)]
+fn find_machi_with_solver(te: &[Hai]) -> Vec<Hai> {
     let open_mentsu_count = (14 - te.len()) / 3;
     let root = solver::GroupTree::generate(te, open_mentsu_count, 4, open_mentsu_count, 0);
     let root = solver::GroupTree::shanten0(root);
@@ -2402,8 +2958,9 @@ fn find_machi(te: &[Hai]) -> Vec<Hai> {
     machi
 }
 
+#[cfg(test)]
 mod solver {
-    use super::{Hai, SuuHai, Values};
+    use crate::tiles::{Hai, SuuHai, Values};
     use std::fmt;
 
     #[derive(Debug, Eq, PartialEq, Copy, Clone)]
@@ -2578,6 +3135,7 @@ mod solver {
     }
 
     impl GroupTree {
+        #[cfg(test)]
         pub fn shanten(trees: &[GroupTree]) -> usize {
             let mut shanten = usize::max_value();
             for tree in trees {
@@ -2787,10 +3345,8 @@ mod solver {
 
 #[cfg(test)]
 pub mod tests {
-    use std::str::FromStr;
-
     // Note this useful idiom: importing names from outer (for mod tests) scope.
-    use super::super::tiles::ParseHaiError;
+    use super::super::tiles::{hai_list_from_str, ParseHaiError};
     use super::*;
 
     struct StringifiedGameDebug<'a> {
@@ -2810,19 +3366,18 @@ pub mod tests {
             ];
             let mut hoo: [Hoo; 4] = Default::default();
 
+            let mut order = 0;
             for i in 0..4 {
-                for c in data.te[i].chars() {
-                    let hai = c.to_string().parse()?;
+                for hai in hai_list_from_str(data.te[i])? {
                     players[i].te.hai.insert(hai);
                 }
-                if let Some(c) = data.tsumo[i].chars().next() {
-                    let hai = c.to_string().parse()?;
-                    players[i].te.tsumo = Some(hai);
+                if let Some(hai) = hai_list_from_str(data.tsumo[i])?.first() {
+                    players[i].te.tsumo = Some(*hai);
                 }
-                for c in data.hoo[i].chars() {
+                for hai in hai_list_from_str(data.hoo[i])? {
                     // FIXME: Ignore riichi
-                    let hai = c.to_string().parse()?;
-                    hoo[i].river.push(SuteHai::Normal(hai));
+                    hoo[i].throw(SuteHai::Normal(hai), order, true);
+                    order += 1;
                 }
             }
 
@@ -2840,18 +3395,52 @@ pub mod tests {
                 score: [Score {
                     score: 25000,
                     riichi_bou: 0,
+                    chips: 0,
                 }; 4],
+                riichi_sengen: None,
+                rules: Default::default(),
             })
         }
     }
 
+    pub fn count_shanten_with_solver(te: &[Hai]) -> usize {
+        count_shanten(te)
+    }
+
+    pub fn find_machi_with_solver(te: &[Hai]) -> Vec<Hai> {
+        super::find_machi_with_solver(te)
+    }
+
     pub fn te_from_string(data: &str) -> Result<Vec<Hai>, ParseHaiError> {
-        let mut te = vec![];
-        for c in data.chars() {
-            let hai = c.to_string().parse()?;
-            te.push(hai);
-        }
-        Ok(te)
+        hai_list_from_str(data)
+    }
+
+    #[test]
+    fn test_te_mpsz() {
+        let te: Te = "123m406p789s11z 1z [1-23s]".parse().unwrap();
+        assert_eq!(te.hai().len(), 11);
+        assert!(te.hai()[4].is_aka());
+        assert_eq!(te.tsumo, Some("1z".parse().unwrap()));
+        assert_eq!(te.fuuro()[0], "[1-23s]".parse().unwrap());
+        assert_eq!(te.to_mpsz(), "123m406p789s11z 1z [1-23s]");
+    }
+
+    #[test]
+    fn test_title_repr_locale() {
+        let game = Game {
+            wind: Fon::Nan,
+            kyoku: 1,
+            honba: 2,
+            ..Default::default()
+        };
+        assert!(game.title_repr(Locale::Japanese).starts_with("南2局2本場 "));
+        assert!(game
+            .title_repr(Locale::Romaji)
+            .starts_with("Nan 2 kyoku 2 honba, "));
+        assert!(game
+            .title_repr(Locale::English)
+            .starts_with("South 2 (2 honba), turn "));
+        assert!(game.score_repr(Locale::English).starts_with("East: "));
     }
 
     #[test]
@@ -2983,6 +3572,38 @@ pub mod tests {
         assert_eq!(find_machi(&te), te_from_string("🀗").unwrap());
     }
 
+    #[test]
+    fn test_find_machi_hadaka_tanki() {
+        // A single tile left after 4 fuuro (裸単騎)
+        let te = te_from_string("🀅").unwrap();
+        assert_eq!(find_machi(&te), te);
+    }
+
+    #[test]
+    fn test_find_machi_agrees_with_solver() {
+        use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(40);
+        // Draw from a single suit to get many tenpai te
+        let mut tiles: Vec<_> = make_all_tiles()[..9]
+            .iter()
+            .flat_map(|hai| [*hai; 4])
+            .collect();
+        for _ in 0..40 {
+            tiles.shuffle(&mut rng);
+            let te = &tiles[..13];
+            let counts = TileCounts::from_hai(te);
+            // The solver only knows about the normal form
+            if shanten::normal_shanten(&counts, 0) != 0 || shanten::chiitoitsu_shanten(&counts) <= 0
+            {
+                continue;
+            }
+            let mut expected = find_machi_with_solver(te);
+            expected.retain(|hai| counts[TileKind::from(*hai)] < 4);
+            assert_eq!(find_machi(te), expected, "{:?}", te);
+        }
+    }
+
     use ron;
 
     #[test]
@@ -2991,6 +3612,330 @@ pub mod tests {
         assert_eq!(game.can_riichi(), vec![ThrowableOnRiichi::Tsumohai]);
     }
 
+    #[test]
+    fn test_remaining_tsumo_count() {
+        let mut game = Game::default();
+        assert_eq!(game.remaining_tsumo_count(), 70);
+        *game.tsumo_cnt_mut() = 69;
+        assert!(game.next_tsumohai_index().is_some());
+        *game.tsumo_cnt_mut() = 70;
+        assert_eq!(game.remaining_tsumo_count(), 0);
+        assert!(game.next_tsumohai_index().is_none());
+    }
+
+    #[test]
+    fn test_live_wall_ends_at_dead_wall() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut game = Game::new(&mut StdRng::seed_from_u64(40));
+        game.deal();
+        let mut draws = 0;
+        while let Some(i) = game.next_tsumohai_index() {
+            assert!(game.yama[i].take().is_some(), "Tile {} drawn twice", i);
+            game.tsumo_cnt += 1;
+            draws += 1;
+        }
+        assert_eq!(draws, 70);
+        // Haitei was the last tile before the dead wall (王牌): only its 14
+        // tiles are left
+        let break_point = game.wall_break_index();
+        let mut dead_wall: Vec<usize> = (1..=14).map(|n| (break_point + 136 - n) % 136).collect();
+        dead_wall.sort();
+        let left: Vec<usize> = (0..136).filter(|i| game.yama[*i].is_some()).collect();
+        assert_eq!(left, dead_wall);
+    }
+
+    #[test]
+    fn test_no_riichi_without_enough_tiles() {
+        let mut game: Game =
+            ron::de::from_reader(std::fs::File::open("riichi.ron").unwrap()).unwrap();
+        *game.tsumo_cnt_mut() = 66;
+        assert_eq!(game.can_riichi(), vec![ThrowableOnRiichi::Tsumohai]);
+        *game.tsumo_cnt_mut() = 67;
+        assert!(game.can_riichi().is_empty());
+    }
+
+    #[test]
+    fn test_only_ron_on_houtei() {
+        let mut game: Game = ron::de::from_reader(std::fs::File::open("pon.ron").unwrap()).unwrap();
+        let all_calls = |game: &Game| -> Vec<PossibleCall> {
+            [Fon::Ton, Fon::Nan, Fon::Shaa, Fon::Pee]
+                .into_iter()
+                .flat_map(|p| game.allowed_calls(p))
+                .collect()
+        };
+        assert!(all_calls(&game).contains(&PossibleCall::Pon));
+        *game.tsumo_cnt_mut() = 70;
+        assert!(all_calls(&game)
+            .iter()
+            .all(|call| call == &PossibleCall::Ron));
+    }
+
+    #[test]
+    fn test_no_kan_on_haitei() {
+        let mut game: Game =
+            ron::de::from_reader(std::fs::File::open("ankan.ron").unwrap()).unwrap();
+        // 東家 has four 🀍
+        game.turn = Fon::Ton;
+        assert!(!game.can_ankan().is_empty());
+        *game.tsumo_cnt_mut() = 70;
+        assert!(game.can_ankan().is_empty());
+    }
+
+    #[test]
+    fn test_riichi_established_after_tile_passes() {
+        let mut game: Game =
+            ron::de::from_reader(std::fs::File::open("riichi.ron").unwrap()).unwrap();
+        game.throw_tile(Fon::Ton, TehaiIndex::Tsumohai, true);
+        // Only declared
+        assert!(!game.player_is_riichi(Fon::Ton));
+        assert_eq!(game.score[Fon::Ton as usize].score, 25000);
+        assert_eq!(game.score[Fon::Ton as usize].riichi_bou, 0);
+
+        game.establish_riichi();
+        let riichi = game.player_riichi(Fon::Ton).expect("Riichi established");
+        assert!(riichi.ippatsu);
+        assert_eq!(game.score[Fon::Ton as usize].score, 24000);
+        assert_eq!(game.score[Fon::Ton as usize].riichi_bou, 1);
+    }
+
+    #[test]
+    fn test_riichi_void_on_ron() {
+        let mut game: Game =
+            ron::de::from_reader(std::fs::File::open("riichi.ron").unwrap()).unwrap();
+        game.throw_tile(Fon::Ton, TehaiIndex::Tsumohai, true);
+        game.change_turn(Fon::Nan);
+        let riichi_hai = game.last_thrown_tile().expect("Has thrown tile");
+
+        // Give player 南 a hand waiting on the riichi tile
+        {
+            let te = game.player_te_mut(Fon::Nan);
+            *te = Te::default();
+            for hai in te_from_string("🀇🀈🀉🀊🀋🀌🀍🀎🀏🀙🀚🀛").unwrap() {
+                te.hai.insert(hai);
+            }
+            te.hai.insert(riichi_hai);
+        }
+        game.agari(vec![Fon::Nan], WinningMethod::Ron, None, false);
+        game.establish_riichi();
+
+        assert!(!game.player_is_riichi(Fon::Ton));
+        assert!(game.score.iter().all(|s| s.riichi_bou == 0));
+        // The winner only gets the ron payment, without the riichi bou
+        assert_eq!(
+            game.score[Fon::Nan as usize].score - 25000,
+            25000 - game.score[Fon::Ton as usize].score
+        );
+    }
+
+    fn game_at_ryukyoku() -> Game {
+        Game::from_string_debug(StringifiedGameDebug {
+            te: [
+                "🀇🀈🀉🀊🀋🀌🀍🀎🀏🀙🀚🀛🀗",
+                "🀇🀋🀏🀙🀝🀡🀐🀔🀘🀀🀁🀂🀃",
+                "🀇🀋🀏🀙🀝🀡🀐🀔🀘🀀🀁🀂🀃",
+                "🀇🀋🀏🀙🀝🀡🀐🀔🀘🀀🀁🀂🀃",
+            ],
+            tsumo: ["", "", "", ""],
+            hoo: ["🀊", "🀊", "🀊", "🀊"],
+            dice: [Dice::One, Dice::Six],
+        })
+        .unwrap()
+    }
+
+    fn noten_bot() -> AiServer {
+        AiServer::new(
+            |_, _| None,
+            |_, _| TurnResult::ThrowHai {
+                index: TehaiIndex::Tsumohai,
+                riichi: false,
+            },
+            |_| false,
+        )
+    }
+
+    #[test]
+    fn test_ryukyoku_declare_tempai() {
+        let mut game = game_at_ryukyoku();
+        let mut channels: [AiServer; 4] = Default::default();
+        let result = game.ryukyoku(&mut channels);
+        assert!(matches!(result, KyokuResult::Ryukyoku { oya_tempai: true }));
+        assert_eq!(game.score[Fon::Ton as usize].score, 28000);
+        assert_eq!(game.score[Fon::Nan as usize].score, 24000);
+    }
+
+    #[test]
+    fn test_ryukyoku_hide_tempai() {
+        let mut game = game_at_ryukyoku();
+        let mut channels = [noten_bot(), noten_bot(), noten_bot(), noten_bot()];
+        let result = game.ryukyoku(&mut channels);
+        assert!(matches!(
+            result,
+            KyokuResult::Ryukyoku { oya_tempai: false }
+        ));
+        assert!(game.score.iter().all(|s| s.score == 25000));
+    }
+
+    #[test]
+    fn test_kyusyukyuhai_is_abortive_draw() {
+        let mut game = game_at_ryukyoku();
+        let kyusyukyuhai = AiServer::new(
+            |_, _| None,
+            |_, _| TurnResult::Kyusyukyuhai,
+            |_| panic!("No tenpai declaration on an abortive draw"),
+        );
+        let mut channels = [kyusyukyuhai, noten_bot(), noten_bot(), noten_bot()];
+        let result = game.do_turn(&mut channels, false);
+        // The tenpai oya neither gets noten bappu nor keeps the deal
+        assert!(matches!(
+            result,
+            Some(KyokuResult::Ryukyoku { oya_tempai: false })
+        ));
+        assert!(game.score.iter().all(|s| s.score == 25000));
+    }
+
+    #[test]
+    fn test_ryukyoku_riichi_always_tempai() {
+        let mut game = game_at_ryukyoku();
+        game.players[Fon::Ton as usize].riichi = Some(Riichi {
+            ippatsu: false,
+            double: false,
+            machi: find_machi(game.players[Fon::Ton as usize].te.hai()),
+            furiten: false,
+        });
+        let mut channels = [noten_bot(), noten_bot(), noten_bot(), noten_bot()];
+        let result = game.ryukyoku(&mut channels);
+        assert!(matches!(result, KyokuResult::Ryukyoku { oya_tempai: true }));
+        assert_eq!(game.score[Fon::Ton as usize].score, 28000);
+    }
+
+    #[test]
+    fn test_ryukyoku_noten_riichi_chombo() {
+        let mut game = game_at_ryukyoku();
+        game.players[Fon::Nan as usize].riichi = Some(Riichi {
+            ippatsu: false,
+            double: false,
+            machi: vec![],
+            furiten: false,
+        });
+        let mut channels: [AiServer; 4] = Default::default();
+        let result = game.ryukyoku(&mut channels);
+        match result {
+            KyokuResult::Chombo {
+                offenders,
+                penalty: Penalty::Mangan,
+                redo: true,
+                ..
+            } => assert_eq!(offenders, vec![(Fon::Nan, ChomboReason::NotenRiichi)]),
+            _ => panic!("Expected chombo, got {:?}", result),
+        }
+        // No noten bappu on redo, only the mangan penalty
+        assert_eq!(game.score[Fon::Ton as usize].score, 29000);
+        assert_eq!(game.score[Fon::Nan as usize].score, 17000);
+        assert_eq!(game.score[Fon::Shaa as usize].score, 27000);
+        assert_eq!(game.score[Fon::Pee as usize].score, 27000);
+    }
+
+    #[test]
+    fn test_ryukyoku_noten_riichi_fixed_penalty() {
+        let mut game = game_at_ryukyoku();
+        game.rules.chombo = ChomboRule {
+            penalty: Penalty::Fixed(20000),
+            redo: false,
+        };
+        game.players[Fon::Nan as usize].riichi = Some(Riichi {
+            ippatsu: false,
+            double: false,
+            machi: vec![],
+            furiten: false,
+        });
+        let mut channels: [AiServer; 4] = Default::default();
+        let result = game.ryukyoku(&mut channels);
+        assert!(matches!(
+            result,
+            KyokuResult::Chombo {
+                redo: false,
+                oya_tempai: true,
+                ..
+            }
+        ));
+        // Noten bappu, then penalty
+        assert_eq!(game.score[Fon::Ton as usize].score, 28000);
+        assert_eq!(game.score[Fon::Nan as usize].score, 4000);
+        assert_eq!(game.score[Fon::Shaa as usize].score, 24000);
+        // The penalty leaves the table: nobody else gets it
+        assert_eq!(game.score[Fon::Pee as usize].score, 24000);
+        let total: isize = game.score.iter().map(|s| s.score).sum();
+        assert_eq!(total, 100000 - 20000);
+    }
+
+    #[test]
+    fn test_chombo_redo_refunds_riichi_bou() {
+        let mut game = game_at_ryukyoku();
+        // 東 and 南 are in riichi, 西 left a stick in a previous kyoku
+        for p in [Fon::Ton, Fon::Nan] {
+            let machi = find_machi(game.players[p as usize].te.hai());
+            game.players[p as usize].riichi = Some(Riichi {
+                ippatsu: false,
+                double: false,
+                machi,
+                furiten: false,
+            });
+        }
+        for p in [Fon::Ton, Fon::Nan, Fon::Shaa] {
+            game.score[p as usize].riichi_bou = 1;
+            game.score[p as usize].score -= 1000;
+        }
+        let mut channels: [AiServer; 4] = Default::default();
+        let result = game.ryukyoku(&mut channels);
+        assert!(matches!(result, KyokuResult::Chombo { redo: true, .. }));
+        assert_eq!(game.score[Fon::Ton as usize].riichi_bou, 0);
+        assert_eq!(game.score[Fon::Nan as usize].riichi_bou, 0);
+        assert_eq!(game.score[Fon::Shaa as usize].riichi_bou, 1);
+        // Sticks back, then the mangan penalty of 南
+        assert_eq!(game.score[Fon::Ton as usize].score, 29000);
+        assert_eq!(game.score[Fon::Nan as usize].score, 17000);
+        assert_eq!(game.score[Fon::Shaa as usize].score, 26000);
+        assert_eq!(game.score[Fon::Pee as usize].score, 27000);
+        let total: isize = game.score.iter().map(|s| s.score).sum();
+        assert_eq!(total + 1000 * game.riichi_bou_count() as isize, 100000);
+    }
+
+    #[test]
+    fn test_shuugi_ippatsu_ron() {
+        let mut game: Game =
+            ron::de::from_reader(std::fs::File::open("riichi.ron").unwrap()).unwrap();
+        game.rules.shuugi.ippatsu = 2;
+        // Past the first go-around, so that this is not renhou
+        game.tsumo_cnt = 8;
+        game.throw_tile(Fon::Ton, TehaiIndex::Tsumohai, false);
+        game.change_turn(Fon::Nan);
+        let hupai = game.last_thrown_tile().expect("Has thrown tile");
+
+        // Player 南 is in riichi and wins on ippatsu
+        {
+            let te = game.player_te_mut(Fon::Nan);
+            *te = Te::default();
+            for hai in te_from_string("🀇🀈🀉🀊🀋🀌🀍🀎🀏🀙🀚🀛").unwrap() {
+                te.hai.insert(hai);
+            }
+            te.hai.insert(hupai);
+        }
+        game.players[Fon::Nan as usize].riichi = Some(Riichi {
+            ippatsu: true,
+            double: false,
+            machi: vec![hupai],
+            furiten: false,
+        });
+        let result = game.agari(vec![Fon::Nan], WinningMethod::Ron, None, false);
+        match result {
+            KyokuResult::Agari { chips, .. } => assert_eq!(chips, [-2, 2, 0, 0]),
+            _ => panic!("Expected agari, got {:?}", result),
+        }
+        assert_eq!(game.score[Fon::Ton as usize].chips, -2);
+        assert_eq!(game.score[Fon::Nan as usize].chips, 2);
+    }
+
     #[test]
     fn test_nagashimangan() {
         let game: Game =
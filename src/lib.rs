@@ -0,0 +1,19 @@
+pub mod ai;
+pub mod analysis;
+pub mod bots;
+pub mod external;
+pub mod game;
+mod list;
+pub mod locale;
+pub mod mjai;
+pub mod monte_carlo;
+pub mod mpsz;
+mod points;
+pub mod rating;
+pub mod rules;
+pub mod safety;
+pub mod shanten;
+pub mod simulation;
+pub mod tiles;
+pub mod tournament;
+pub mod yaku;
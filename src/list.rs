@@ -20,16 +20,9 @@ impl<T> AsRef<[T]> for OrderedList<T> {
 }
 
 impl<T: PartialOrd> OrderedList<T> {
+    /// Insert after the elements lower or equal to `element`
     pub fn insert(&mut self, element: T) {
-        let mut index = 0;
-        for item in &self.container {
-            if item <= &element {
-                index += 1;
-                continue;
-            } else {
-                break;
-            }
-        }
+        let index = self.container.partition_point(|item| item <= &element);
         self.container.insert(index, element)
     }
 
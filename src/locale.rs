@@ -0,0 +1,194 @@
+//! Language of user-facing text.
+//!
+//! Every user-facing string exists in Japanese, romaji (Japanese terms in
+//! the latin alphabet) and English.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Locale {
+    #[default]
+    Japanese,
+    Romaji,
+    English,
+}
+
+impl Locale {
+    /// Pick the text of the current locale
+    pub fn pick<T>(self, japanese: T, romaji: T, english: T) -> T {
+        match self {
+            Locale::Japanese => japanese,
+            Locale::Romaji => romaji,
+            Locale::English => english,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseLocaleError {
+    string: String,
+}
+
+impl fmt::Display for ParseLocaleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Unknown locale '{}' (expected ja, romaji or en)",
+            self.string
+        )
+    }
+}
+
+impl Error for ParseLocaleError {}
+
+impl FromStr for Locale {
+    type Err = ParseLocaleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "ja" | "jp" | "japanese" | "日本語" => Ok(Locale::Japanese),
+            "romaji" | "ja-latn" => Ok(Locale::Romaji),
+            "en" | "english" => Ok(Locale::English),
+            _ => Err(ParseLocaleError {
+                string: s.to_owned(),
+            }),
+        }
+    }
+}
+
+/// Value which can be displayed in any [`Locale`].
+pub trait Localize {
+    fn fmt_localized(&self, f: &mut fmt::Formatter<'_>, locale: Locale) -> fmt::Result;
+
+    fn localized(&self, locale: Locale) -> Localized<'_, Self> {
+        Localized {
+            value: self,
+            locale,
+        }
+    }
+}
+
+/// Displays the inner value in the given locale
+pub struct Localized<'a, T: ?Sized> {
+    value: &'a T,
+    locale: Locale,
+}
+
+impl<T: Localize + ?Sized> fmt::Display for Localized<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt_localized(f, self.locale)
+    }
+}
+
+/// Write `rows` as a table, the first column aligned left and the others
+/// right. The first row is the header.
+pub(crate) fn write_table(f: &mut fmt::Formatter<'_>, rows: &[Vec<String>]) -> fmt::Result {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            rows.iter()
+                .filter_map(|row| row.get(column))
+                .map(|cell| width(cell))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    for row in rows {
+        let mut line = String::new();
+        for (column, cell) in row.iter().enumerate() {
+            let padding = " ".repeat(widths[column] - width(cell));
+            if column == 0 {
+                line += &format!("{}{}", cell, padding);
+            } else {
+                line += &format!("  {}{}", padding, cell);
+            }
+        }
+        writeln!(f, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+/// Width of `s` in a terminal, where Japanese characters are full-width
+fn width(s: &str) -> usize {
+    s.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum()
+}
+
+/// Labels of dialogs and buttons of the user interface
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Label {
+    Call,
+    Pass,
+    Chi,
+    Pon,
+    Kan,
+    Ron,
+    Hand,
+    Tsumo,
+    Kyushukyuhai,
+    Riichi,
+    Ankan,
+    Kakan,
+    Ryukyoku,
+    Tenpai,
+    Noten,
+    DeclareTenpai,
+    Ok,
+    Result,
+    FinalResult,
+    Agari,
+    Chips,
+    Chombo,
+    Waits,
+    NoYaku,
+    Safety,
+}
+
+impl Label {
+    pub fn text(self, locale: Locale) -> &'static str {
+        use Label::*;
+        match self {
+            Call => locale.pick("鳴きますか？", "Call?", "Call?"),
+            Pass => locale.pick("パス", "Pass", "Pass"),
+            Chi => locale.pick("チー", "Chi", "Chi"),
+            Pon => locale.pick("ポン", "Pon", "Pon"),
+            Kan => locale.pick("カン", "Kan", "Kan"),
+            Ron => locale.pick("ロン", "Ron", "Ron"),
+            Hand => locale.pick("手牌", "Tehai", "Hand"),
+            Tsumo => locale.pick("ツモ", "Tsumo", "Tsumo"),
+            Kyushukyuhai => locale.pick("九種九牌", "Kyuushu kyuuhai", "Nine terminals"),
+            Riichi => locale.pick("立直", "Riichi", "Riichi"),
+            Ankan => locale.pick("暗槓", "Ankan", "Closed kan"),
+            Kakan => locale.pick("加槓", "Kakan", "Added kan"),
+            Ryukyoku => locale.pick("流局", "Ryuukyoku", "Draw"),
+            Tenpai => locale.pick("聴牌", "Tenpai", "Ready"),
+            Noten => locale.pick("不聴", "Noten", "Not ready"),
+            DeclareTenpai => locale.pick("聴牌しますか？", "Tenpai?", "Declare tenpai?"),
+            Ok => "OK",
+            Result => locale.pick("結果", "Kekka", "Result"),
+            FinalResult => locale.pick("最終結果", "Saishuu kekka", "Final result"),
+            Agari => locale.pick("和了", "Agari", "won with"),
+            Chips => locale.pick("祝儀", "Shuugi", "Chips"),
+            Chombo => locale.pick("錯和", "Chombo", "Chombo"),
+            Waits => locale.pick("待ち", "Machi", "Waits"),
+            NoYaku => locale.pick("役なし", "Yaku nashi", "No yaku"),
+            Safety => locale.pick("安全度", "Anzendo", "Safety"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_locale() {
+        assert_eq!("ja".parse(), Ok(Locale::Japanese));
+        assert_eq!("Romaji".parse(), Ok(Locale::Romaji));
+        assert_eq!("en".parse(), Ok(Locale::English));
+        assert!("fr".parse::<Locale>().is_err());
+    }
+}
@@ -1,6 +1,7 @@
+use std::fmt;
 use std::path::PathBuf;
 
-use log::{debug, info};
+use log::{debug, info, warn};
 
 use clap::Parser;
 use cursive::views::Dialog;
@@ -9,18 +10,19 @@ use cursive::Cursive;
 use cursive::CursiveExt;
 use rand::{rngs::StdRng, SeedableRng};
 
-mod ai;
-mod game;
-mod list;
-mod points;
-mod tiles;
-mod yaku;
+use mahjong::bots::{self, Bot, BotSpec};
+use mahjong::locale::{Label, Locale, Localize};
+use mahjong::rules::Rules;
+use mahjong::{ai, analysis, game, safety, simulation, tiles};
+
+mod ratings_cmd;
+mod score_cmd;
+mod simulate_cmd;
+mod tournament_cmd;
 
 use ai::TehaiIndex;
 
-use crate::ai::dump_caller_bot;
-use crate::ai::null_bot;
-use crate::ai::AiServer;
+use ai::AiServer;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -29,42 +31,177 @@ struct Args {
     #[arg(long)]
     from_state: Option<PathBuf>,
 
-    /// Who is player 1?
-    #[arg(long, value_enum, default_value_t = AI::CursiveHuman)]
-    p1: AI,
+    /// Rules of a new game (.ron file): chombo penalty, shuugi and kiriage
+    /// mangan. Default rules otherwise.
+    #[arg(long, conflicts_with = "from_state")]
+    rules: Option<PathBuf>,
+
+    /// Who is player 1? A bot spec (see --list-bots) or cursive-human
+    #[arg(long, value_parser = parse_player, default_value = "cursive-human")]
+    p1: Player,
     /// Who is player 2?
-    #[arg(long, value_enum, default_value_t = AI::NullBot)]
-    p2: AI,
+    #[arg(long, value_parser = parse_player, default_value = "null")]
+    p2: Player,
     /// Who is player 3?
-    #[arg(long, value_enum, default_value_t = AI::NullBot)]
-    p3: AI,
+    #[arg(long, value_parser = parse_player, default_value = "null")]
+    p3: Player,
     /// Who is player 4?
-    #[arg(long, value_enum, default_value_t = AI::NullBot)]
-    p4: AI,
+    #[arg(long, value_parser = parse_player, default_value = "null")]
+    p4: Player,
+
+    /// Name of the human player in the ratings
+    #[arg(long, default_value = "human")]
+    name: String,
+
+    /// List the bots and their parameters
+    #[arg(long)]
+    list_bots: bool,
+
+    /// Language of the interface: ja (Japanese), romaji or en (English)
+    #[arg(long, global = true, env = "MAHJONG_LOCALE", default_value = "ja")]
+    locale: Locale,
+
+    /// Ratings file, updated after each hanchan (no ratings by default)
+    #[arg(long, global = true, env = "MAHJONG_RATINGS")]
+    ratings: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Score a winning hand
+    Score(score_cmd::ScoreArgs),
+    /// Play many hanchan between bots and print stats
+    Simulate(simulate_cmd::SimulateArgs),
+    /// Play a duplicate tournament between four bots
+    Tournament(tournament_cmd::TournamentArgs),
+    /// Show the leaderboard of the ratings, or the history of players
+    Ratings(ratings_cmd::RatingsArgs),
 }
 
-#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
-enum AI {
+/// Who plays a seat: the interactive interface or a bot of the registry
+#[derive(Debug, Clone)]
+enum Player {
     CursiveHuman,
-    NullBot,
-    DumbCallerBot,
+    Bot { spec: BotSpec, bot: Box<Bot> },
+}
+
+const CURSIVE_HUMAN: &str = "cursive-human";
+
+fn parse_player(s: &str) -> Result<Player, String> {
+    if s == CURSIVE_HUMAN {
+        return Ok(Player::CursiveHuman);
+    }
+    let spec: BotSpec = s.parse()?;
+    let bot = Box::new(Bot::from_spec(&spec)?);
+    Ok(Player::Bot { spec, bot })
 }
 
-fn make_ai_server(ai: AI) -> AiServer {
-    match ai {
-        AI::CursiveHuman => cursive_human(),
-        AI::NullBot => null_bot(),
-        AI::DumbCallerBot => dump_caller_bot(),
+impl fmt::Display for Player {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Player::CursiveHuman => write!(f, "{}", CURSIVE_HUMAN),
+            Player::Bot { spec, .. } => write!(f, "{}", spec),
+        }
     }
 }
 
+/// Number of threads playing hanchan: `requested`, or the number of CPUs.
+/// Only one hanchan at a time can play with a bot listening on an address.
+fn threads(requested: Option<usize>, players: &[Player]) -> Result<usize, String> {
+    let exclusive = players.iter().find(|player| match player {
+        Player::Bot { bot, .. } => bot.is_exclusive(),
+        Player::CursiveHuman => false,
+    });
+    match (exclusive, requested) {
+        (Some(player), Some(threads)) if threads > 1 => Err(format!(
+            "{} listens on a single address and needs --threads 1",
+            player
+        )),
+        (Some(_), _) => Ok(1),
+        (None, Some(threads)) => Ok(threads),
+        (None, None) => Ok(std::thread::available_parallelism()
+            .map(usize::from)
+            .unwrap_or(1)),
+    }
+}
+
+fn make_ai_server(player: &Player, locale: Locale) -> AiServer {
+    match player {
+        Player::CursiveHuman => cursive_human(locale),
+        Player::Bot { bot, .. } => bot.server(),
+    }
+}
+
+/// Print the bots and their parameters, for `--list-bots`
+fn list_bots() {
+    println!("{}", CURSIVE_HUMAN);
+    println!("    Human player in the terminal (not in simulations)");
+    for info in bots::BOTS {
+        println!("{}", info.name);
+        println!("    {}", info.description);
+        for param in info.params {
+            println!(
+                "    {}={} (default {}): {}",
+                param.name, param.values, param.default, param.description
+            );
+        }
+    }
+    println!();
+    println!("Spec: name[:key=value,...] or @config.ron");
+}
+
 fn main() {
     let mut log_builder = env_logger::Builder::from_default_env();
     log_builder.target(env_logger::Target::Stderr).init();
 
+    let args = Args::parse();
+
+    let locale = args.locale;
+    if args.list_bots {
+        list_bots();
+        return;
+    }
+    let ratings = args.ratings.as_deref();
+    if let Some(command) = args.command {
+        let result = match command {
+            Command::Score(args) => score_cmd::run(args, locale),
+            Command::Simulate(args) => simulate_cmd::run(args, ratings, locale),
+            Command::Tournament(args) => tournament_cmd::run(args, ratings, locale),
+            Command::Ratings(args) => ratings_cmd::run(args, ratings, locale),
+        };
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let rules = args
+        .rules
+        .as_deref()
+        .map(Rules::load)
+        .transpose()
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+
     test_print_all_chars();
 
-    let args = Args::parse();
+    // A game loaded from a state did not start at the first kyoku
+    let rated = match ratings {
+        Some(_) if args.from_state.is_some() => {
+            warn!("A game loaded from a state is not rated");
+            None
+        }
+        _ => ratings_cmd::RatingsFile::open(ratings).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }),
+    };
 
     let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
     let mut game: game::Game = if let Some(file) = args.from_state {
@@ -72,28 +209,46 @@ fn main() {
     } else {
         game::Game::new(&mut rng)
     };
+    if let Some(rules) = rules {
+        game.set_rules(rules);
+    }
 
     game.play_hanchan(
         [
-            make_ai_server(args.p1),
-            make_ai_server(args.p2),
-            make_ai_server(args.p3),
-            make_ai_server(args.p4),
+            make_ai_server(&args.p1, locale),
+            make_ai_server(&args.p2, locale),
+            make_ai_server(&args.p3, locale),
+            make_ai_server(&args.p4, locale),
         ],
         &mut rng,
     );
 
     // Dump table before ending
-    println!("{}", game);
+    println!("{}", game.localized(locale));
+    println!("\n{}", final_result_repr(&game, locale));
+
+    if let Some(rated) = rated {
+        let players = [&args.p1, &args.p2, &args.p3, &args.p4];
+        let names = players.map(|player| match player {
+            Player::CursiveHuman => args.name.clone(),
+            Player::Bot { spec, .. } => spec.to_string(),
+        });
+        let names = [0, 1, 2, 3].map(|i| names[i].as_str());
+        let (scores, placements) = simulation::final_results(&game);
+        if let Err(e) = rated.record([(names, scores, placements)]) {
+            eprintln!("{}", e);
+        }
+    }
 }
 
-fn cursive_human() -> ai::AiServer {
+fn cursive_human(locale: Locale) -> ai::AiServer {
     let (server, client) = ai::channel();
     std::thread::spawn(move || {
         debug!("Init..");
         let rx = client.rx;
         let tx_call = client.tx_call;
         let tx_turn = client.tx_turn;
+        let tx_tenpai = client.tx_tenpai;
 
         let quit = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
         let mut instant;
@@ -119,19 +274,32 @@ fn cursive_human() -> ai::AiServer {
                 game.to_string_repr()
             );
 
-            let display = format!("{}", &game);
+            let display = format!(
+                "{}\n{}{}",
+                game.localized(locale),
+                waits_repr(&game, player, locale),
+                safety_repr(&game, player, locale)
+            );
             siv.add_layer(TextView::new(display));
 
+            let end = matches!(request, game::Request::EndGame);
             match request {
-                game::Request::EndGame => return,
+                game::Request::EndGame => {
+                    let dialog = Dialog::text(final_result_repr(&game, locale))
+                        .title(Label::FinalResult.text(locale))
+                        .button(Label::Ok.text(locale), |s| s.quit());
+                    siv.add_layer(dialog);
+
+                    instant = None;
+                }
                 game::Request::Refresh => {
                     snapshot(&game);
                     instant = Some(std::time::Instant::now());
                 }
                 game::Request::Call(calls) => {
-                    let mut dialog = Dialog::text("").title("Call?");
+                    let mut dialog = Dialog::text("").title(Label::Call.text(locale));
                     let tx_call_ = tx_call.clone();
-                    dialog = dialog.button("Pass", move |s| {
+                    dialog = dialog.button(Label::Pass.text(locale), move |s| {
                         tx_call_.send(None).expect("Sent call result!");
                         s.quit();
                     });
@@ -148,7 +316,7 @@ fn cursive_human() -> ai::AiServer {
                                     let tile2 =
                                         game.player_te(player).nth(chi[1]).expect("Has tile");
                                     dialog = dialog.button(
-                                        format!("Chi {}{}", tile1, tile2),
+                                        format!("{} {}{}", Label::Chi.text(locale), tile1, tile2),
                                         move |s| {
                                             tx_call
                                                 .send(Some(ai::Call::Chi { index: chi }))
@@ -160,7 +328,7 @@ fn cursive_human() -> ai::AiServer {
                             }
                             ai::PossibleCall::Pon => {
                                 let tx_call = tx_call.clone();
-                                dialog = dialog.button("Pon", move |s| {
+                                dialog = dialog.button(Label::Pon.text(locale), move |s| {
                                     debug!("Send pon call");
                                     tx_call
                                         .send(Some(ai::Call::Pon))
@@ -170,7 +338,7 @@ fn cursive_human() -> ai::AiServer {
                             }
                             ai::PossibleCall::Kan => {
                                 let tx_call = tx_call.clone();
-                                dialog = dialog.button("Kan", move |s| {
+                                dialog = dialog.button(Label::Kan.text(locale), move |s| {
                                     tx_call
                                         .send(Some(ai::Call::Kan))
                                         .expect("Sent call result!");
@@ -179,7 +347,7 @@ fn cursive_human() -> ai::AiServer {
                             }
                             ai::PossibleCall::Ron => {
                                 let tx_call = tx_call.clone();
-                                dialog = dialog.button("Ron", move |s| {
+                                dialog = dialog.button(Label::Ron.text(locale), move |s| {
                                     tx_call
                                         .send(Some(ai::Call::Ron))
                                         .expect("Sent call result!");
@@ -199,10 +367,10 @@ fn cursive_human() -> ai::AiServer {
                     can_shominkan,
                     can_ankan,
                 }) => {
-                    let mut dialog = Dialog::text("").title("Hand");
+                    let mut dialog = Dialog::text("").title(Label::Hand.text(locale));
                     if can_tsumo {
                         let tx_turn = tx_turn.clone();
-                        dialog = dialog.button("Tsumo", move |s| {
+                        dialog = dialog.button(Label::Tsumo.text(locale), move |s| {
                             tx_turn
                                 .send(ai::TurnResult::Tsumo)
                                 .expect("Sent turn result!");
@@ -211,7 +379,7 @@ fn cursive_human() -> ai::AiServer {
                     }
                     if can_kyusyukyuhai {
                         let tx_turn = tx_turn.clone();
-                        dialog = dialog.button("Kyushukyuhai", move |s| {
+                        dialog = dialog.button(Label::Kyushukyuhai.text(locale), move |s| {
                             tx_turn
                                 .send(ai::TurnResult::Kyusyukyuhai)
                                 .expect("Sent turn result!");
@@ -224,27 +392,33 @@ fn cursive_human() -> ai::AiServer {
                             match throwable {
                                 game::ThrowableOnRiichi::Te(index) => {
                                     let tile = game.player_te(player).nth(index).expect("Has tile");
-                                    dialog = dialog.button(format!("Riichi {}", tile), move |s| {
-                                        tx_turn
-                                            .send(ai::TurnResult::ThrowHai {
-                                                index: TehaiIndex::Tehai(index),
-                                                riichi: true,
-                                            })
-                                            .expect("Sent turn result!");
-                                        s.quit();
-                                    })
+                                    dialog = dialog.button(
+                                        format!("{} {}", Label::Riichi.text(locale), tile),
+                                        move |s| {
+                                            tx_turn
+                                                .send(ai::TurnResult::ThrowHai {
+                                                    index: TehaiIndex::Tehai(index),
+                                                    riichi: true,
+                                                })
+                                                .expect("Sent turn result!");
+                                            s.quit();
+                                        },
+                                    )
                                 }
                                 game::ThrowableOnRiichi::Tsumohai => {
                                     let tile = game.player_tsumo(player).expect("Has tsumohai");
-                                    dialog = dialog.button(format!("Riichi {}", tile), move |s| {
-                                        tx_turn
-                                            .send(ai::TurnResult::ThrowHai {
-                                                index: TehaiIndex::Tsumohai,
-                                                riichi: true,
-                                            })
-                                            .expect("Sent turn result!");
-                                        s.quit();
-                                    })
+                                    dialog = dialog.button(
+                                        format!("{} {}", Label::Riichi.text(locale), tile),
+                                        move |s| {
+                                            tx_turn
+                                                .send(ai::TurnResult::ThrowHai {
+                                                    index: TehaiIndex::Tsumohai,
+                                                    riichi: true,
+                                                })
+                                                .expect("Sent turn result!");
+                                            s.quit();
+                                        },
+                                    )
                                 }
                             };
                         }
@@ -253,24 +427,30 @@ fn cursive_human() -> ai::AiServer {
                         for hai in can_ankan {
                             let tx_turn = tx_turn.clone();
                             let index = game.player_te_(player).index(hai).expect("Has ankan tile");
-                            dialog = dialog.button(format!("AnKan {}", hai), move |s| {
-                                tx_turn
-                                    .send(ai::TurnResult::Ankan { index })
-                                    .expect("Sent turn result!");
-                                s.quit();
-                            })
+                            dialog = dialog.button(
+                                format!("{} {}", Label::Ankan.text(locale), hai),
+                                move |s| {
+                                    tx_turn
+                                        .send(ai::TurnResult::Ankan { index })
+                                        .expect("Sent turn result!");
+                                    s.quit();
+                                },
+                            )
                         }
                     }
                     if !can_shominkan.is_empty() {
                         for hai in can_shominkan {
                             let tx_turn = tx_turn.clone();
                             let index = game.player_te_(player).index(hai).expect("Has kakan tile");
-                            dialog = dialog.button(format!("Kakan {}", hai), move |s| {
-                                tx_turn
-                                    .send(ai::TurnResult::Kakan { index })
-                                    .expect("Sent turn result!");
-                                s.quit();
-                            })
+                            dialog = dialog.button(
+                                format!("{} {}", Label::Kakan.text(locale), hai),
+                                move |s| {
+                                    tx_turn
+                                        .send(ai::TurnResult::Kakan { index })
+                                        .expect("Sent turn result!");
+                                    s.quit();
+                                },
+                            )
                         }
                     }
                     if !game.player_is_riichi(player) {
@@ -304,28 +484,80 @@ fn cursive_human() -> ai::AiServer {
                     instant = None;
                 }
 
+                game::Request::DeclareTenpai => {
+                    let mut dialog = Dialog::text(Label::DeclareTenpai.text(locale))
+                        .title(Label::Ryukyoku.text(locale));
+                    let tx_tenpai_ = tx_tenpai.clone();
+                    dialog = dialog.button(Label::Tenpai.text(locale), move |s| {
+                        tx_tenpai_.send(true).expect("Sent tenpai declaration!");
+                        s.quit();
+                    });
+                    let tx_tenpai_ = tx_tenpai.clone();
+                    dialog = dialog.button(Label::Noten.text(locale), move |s| {
+                        tx_tenpai_.send(false).expect("Sent tenpai declaration!");
+                        s.quit();
+                    });
+                    siv.add_layer(dialog);
+
+                    instant = None;
+                }
+                game::Request::RevealTenpai(players) => {
+                    let mut display = format!("{}:", Label::Tenpai.text(locale));
+                    for p in players {
+                        display.push(' ');
+                        display.push_str(p.name(locale));
+                    }
+                    let mut dialog = Dialog::text(display).title(Label::Ryukyoku.text(locale));
+                    dialog = dialog.button(Label::Ok.text(locale), |s| s.quit());
+                    siv.add_layer(dialog);
+
+                    instant = None;
+                }
+
                 game::Request::DisplayScore(result) => {
                     use game::KyokuResult;
                     let mut display = String::new();
                     match result {
-                        KyokuResult::Agari { winners, .. } => {
-                            for winner in winners {
-                                info!("Player {} won with {:?}", winner.0 as usize, &winner.1);
-                                let yaku: Vec<_> =
-                                    winner.1.into_iter().map(|yaku| yaku.name()).collect();
+                        KyokuResult::Agari { winners, chips, .. } => {
+                            for (winner, breakdown) in winners {
+                                info!("Player {} won with {:?}", winner as usize, &breakdown);
                                 display.push_str(&format!(
-                                    "{} won with {:?}\n",
-                                    winner.0.to_char(),
-                                    yaku
+                                    "{} {}\n{}\n",
+                                    winner.to_char(),
+                                    Label::Agari.text(locale),
+                                    breakdown.localized(locale)
                                 ));
                             }
+                            if chips.iter().any(|c| *c != 0) {
+                                display.push_str(Label::Chips.text(locale));
+                                display.push(':');
+                                let mut fon = tiles::Fon::Ton;
+                                for c in chips {
+                                    display.push_str(&format!(" {} {:+}", fon.to_char(), c));
+                                    fon = fon.next();
+                                }
+                                display.push('\n');
+                            }
                         }
                         KyokuResult::Ryukyoku { .. } => {
-                            display.push_str("Ryukyoku");
+                            display.push_str(Label::Ryukyoku.text(locale));
+                        }
+                        KyokuResult::Chombo {
+                            offenders, penalty, ..
+                        } => {
+                            for (offender, reason) in offenders {
+                                display.push_str(&format!(
+                                    "{} {}: {} ({})\n",
+                                    offender.to_char(),
+                                    Label::Chombo.text(locale),
+                                    reason.name(locale),
+                                    penalty.localized(locale)
+                                ));
+                            }
                         }
                     }
-                    let mut dialog = Dialog::text(display).title("End");
-                    dialog = dialog.button("OK", |s| s.quit());
+                    let mut dialog = Dialog::text(display).title(Label::Result.text(locale));
+                    dialog = dialog.button(Label::Ok.text(locale), |s| s.quit());
                     siv.add_layer(dialog);
 
                     instant = None;
@@ -352,6 +584,9 @@ fn cursive_human() -> ai::AiServer {
                     break;
                 }
             }
+            if end {
+                return;
+            }
         }
         debug!("Quitted...");
 
@@ -361,6 +596,101 @@ fn cursive_human() -> ai::AiServer {
     server
 }
 
+/// Placement, score and chips of each player at the end of a hanchan
+fn final_result_repr(game: &game::Game, locale: Locale) -> String {
+    let (scores, placements) = simulation::final_results(game);
+    let mut players = [0, 1, 2, 3];
+    players.sort_by_key(|p| placements[*p]);
+    let shuugi = game.rules().shuugi.is_enabled();
+    let mut out = String::new();
+    for p in players {
+        let seat = tiles::Fon::Ton.next_nth((p + game.kyoku()) % 4);
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!(
+            "{}. {} {}",
+            placements[p],
+            seat.name(locale),
+            scores[p]
+        ));
+        if shuugi {
+            out.push_str(&format!(
+                "  {} {:+}",
+                Label::Chips.text(locale),
+                game.player_chips(seat)
+            ));
+        }
+    }
+    out
+}
+
+/// Waits of the te of the player, with the points won on each of them
+fn waits_repr(game: &game::Game, player: tiles::Fon, locale: Locale) -> String {
+    let view = game.player_view(player);
+    let te = view.te();
+    let waits = analysis::analyze_waits(te.hai(), te.fuuro(), &view);
+    if waits.is_empty() {
+        return String::new();
+    }
+    let points = |breakdown: &mahjong::yaku::ScoreBreakdown| {
+        if breakdown.is_agari() {
+            breakdown.payments[player as usize].to_string()
+        } else {
+            Label::NoYaku.text(locale).to_owned()
+        }
+    };
+    let mut out = format!("{}:", Label::Waits.text(locale));
+    for wait in waits {
+        out.push_str(&format!(
+            "\n {} x{} {} ({} {} / {} {})",
+            wait.hai,
+            wait.remaining,
+            wait.machi.name(locale),
+            Label::Ron.text(locale),
+            points(&wait.ron),
+            Label::Tsumo.text(locale),
+            points(&wait.tsumo),
+        ));
+    }
+    out
+}
+
+/// Safety of the tiles of the player against each opponent in riichi,
+/// safest first
+fn safety_repr(game: &game::Game, player: tiles::Fon, locale: Locale) -> String {
+    let view = game.player_view(player);
+    let te = view.te();
+    let mut kinds: Vec<tiles::TileKind> = te
+        .hai()
+        .iter()
+        .chain(te.get(TehaiIndex::Tsumohai).iter())
+        .map(|hai| (*hai).into())
+        .collect();
+    kinds.sort();
+    kinds.dedup();
+
+    let mut out = String::new();
+    let mut target = player.next();
+    while target != player {
+        if view.is_riichi(target) {
+            let safety = safety::analyze_safety(&view, target);
+            let mut tiles: Vec<_> = kinds.iter().map(|kind| &safety[kind.index()]).collect();
+            tiles.sort_by_key(|tile| tile.danger);
+            out.push_str(&format!(
+                "\n{} {}:",
+                Label::Safety.text(locale),
+                target.to_char()
+            ));
+            for tile in tiles {
+                out.push_str(&format!(" {}{}", tile.hai, tile.category.name(locale)));
+            }
+        }
+        target = target.next();
+    }
+    out
+}
+
 fn test_print_all_chars() {
     for hai in tiles::make_all_tiles().iter() {
         print!("{}", hai);
@@ -0,0 +1,977 @@
+//! Bots speaking the [MJAI](https://github.com/gimite/mjai) protocol, over
+//! the stdin and stdout of a child process or as the client of a local TCP
+//! server.
+//!
+//! The adapter rebuilds MJAI events (tsumo, dahai, calls, dora...) from the
+//! game snapshots it is sent, sends them one per line and reads one response
+//! per event. The response to the last event is the decision of the bot
+//! when the game asks for one: a `dahai` or `reach` after its own tsumo, a
+//! `pon` or `hora` after a dahai... Like with [`exec_bot`](super::external::exec_bot),
+//! a bot which fails is stopped and its seat plays on as a null bot.
+//!
+//! Players are numbered from the first oya, 0, as MJAI actors.
+
+use std::fmt;
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use super::ai::{channel, AiServer, Call, PossibleCall, TehaiIndex, TurnResult};
+use super::external::{ask, call_result, tile, tsumogiri, turn_result, Connection};
+use super::game::{
+    Direction, Fuuro, Game, GameRequest, KantsuInner, KyokuResult, PlayerView, PossibleActions,
+    Request, SuteHai,
+};
+use super::tiles::{Fon, Hai};
+
+const WINDS: [Fon; 4] = [Fon::Ton, Fon::Nan, Fon::Shaa, Fon::Pee];
+
+/// Letters of the honor tiles, east to chun
+const HONORS: [&str; 7] = ["E", "S", "W", "N", "P", "F", "C"];
+
+/// How to reach an MJAI bot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transport {
+    /// Program and its arguments, split on whitespace
+    Stdio { command: String },
+    /// Listen on `address` and wait for a client to join
+    Tcp { address: String, wait: Duration },
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Transport::Stdio { command } => write!(f, "mjai:{}", command),
+            Transport::Tcp { address, .. } => write!(f, "mjai-tcp:{}", address),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MjaiParams {
+    pub transport: Transport,
+    /// Longest wait for each response
+    pub timeout: Duration,
+}
+
+impl Default for MjaiParams {
+    fn default() -> Self {
+        MjaiParams {
+            transport: Transport::Tcp {
+                address: "127.0.0.1:11600".to_owned(),
+                wait: Duration::from_secs(60),
+            },
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// AI played by an MJAI bot, connected anew for each game
+pub fn mjai_bot(params: MjaiParams) -> AiServer {
+    let (server, client) = channel();
+    std::thread::spawn(move || {
+        let name = params.transport.to_string();
+        let mut connection = match connect(&params) {
+            Ok(connection) => Some(connection),
+            Err(e) => {
+                warn!("Bot '{}' did not start: {}", name, e);
+                None
+            }
+        };
+        let mut tracker = Tracker::default();
+        let mut last = None;
+        loop {
+            let GameRequest {
+                game,
+                request,
+                player,
+            } = match client.rx.recv() {
+                Ok(request) => request,
+                // The game is over
+                Err(_) => return,
+            };
+            let mut events = tracker.events(&game, player, &request);
+            let end_game = match request {
+                Request::EndGame => events.pop(),
+                _ => None,
+            };
+            for event in &events {
+                last = ask(&mut connection, &name, |connection| {
+                    connection.send(event)?;
+                    connection.receive::<Response>()
+                });
+            }
+            match request {
+                Request::Call(possible_calls) => {
+                    let response = last.take();
+                    let call = ask(&mut connection, &name, |_| {
+                        mjai_call(response, &possible_calls, &game, player)
+                    });
+                    client.tx_call.send(call.flatten()).expect("Sent!");
+                }
+                Request::DoTurn(possible_actions) => {
+                    let response = last.take();
+                    let result = ask(&mut connection, &name, |connection| {
+                        mjai_turn(
+                            connection,
+                            &mut tracker,
+                            response,
+                            &possible_actions,
+                            &game,
+                            player,
+                        )
+                    });
+                    let result = result.unwrap_or_else(|| tsumogiri(&game, player));
+                    tracker.tsumogiri = matches!(
+                        result,
+                        TurnResult::ThrowHai {
+                            index: TehaiIndex::Tsumohai,
+                            ..
+                        }
+                    );
+                    client.tx_turn.send(result).expect("Sent!");
+                }
+                // MJAI bots are not asked, tenpai is always declared
+                Request::DeclareTenpai => client.tx_tenpai.send(true).expect("Sent!"),
+                Request::EndGame => {
+                    if let (Some(connection), Some(end_game)) = (connection.take(), end_game) {
+                        connection.close(&end_game);
+                    }
+                    return;
+                }
+                _ => {}
+            }
+        }
+    });
+    server
+}
+
+/// Start the bot, or wait for it to join
+fn connect(params: &MjaiParams) -> Result<Connection, String> {
+    match &params.transport {
+        Transport::Stdio { command } => Connection::spawn(command, params.timeout),
+        Transport::Tcp { address, wait } => {
+            let listener = TcpListener::bind(address)
+                .map_err(|e| format!("Cannot listen on {}: {}", address, e))?;
+            listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+            info!("Waiting for an MJAI client on {}", address);
+            let start = Instant::now();
+            let stream = loop {
+                match listener.accept() {
+                    Ok((stream, _)) => break stream,
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                        if start.elapsed() >= *wait {
+                            return Err(format!("No client in {} ms", wait.as_millis()));
+                        }
+                        std::thread::sleep(Duration::from_millis(10));
+                    }
+                    Err(e) => return Err(e.to_string()),
+                }
+            };
+            stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+            let mut connection = Connection::tcp(stream, params.timeout)?;
+            connection.send(&Event::Hello {
+                protocol: "mjsonp",
+                protocol_version: 3,
+            })?;
+            let join: Response = connection.receive()?;
+            if join.kind != "join" {
+                return Err(format!("Expected join, got {}", join.kind));
+            }
+            Ok(connection)
+        }
+    }
+}
+
+/// Call answered by `response`, the response to the last dahai or kan
+fn mjai_call(
+    response: Option<Response>,
+    possible_calls: &[PossibleCall],
+    game: &Game,
+    player: Fon,
+) -> Result<Option<Call>, String> {
+    let response = match response {
+        Some(response) => response,
+        None => return Ok(None),
+    };
+    let call = match response.kind.as_str() {
+        "chi" => "chi",
+        "pon" => "pon",
+        "daiminkan" => "kan",
+        "hora" => "ron",
+        _ => return Ok(None),
+    };
+    let tiles = response
+        .consumed
+        .iter()
+        .map(|pai| parse_pai(pai).map(tile))
+        .collect::<Result<Vec<_>, _>>()?;
+    call_result(possible_calls, game.player_te_(player), Some(call), &tiles)
+}
+
+/// Turn answered by `response`, the response to the last tsumo or call
+fn mjai_turn(
+    connection: &mut Connection,
+    tracker: &mut Tracker,
+    response: Option<Response>,
+    possible_actions: &PossibleActions,
+    game: &Game,
+    player: Fon,
+) -> Result<TurnResult, String> {
+    let response = response.ok_or("No response")?;
+    let pai = |response: &Response| -> Result<Hai, String> {
+        parse_pai(response.pai.as_deref().ok_or("No pai")?)
+    };
+    let discard = |response: &Response, riichi: bool| {
+        let hai = if response.tsumogiri {
+            None
+        } else {
+            Some(pai(response)?)
+        };
+        turn_result(possible_actions, game, player, "discard", hai, riichi)
+    };
+    match response.kind.as_str() {
+        "dahai" => discard(&response, false),
+        "reach" => {
+            // The reach is echoed, then the bot chooses its tile
+            tracker.reach[tracker.id] = true;
+            connection.send(&Event::Reach { actor: tracker.id })?;
+            let response: Response = connection.receive()?;
+            if response.kind != "dahai" {
+                return Err(format!("Expected dahai after reach, got {}", response.kind));
+            }
+            discard(&response, true)
+        }
+        "hora" => turn_result(possible_actions, game, player, "tsumo", None, false),
+        "ankan" => {
+            let consumed = response.consumed.first().ok_or("No consumed")?;
+            let hai = parse_pai(consumed)?;
+            turn_result(possible_actions, game, player, "ankan", Some(hai), false)
+        }
+        "kakan" => turn_result(
+            possible_actions,
+            game,
+            player,
+            "kakan",
+            Some(pai(&response)?),
+            false,
+        ),
+        "ryukyoku" => turn_result(possible_actions, game, player, "kyusyukyuhai", None, false),
+        other => Err(format!("Cannot {}", other)),
+    }
+}
+
+/// Name of `hai` in MJAI, e.g. `5mr` for a red five or `E` for east
+pub fn pai(hai: Hai) -> String {
+    let mpsz = tile(hai);
+    let (number, suit) = mpsz.split_at(1);
+    match (number, suit) {
+        (_, "z") => HONORS[number.parse::<usize>().expect("Honor number") - 1].to_owned(),
+        ("0", _) => format!("5{}r", suit),
+        _ => mpsz,
+    }
+}
+
+/// Tile named `s` in MJAI
+pub fn parse_pai(s: &str) -> Result<Hai, String> {
+    let mpsz = match HONORS.iter().position(|honor| *honor == s) {
+        Some(i) => format!("{}z", i + 1),
+        None => match s.strip_suffix('r') {
+            Some(five) if five.starts_with('5') => five.replacen('5', "0", 1),
+            _ => s.to_owned(),
+        },
+    };
+    mpsz.parse()
+        .map_err(|_| format!("Bad pai '{}'", s))
+        .and_then(|hai: Hai| {
+            if pai(hai) == s {
+                Ok(hai)
+            } else {
+                Err(format!("Bad pai '{}'", s))
+            }
+        })
+}
+
+/// MJAI event, one per line. Arrays of 4 are indexed by actor.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Event {
+    Hello {
+        protocol: &'static str,
+        protocol_version: u32,
+    },
+    StartGame {
+        id: usize,
+        names: [String; 4],
+    },
+    StartKyoku {
+        bakaze: String,
+        kyoku: usize,
+        honba: usize,
+        kyotaku: usize,
+        oya: usize,
+        dora_marker: String,
+        tehais: [Vec<String>; 4],
+        scores: [isize; 4],
+    },
+    Tsumo {
+        actor: usize,
+        pai: String,
+    },
+    Dahai {
+        actor: usize,
+        pai: String,
+        tsumogiri: bool,
+    },
+    Chi {
+        actor: usize,
+        target: usize,
+        pai: String,
+        consumed: Vec<String>,
+    },
+    Pon {
+        actor: usize,
+        target: usize,
+        pai: String,
+        consumed: Vec<String>,
+    },
+    Daiminkan {
+        actor: usize,
+        target: usize,
+        pai: String,
+        consumed: Vec<String>,
+    },
+    Kakan {
+        actor: usize,
+        pai: String,
+        consumed: Vec<String>,
+    },
+    Ankan {
+        actor: usize,
+        consumed: Vec<String>,
+    },
+    Dora {
+        dora_marker: String,
+    },
+    Reach {
+        actor: usize,
+    },
+    ReachAccepted {
+        actor: usize,
+        deltas: [isize; 4],
+        scores: [isize; 4],
+    },
+    Hora {
+        actor: usize,
+        target: usize,
+        pai: String,
+        deltas: [isize; 4],
+        scores: [isize; 4],
+    },
+    Ryukyoku {
+        reason: &'static str,
+        tenpais: [bool; 4],
+        deltas: [isize; 4],
+        scores: [isize; 4],
+    },
+    EndKyoku,
+    EndGame {
+        scores: [isize; 4],
+    },
+}
+
+/// Response to an event, only the fields used by the adapter
+#[derive(Debug, Clone, Deserialize)]
+struct Response {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    pai: Option<String>,
+    #[serde(default)]
+    consumed: Vec<String>,
+    #[serde(default)]
+    tsumogiri: bool,
+}
+
+/// What the bot was told so far, to find the events between two snapshots.
+/// Arrays of 4 are indexed by actor.
+#[derive(Debug, Default)]
+struct Tracker {
+    /// Actor of the bot
+    id: usize,
+    started: bool,
+    in_kyoku: bool,
+    /// Bakaze, kyoku and honba of the current kyoku
+    kyoku: (usize, usize, usize),
+    tsumo_cnt: usize,
+    dora: usize,
+    fuuro: [Vec<Fuuro>; 4],
+    /// Tiles discarded, called ones included
+    discards: [usize; 4],
+    reach: [bool; 4],
+    reach_accepted: [bool; 4],
+    scores: [isize; 4],
+    tenpais: [bool; 4],
+    /// Actor who plays after the last dahai
+    next: usize,
+    /// Kan which may be robbed (槍槓), whose rinshan tsumo is not sent yet
+    rinshan: Option<(usize, Hai)>,
+    /// Whether the last discard of the bot was its drawn tile
+    tsumogiri: bool,
+}
+
+impl Tracker {
+    /// Events since the last snapshot, and the results sent with `request`
+    fn events(&mut self, game: &Game, player: Fon, request: &Request) -> Vec<Event> {
+        let view = game.player_view(player);
+        let actor = |wind: Fon| (wind as usize + 4 - game.kyoku() % 4) % 4;
+        let wind = |actor: usize| WINDS[(actor + game.kyoku()) % 4];
+        let scores = || [0, 1, 2, 3].map(|a| game.player_score(wind(a)));
+        let mut events = vec![];
+
+        let bakaze = view.win_context().bakaze;
+        let kyoku = (bakaze as usize, game.kyoku(), game.honba());
+        let ended = matches!(request, Request::EndGame)
+            || kyoku != self.kyoku
+            || game.tsumo_cnt() < self.tsumo_cnt;
+        if self.in_kyoku && ended {
+            // Aborted without a result, e.g. by kyuushu kyuuhai
+            events.push(Event::Ryukyoku {
+                reason: "abort",
+                tenpais: [false; 4],
+                deltas: [0; 4],
+                scores: scores(),
+            });
+            events.push(Event::EndKyoku);
+            self.in_kyoku = false;
+        }
+        if let Request::EndGame = request {
+            events.push(Event::EndGame { scores: scores() });
+            return events;
+        }
+        if !self.in_kyoku {
+            let id = actor(player);
+            if !self.started {
+                events.push(Event::StartGame {
+                    id,
+                    names: [1, 2, 3, 4].map(|i| format!("p{}", i)),
+                });
+            }
+            *self = Tracker {
+                id,
+                started: true,
+                in_kyoku: true,
+                kyoku,
+                tsumo_cnt: game.tsumo_cnt(),
+                dora: 1,
+                scores: scores(),
+                next: actor(Fon::Ton),
+                ..Default::default()
+            };
+            let mut tehais = [0, 1, 2, 3].map(|_| vec!["?".to_owned(); 13]);
+            tehais[id] = view.te().hai().iter().map(|hai| pai(*hai)).collect();
+            events.push(Event::StartKyoku {
+                bakaze: HONORS[bakaze as usize].to_owned(),
+                kyoku: game.kyoku() + 1,
+                honba: game.honba(),
+                kyotaku: game.riichi_bou_count(),
+                oya: actor(Fon::Ton),
+                dora_marker: pai(game.dora_indicator()[0]),
+                tehais,
+                scores: self.scores,
+            });
+        }
+
+        for a in 0..4 {
+            if view.is_riichi(wind(a)) && !self.reach_accepted[a] {
+                self.reach_accepted[a] = true;
+                let mut deltas = [0; 4];
+                deltas[a] = -1000;
+                self.scores = scores();
+                events.push(Event::ReachAccepted {
+                    actor: a,
+                    deltas,
+                    scores: self.scores,
+                });
+            }
+        }
+        let id = self.id;
+        let hidden = |a: usize, hai: Option<Hai>| match hai {
+            Some(hai) if a == id => pai(hai),
+            _ => "?".to_owned(),
+        };
+        let indicators = game.dora_indicator();
+        // A kan reveals its dora before the rinshan tsumo, which is only
+        // drawn once nobody robs the kan
+        let rinshan = |tracker: &mut Tracker, events: &mut Vec<Event>, a: usize| {
+            if tracker.dora < indicators.len() {
+                events.push(Event::Dora {
+                    dora_marker: pai(indicators[tracker.dora]),
+                });
+                tracker.dora += 1;
+            }
+            events.push(Event::Tsumo {
+                actor: a,
+                pai: hidden(a, game.player_tsumo(wind(a))),
+            });
+        };
+        let robbed = matches!(request, Request::DisplayScore(_));
+        if !robbed {
+            if let Some((a, _)) = self.rinshan.take() {
+                rinshan(self, &mut events, a);
+            }
+        }
+
+        let mut draws = game.tsumo_cnt() - self.tsumo_cnt;
+        self.tsumo_cnt = game.tsumo_cnt();
+        for i in 0..4 {
+            let a = (self.next + i) % 4;
+            let fuuro = view.fuuro(wind(a));
+            let discards = discard_count(&view, wind(a));
+            let melds: Vec<_> = fuuro
+                .iter()
+                .enumerate()
+                .filter_map(|(j, meld)| meld_event(self.fuuro[a].get(j), meld, wind(a), &actor))
+                .collect();
+            if melds.is_empty() && discards == self.discards[a] {
+                continue;
+            }
+
+            let call_in = melds.first().map_or(false, |(event, _)| {
+                matches!(
+                    event,
+                    Event::Chi { .. } | Event::Pon { .. } | Event::Daiminkan { .. }
+                )
+            });
+            if !call_in && draws > 0 {
+                draws -= 1;
+                events.push(Event::Tsumo {
+                    actor: a,
+                    pai: "?".to_owned(),
+                });
+            }
+            for (event, kan) in melds {
+                events.push(event);
+                if let Some(hai) = kan {
+                    if let Request::Call(_) = request {
+                        self.rinshan = Some((a, hai));
+                    } else if !robbed {
+                        rinshan(self, &mut events, a);
+                    }
+                }
+            }
+
+            if discards > self.discards[a] {
+                if let Some(sutehai) = view.river(wind(a)).last() {
+                    if let (SuteHai::Riichi(_), false) = (sutehai, self.reach[a]) {
+                        self.reach[a] = true;
+                        events.push(Event::Reach { actor: a });
+                    }
+                    events.push(Event::Dahai {
+                        actor: a,
+                        pai: pai(sutehai.hai()),
+                        tsumogiri: a == self.id && self.tsumogiri,
+                    });
+                }
+                self.next = (a + 1) % 4;
+            }
+            self.fuuro[a] = fuuro.to_vec();
+            self.discards[a] = discards;
+        }
+        // Drawn by a player who did nothing else yet, e.g. the bot itself
+        if draws > 0 {
+            if let Some(a) = (0..4).find(|a| game.player_tsumo(wind(*a)).is_some()) {
+                events.push(Event::Tsumo {
+                    actor: a,
+                    pai: hidden(a, game.player_tsumo(wind(a))),
+                });
+            }
+        }
+        // Dora revealed after the discard of a kan
+        if self.rinshan.is_none() {
+            while self.dora < indicators.len() {
+                events.push(Event::Dora {
+                    dora_marker: pai(indicators[self.dora]),
+                });
+                self.dora += 1;
+            }
+        }
+
+        match request {
+            Request::RevealTenpai(players) => {
+                for p in players {
+                    self.tenpais[actor(*p)] = true;
+                }
+            }
+            Request::DisplayScore(result) => {
+                let now = scores();
+                let mut deltas = [0; 4];
+                for a in 0..4 {
+                    deltas[a] = now[a] - self.scores[a];
+                }
+                match result {
+                    KyokuResult::Agari { winners, .. } => {
+                        for (winner, breakdown) in winners {
+                            let payers: Vec<_> = (0..4)
+                                .filter(|a| breakdown.payments[wind(*a) as usize] < 0)
+                                .collect();
+                            let target = match payers[..] {
+                                [payer] => payer,
+                                _ => actor(*winner),
+                            };
+                            let hai = match self.rinshan.take() {
+                                Some((_, hai)) => Some(hai),
+                                None if target == actor(*winner) => game.player_tsumo(*winner),
+                                None => view.last_discard(),
+                            };
+                            events.push(Event::Hora {
+                                actor: actor(*winner),
+                                target,
+                                pai: hai.map_or_else(|| "?".to_owned(), pai),
+                                deltas: [0, 1, 2, 3].map(|a| breakdown.payments[wind(a) as usize]),
+                                scores: now,
+                            });
+                        }
+                    }
+                    KyokuResult::Ryukyoku { .. } => events.push(Event::Ryukyoku {
+                        reason: "fanpai",
+                        tenpais: self.tenpais,
+                        deltas,
+                        scores: now,
+                    }),
+                    KyokuResult::Chombo { .. } => events.push(Event::Ryukyoku {
+                        reason: "chombo",
+                        tenpais: self.tenpais,
+                        deltas,
+                        scores: now,
+                    }),
+                }
+                events.push(Event::EndKyoku);
+                self.scores = now;
+                self.in_kyoku = false;
+            }
+            _ => {}
+        }
+        events
+    }
+}
+
+/// Event of a new `meld`, which was `old` before, with the tile of the
+/// kan if it is one
+fn meld_event(
+    old: Option<&Fuuro>,
+    meld: &Fuuro,
+    caller: Fon,
+    actor: &impl Fn(Fon) -> usize,
+) -> Option<(Event, Option<Hai>)> {
+    let a = actor(caller);
+    let tiles = |own: &[Hai]| own.iter().map(|hai| pai(*hai)).collect();
+    let event = match (old, meld) {
+        (None, Fuuro::Shuntsu { own, taken, from }) => (
+            Event::Chi {
+                actor: a,
+                target: actor(pointed(caller, *from)),
+                pai: pai(*taken),
+                consumed: tiles(own),
+            },
+            None,
+        ),
+        (None, Fuuro::Kootsu { own, taken, from }) => (
+            Event::Pon {
+                actor: a,
+                target: actor(pointed(caller, *from)),
+                pai: pai(*taken),
+                consumed: tiles(own),
+            },
+            None,
+        ),
+        (None, Fuuro::Kantsu(KantsuInner::DaiMinkan { own, taken, from })) => (
+            Event::Daiminkan {
+                actor: a,
+                target: actor(pointed(caller, *from)),
+                pai: pai(*taken),
+                consumed: tiles(own),
+            },
+            Some(*taken),
+        ),
+        (None, Fuuro::Kantsu(KantsuInner::Ankan { own })) => (
+            Event::Ankan {
+                actor: a,
+                consumed: tiles(own),
+            },
+            Some(own[0]),
+        ),
+        (
+            Some(Fuuro::Kootsu { .. }),
+            Fuuro::Kantsu(KantsuInner::ShouMinkan {
+                own, added, taken, ..
+            }),
+        ) => (
+            Event::Kakan {
+                actor: a,
+                pai: pai(*added),
+                consumed: vec![pai(own[0]), pai(own[1]), pai(*taken)],
+            },
+            Some(*added),
+        ),
+        _ => return None,
+    };
+    Some(event)
+}
+
+/// Seat which discarded the tile called by `caller`, who sits on its
+/// `from` side (a chi is always called from the right)
+fn pointed(caller: Fon, from: Direction) -> Fon {
+    match from {
+        Direction::Right => caller.prev(),
+        Direction::Front => caller.next_nth(2),
+        Direction::Left => caller.next(),
+    }
+}
+
+/// Tiles discarded by `p`, those called by other players included
+fn discard_count(view: &PlayerView, p: Fon) -> usize {
+    let called = WINDS
+        .iter()
+        .flat_map(|caller| view.fuuro(*caller).iter().map(move |fuuro| (caller, fuuro)))
+        .filter(|(caller, fuuro)| {
+            let from = match fuuro {
+                Fuuro::Shuntsu { from, .. }
+                | Fuuro::Kootsu { from, .. }
+                | Fuuro::Kantsu(KantsuInner::DaiMinkan { from, .. })
+                | Fuuro::Kantsu(KantsuInner::ShouMinkan { from, .. }) => *from,
+                Fuuro::Kantsu(KantsuInner::Ankan { .. }) => return false,
+            };
+            pointed(**caller, from) == p
+        })
+        .count();
+    view.river(p).len() + called
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpStream;
+
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::ai::{dump_caller_bot, null_bot};
+    use crate::simulation::play_hanchan;
+
+    /// Stand-in bot playing like `null_bot`, written in shell
+    const STAND_IN: &str = r#"id=0
+while read line; do
+    case "$line" in
+        *'"type":"start_game"'*)
+            id=$(echo "$line" | sed 's/.*"id":\([0-9]\).*/\1/')
+            echo '{"type":"none"}' ;;
+        *'"type":"tsumo","actor":'"$id"',"pai"'*)
+            pai=$(echo "$line" | sed 's/.*"pai":"\([^"]*\)".*/\1/')
+            echo "{\"type\":\"dahai\",\"actor\":$id,\"pai\":\"$pai\",\"tsumogiri\":true}" ;;
+        *) echo '{"type":"none"}' ;;
+    esac
+done
+"#;
+
+    /// Stand-in client playing like `null_bot` over TCP, returning the
+    /// events it was sent
+    fn join(address: String) -> std::thread::JoinHandle<Vec<Value>> {
+        std::thread::spawn(move || {
+            let stream = loop {
+                match TcpStream::connect(&address) {
+                    Ok(stream) => break stream,
+                    Err(_) => std::thread::sleep(Duration::from_millis(10)),
+                }
+            };
+            stream.set_nodelay(true).unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            let mut events = vec![];
+            let mut id = Value::Null;
+            for line in BufReader::new(stream).lines() {
+                let event: Value = serde_json::from_str(&line.unwrap()).unwrap();
+                let response = match event["type"].as_str().unwrap() {
+                    "hello" => json!({"type": "join", "name": "stand-in", "room": "default"}),
+                    "tsumo" if event["actor"] == id => json!({
+                        "type": "dahai",
+                        "actor": id,
+                        "pai": event["pai"],
+                        "tsumogiri": true,
+                    }),
+                    "start_game" => {
+                        id = event["id"].clone();
+                        json!({"type": "none"})
+                    }
+                    _ => json!({"type": "none"}),
+                };
+                if event["type"] == "end_game" {
+                    events.push(event);
+                    break;
+                }
+                writeln!(writer, "{}", response).unwrap();
+                events.push(event);
+            }
+            events
+        })
+    }
+
+    fn free_address() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_pai() {
+        for s in ["1m", "9p", "5sr", "E", "C"] {
+            assert_eq!(pai(parse_pai(s).unwrap()), s);
+        }
+        assert_eq!(pai("0m".parse().unwrap()), "5mr");
+        assert_eq!(pai("7z".parse().unwrap()), "C");
+        assert_eq!(parse_pai("F"), Ok("6z".parse().unwrap()));
+        for s in ["0m", "6z", "6sr", "5zr", "?", ""] {
+            assert!(parse_pai(s).is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_mjai_bot_stdio() {
+        let path =
+            std::env::temp_dir().join(format!("mahjong-mjai-stand-in-{}.sh", std::process::id()));
+        std::fs::write(&path, STAND_IN).unwrap();
+        let params = MjaiParams {
+            transport: Transport::Stdio {
+                command: format!("sh {}", path.display()),
+            },
+            timeout: Duration::from_millis(500),
+        };
+        let record = play_hanchan(47, [null_bot(), mjai_bot(params), null_bot(), null_bot()]);
+        std::fs::remove_file(&path).unwrap();
+        let expected = play_hanchan(47, [null_bot(), null_bot(), null_bot(), null_bot()]);
+        assert_eq!(record, expected);
+    }
+
+    #[test]
+    fn test_mjai_bot_no_client() {
+        let params = MjaiParams {
+            transport: Transport::Tcp {
+                address: free_address(),
+                wait: Duration::from_millis(50),
+            },
+            ..Default::default()
+        };
+        let record = play_hanchan(47, [mjai_bot(params), null_bot(), null_bot(), null_bot()]);
+        let expected = play_hanchan(47, [null_bot(), null_bot(), null_bot(), null_bot()]);
+        assert_eq!(record, expected);
+    }
+
+    /// Play a hanchan with a client over TCP as player 3, returning the
+    /// events it was sent and the number of kyoku
+    fn play_with_client(seed: u64, players: [AiServer; 3]) -> (Vec<Value>, usize) {
+        let address = free_address();
+        let client = join(address.clone());
+        let params = MjaiParams {
+            transport: Transport::Tcp {
+                address,
+                wait: Duration::from_secs(10),
+            },
+            ..Default::default()
+        };
+        let [p1, p2, p4] = players;
+        let record = play_hanchan(seed, [p1, p2, mjai_bot(params), p4]);
+        (client.join().unwrap(), record.kyoku.len())
+    }
+
+    /// Check the order of draws, calls and discards of every player, and
+    /// the hand of the client
+    fn check_events(events: &[Value], kyoku: usize) {
+        let kind = |event: &Value| event["type"].as_str().unwrap().to_owned();
+        assert_eq!(kind(&events[0]), "hello");
+        assert_eq!(kind(&events[1]), "start_game");
+        assert_eq!(kind(events.last().unwrap()), "end_game");
+        let id = events[1]["id"].as_u64().unwrap() as usize;
+        assert_eq!(id, 2);
+        let count = |name: &str| events.iter().filter(|event| kind(event) == name).count();
+        assert_eq!(count("start_kyoku"), kyoku);
+        assert_eq!(count("end_kyoku"), kyoku);
+
+        let tiles = |value: &Value| -> Vec<String> {
+            value
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|pai| pai.as_str().unwrap().to_owned())
+                .collect()
+        };
+        // Hidden tiles of each player, and the exact hand of the client
+        let mut sizes = [0; 4];
+        let mut hand: Vec<String> = vec![];
+        let remove = |hand: &mut Vec<String>, pai: &str| {
+            let i = hand.iter().position(|other| other == pai);
+            hand.remove(i.unwrap_or_else(|| panic!("No {} in {:?}", pai, hand)));
+        };
+        for event in events {
+            let actor = event["actor"].as_u64().map(|a| a as usize);
+            match kind(event).as_str() {
+                "start_kyoku" => {
+                    sizes = [13; 4];
+                    hand = tiles(&event["tehais"][id]);
+                    assert_eq!(hand.len(), 13);
+                    assert!(!hand.contains(&"?".to_owned()));
+                }
+                "tsumo" => {
+                    let a = actor.unwrap();
+                    assert_eq!(sizes[a] % 3, 1, "{}", event);
+                    sizes[a] += 1;
+                    if a == id {
+                        hand.push(event["pai"].as_str().unwrap().to_owned());
+                    } else {
+                        assert_eq!(event["pai"], "?");
+                    }
+                }
+                "dahai" => {
+                    let a = actor.unwrap();
+                    assert_eq!(sizes[a] % 3, 2, "{}", event);
+                    sizes[a] -= 1;
+                    if a == id {
+                        remove(&mut hand, event["pai"].as_str().unwrap());
+                    }
+                }
+                name @ ("chi" | "pon" | "daiminkan" | "ankan") => {
+                    let a = actor.unwrap();
+                    let consumed = tiles(&event["consumed"]);
+                    assert_eq!(sizes[a] % 3, if name == "ankan" { 2 } else { 1 });
+                    sizes[a] -= consumed.len();
+                    if a == id {
+                        for pai in consumed {
+                            remove(&mut hand, &pai);
+                        }
+                    }
+                }
+                "kakan" => {
+                    let a = actor.unwrap();
+                    assert_eq!(sizes[a] % 3, 2, "{}", event);
+                    sizes[a] -= 1;
+                    if a == id {
+                        remove(&mut hand, event["pai"].as_str().unwrap());
+                    }
+                }
+                _ => {}
+            }
+            if actor == Some(id) {
+                assert_eq!(hand.len(), sizes[id], "{}", event);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mjai_bot_tcp_events() {
+        let (events, kyoku) =
+            play_with_client(5, [dump_caller_bot(), dump_caller_bot(), dump_caller_bot()]);
+        check_events(&events, kyoku);
+        for kind in ["chi", "pon", "daiminkan", "ankan", "kakan", "dora"] {
+            assert!(events.iter().any(|event| event["type"] == kind), "{}", kind);
+        }
+    }
+}
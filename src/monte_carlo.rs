@@ -0,0 +1,450 @@
+//! Monte Carlo bot: plays out each candidate decision many times on games
+//! where what the bot cannot see is dealt again at random (determinization),
+//! and picks the decision with the best average outcome.
+
+use std::time::{Duration, Instant};
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use super::ai::{efficiency_call, AiServer, Bots, Call, PossibleCall, TehaiIndex, TurnResult};
+use super::game::{Game, GameRequest, PossibleActions, Te, ThrowableOnRiichi};
+use super::shanten::{kokushimusou_shanten, shanten_counts};
+use super::tiles::{Fon, Hai, TileCounts, TileKind};
+
+const WINDS: [Fon; 4] = [Fon::Ton, Fon::Nan, Fon::Shaa, Fon::Pee];
+
+/// Bonus of each placement at the end of a playout, added to the score so
+/// that placement matters as well (順位点)
+const PLACEMENT_BONUS: [isize; 4] = [15000, 5000, -5000, -15000];
+
+/// Most discards played out on a turn, best by shanten first
+const MAX_DISCARDS: usize = 6;
+
+/// Search allowed for each decision
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Budget {
+    /// Number of playouts of each candidate decision
+    pub playouts: usize,
+    /// Stop playing out after this time, if set. At least one playout of
+    /// each candidate is always played. Decisions are not reproducible
+    /// anymore with a time limit.
+    pub time: Option<Duration>,
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Budget {
+            playouts: 32,
+            time: None,
+        }
+    }
+}
+
+/// AI playing out each of its candidate decisions until the end of the
+/// kyoku, with the other players and itself following a fast policy
+/// ([`PlayoutBots`]).
+///
+/// The outcome of a playout is the final score of the bot, with a bonus
+/// for its placement. With no time limit, the bot always takes the same
+/// decisions in the same games.
+pub fn monte_carlo_bot(budget: Budget) -> AiServer {
+    let mut call_rng = StdRng::seed_from_u64(0);
+    let mut turn_rng = StdRng::seed_from_u64(1);
+    AiServer::with_handlers(
+        move |possible_calls, request| {
+            monte_carlo_call(budget, possible_calls, request, &mut call_rng)
+        },
+        move |possible_actions, request| {
+            monte_carlo_turn(budget, possible_actions, request, &mut turn_rng)
+        },
+        // Always declare tempai
+        |_| true,
+    )
+}
+
+/// Decision played out first, before following the fast policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Candidate {
+    Call(Option<Call>),
+    Throw { index: TehaiIndex, riichi: bool },
+}
+
+fn monte_carlo_call(
+    budget: Budget,
+    possible_calls: &[PossibleCall],
+    request: &GameRequest,
+    rng: &mut StdRng,
+) -> Option<Call> {
+    if possible_calls.contains(&PossibleCall::Ron) {
+        return Some(Call::Ron);
+    }
+    // Only the call the efficiency bot would make: playouts do not look
+    // for yaku, and would call for a noten-bappu tenpai (形式聴牌)
+    let mut candidates = vec![Candidate::Call(None)];
+    if let Some(call) = efficiency_call(possible_calls, request) {
+        candidates.push(Candidate::Call(Some(call)));
+    }
+    match search(budget, &candidates, request, rng) {
+        Candidate::Call(call) => call,
+        Candidate::Throw { .. } => unreachable!("Only calls are candidates"),
+    }
+}
+
+fn monte_carlo_turn(
+    budget: Budget,
+    possible_actions: &PossibleActions,
+    request: &GameRequest,
+    rng: &mut StdRng,
+) -> TurnResult {
+    let te = request.game.player_te_(request.player);
+    if possible_actions.can_tsumo {
+        return TurnResult::Tsumo;
+    }
+    if request.game.player_is_riichi(request.player) {
+        return TurnResult::ThrowHai {
+            index: TehaiIndex::Tsumohai,
+            riichi: false,
+        };
+    }
+    // Keep going with 10 kinds or more, for kokushimusou
+    if possible_actions.can_kyusyukyuhai && kokushimusou_shanten(&te.counts()) > 3 {
+        return TurnResult::Kyusyukyuhai;
+    }
+
+    let mut candidates = vec![];
+    for (index, _) in ranked_discards(te).into_iter().take(MAX_DISCARDS) {
+        candidates.push(Candidate::Throw {
+            index,
+            riichi: false,
+        });
+        if possible_actions
+            .can_riichi
+            .iter()
+            .any(|throwable| throwable_index(*throwable) == index)
+        {
+            candidates.push(Candidate::Throw {
+                index,
+                riichi: true,
+            });
+        }
+    }
+    match search(budget, &candidates, request, rng) {
+        Candidate::Throw { index, riichi } => TurnResult::ThrowHai { index, riichi },
+        Candidate::Call(_) => unreachable!("Only discards are candidates"),
+    }
+}
+
+/// Candidate with the best average outcome over playouts. Each round deals
+/// one determinized game, on which every candidate is played out.
+fn search(
+    budget: Budget,
+    candidates: &[Candidate],
+    request: &GameRequest,
+    rng: &mut StdRng,
+) -> Candidate {
+    if candidates.len() == 1 {
+        return candidates[0];
+    }
+    let start = Instant::now();
+    let mut totals = vec![0; candidates.len()];
+    for round in 0..budget.playouts.max(1) {
+        if round > 0 && budget.time.map_or(false, |time| start.elapsed() >= time) {
+            break;
+        }
+        let determinized = request.game.determinize(request.player, rng);
+        for (total, candidate) in totals.iter_mut().zip(candidates) {
+            let mut game = determinized.clone();
+            let mut bots = PlayoutBots {
+                first: Some((request.player, *candidate)),
+            };
+            game.play_from(&request.request, &mut bots);
+            *total += outcome(&game, request.player);
+        }
+    }
+    let best = (0..candidates.len())
+        .max_by_key(|i| (totals[*i], std::cmp::Reverse(*i)))
+        .expect("Has candidates");
+    candidates[best]
+}
+
+/// Score of `player` with a bonus for their placement. Ties go to the
+/// player closest to the oya.
+fn outcome(game: &Game, player: Fon) -> isize {
+    let score = game.player_score(player);
+    let above = WINDS
+        .iter()
+        .filter(|p| {
+            let other = game.player_score(**p);
+            other > score || (other == score && (**p as usize) < player as usize)
+        })
+        .count();
+    score + PLACEMENT_BONUS[above]
+}
+
+/// Fast policy of every player during playouts: never call, win whenever
+/// possible, riichi as soon as possible and discard for the lowest shanten
+#[derive(Debug, Default)]
+pub struct PlayoutBots {
+    /// Decision taken by a player the first time they are asked
+    first: Option<(Fon, Candidate)>,
+}
+
+impl PlayoutBots {
+    fn take_first(&mut self, player: Fon) -> Option<Candidate> {
+        match self.first {
+            Some((p, candidate)) if p == player => {
+                self.first = None;
+                Some(candidate)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Bots for PlayoutBots {
+    fn call(
+        &mut self,
+        _game: &Game,
+        player: Fon,
+        possible_calls: Vec<PossibleCall>,
+    ) -> Option<Call> {
+        if possible_calls.contains(&PossibleCall::Ron) {
+            return Some(Call::Ron);
+        }
+        match self.take_first(player) {
+            Some(Candidate::Call(call)) => call,
+            _ => None,
+        }
+    }
+
+    fn turn(&mut self, game: &Game, player: Fon, possible_actions: PossibleActions) -> TurnResult {
+        if possible_actions.can_tsumo {
+            return TurnResult::Tsumo;
+        }
+        if let Some(Candidate::Throw { index, riichi }) = self.take_first(player) {
+            return TurnResult::ThrowHai { index, riichi };
+        }
+        let te = game.player_te_(player);
+        if game.player_is_riichi(player) || te.hai().is_empty() {
+            return TurnResult::ThrowHai {
+                index: TehaiIndex::Tsumohai,
+                riichi: false,
+            };
+        }
+        let discards = ranked_discards(te);
+        let riichi = discards.iter().find(|(index, _)| {
+            possible_actions
+                .can_riichi
+                .iter()
+                .any(|throwable| throwable_index(*throwable) == *index)
+        });
+        match riichi {
+            Some((index, _)) => TurnResult::ThrowHai {
+                index: *index,
+                riichi: true,
+            },
+            None => TurnResult::ThrowHai {
+                index: discards[0].0,
+                riichi: false,
+            },
+        }
+    }
+
+    fn declare_tenpai(&mut self, _game: &Game, _player: Fon) -> bool {
+        true
+    }
+}
+
+fn throwable_index(throwable: ThrowableOnRiichi) -> TehaiIndex {
+    match throwable {
+        ThrowableOnRiichi::Te(index) => TehaiIndex::Tehai(index),
+        ThrowableOnRiichi::Tsumohai => TehaiIndex::Tsumohai,
+    }
+}
+
+/// One discard of each kind with the shanten after it, best first: lowest
+/// shanten, then least connected to the other tiles
+fn ranked_discards(te: &Te) -> Vec<(TehaiIndex, isize)> {
+    let mut counts = te.counts();
+    let melds = te.fuuro().len();
+    let tiles = te
+        .hai()
+        .iter()
+        .enumerate()
+        .map(|(i, hai)| (TehaiIndex::Tehai(i), *hai))
+        .chain(
+            te.get(TehaiIndex::Tsumohai)
+                .map(|hai| (TehaiIndex::Tsumohai, hai)),
+        );
+
+    let mut discards: Vec<(TehaiIndex, Hai, isize, usize)> = vec![];
+    for (index, hai) in tiles {
+        let kind = TileKind::from(hai);
+        // Rather discard a normal five than a red one
+        if let Some(other) = discards.iter_mut().find(|d| TileKind::from(d.1) == kind) {
+            if other.1.is_aka() && !hai.is_aka() {
+                other.0 = index;
+                other.1 = hai;
+            }
+            continue;
+        }
+        counts.remove(kind);
+        let shanten = shanten_counts(&counts, melds);
+        let connection = connection(&counts, kind);
+        counts.add(kind);
+        discards.push((index, hai, shanten, connection));
+    }
+    discards.sort_by_key(|(_, _, shanten, connection)| (*shanten, *connection));
+    discards
+        .into_iter()
+        .map(|(index, _, shanten, _)| (index, shanten))
+        .collect()
+}
+
+/// Number of tiles of `counts` which could make a mentsu with `kind`
+fn connection(counts: &TileCounts, kind: TileKind) -> usize {
+    (-2..=2)
+        .filter_map(|offset| kind.offset(offset))
+        .map(|other| counts[other] as usize)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Bots;
+    use crate::game::Request;
+    use crate::shanten::machi;
+    use crate::tiles::hai_list_from_str;
+
+    /// Playout bots keeping a copy of the game on each turn where another
+    /// player is in riichi
+    #[derive(Default)]
+    struct RiichiSnapshots {
+        bots: PlayoutBots,
+        games: Vec<(Game, Fon)>,
+    }
+
+    impl Bots for RiichiSnapshots {
+        fn call(
+            &mut self,
+            game: &Game,
+            player: Fon,
+            possible_calls: Vec<PossibleCall>,
+        ) -> Option<Call> {
+            self.bots.call(game, player, possible_calls)
+        }
+
+        fn turn(
+            &mut self,
+            game: &Game,
+            player: Fon,
+            possible_actions: PossibleActions,
+        ) -> TurnResult {
+            if WINDS
+                .iter()
+                .any(|p| *p != player && game.player_is_riichi(*p))
+            {
+                self.games.push((game.clone(), player));
+            }
+            self.bots.turn(game, player, possible_actions)
+        }
+
+        fn declare_tenpai(&mut self, game: &Game, player: Fon) -> bool {
+            self.bots.declare_tenpai(game, player)
+        }
+
+        fn notify(&mut self, game: &Game, player: Fon, request: Request) {
+            self.bots.notify(game, player, request)
+        }
+    }
+
+    #[test]
+    fn test_ranked_discards() {
+        let te: Te = "12399m456p2378s1z 5s".parse().unwrap();
+        let discards = ranked_discards(&te);
+        let first = te.get(discards[0].0);
+        assert_eq!(first, hai_list_from_str("1z").unwrap().first().copied());
+        assert_eq!(discards[0].1, 1);
+        // One discard per kind
+        assert_eq!(discards.len(), 13);
+    }
+
+    #[test]
+    fn test_playouts_end() {
+        let mut rng = StdRng::seed_from_u64(45);
+        let mut game = Game::new(&mut rng);
+        let result = game.play(&mut PlayoutBots::default());
+        let total: isize = WINDS.iter().map(|p| game.player_score(*p)).sum();
+        let riichi_bou = game.riichi_bou_count() as isize * 1000;
+        assert_eq!(total + riichi_bou, 100000, "{:?}", result);
+
+        let determinized = game.determinize(Fon::Ton, &mut rng);
+        assert_eq!(
+            determinized.player_te_(Fon::Ton).hai(),
+            game.player_te_(Fon::Ton).hai()
+        );
+    }
+
+    #[test]
+    fn test_determinize_keeps_view() {
+        let mut rng = StdRng::seed_from_u64(45);
+        let mut game = Game::new(&mut rng);
+        game.play(&mut PlayoutBots::default());
+        for p in WINDS {
+            let determinized = game.determinize(p, &mut rng);
+            let before = game.player_view(p);
+            let after = determinized.player_view(p);
+            assert_eq!(after.te().hai(), before.te().hai());
+            assert_eq!(determinized.dora_indicator(), game.dora_indicator());
+            for other in WINDS {
+                assert_eq!(after.river(other).len(), before.river(other).len());
+                assert_eq!(
+                    determinized.player_te_(other).hai().len(),
+                    game.player_te_(other).hai().len()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_determinize_keeps_constraints() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut snapshots = RiichiSnapshots::default();
+        while snapshots.games.len() < 20 {
+            Game::new(&mut rng).play(&mut snapshots);
+        }
+        for (game, player) in &snapshots.games {
+            let determinized = game.determinize(*player, &mut rng);
+            // Own hidden tiles, drawn tile included
+            assert_eq!(determinized.player_te_(*player), game.player_te_(*player));
+            let before = game.player_view(*player);
+            let after = determinized.player_view(*player);
+            for other in WINDS {
+                assert_eq!(after.fuuro(other), before.fuuro(other));
+                assert_eq!(after.discards(other), before.discards(other));
+                let te = determinized.player_te_(other);
+                let original = game.player_te_(other);
+                assert_eq!(te.hai().len(), original.hai().len());
+                assert_eq!(
+                    te.get(TehaiIndex::Tsumohai).is_some(),
+                    original.get(TehaiIndex::Tsumohai).is_some()
+                );
+                if other == *player || !game.player_is_riichi(other) {
+                    continue;
+                }
+                // Riichi players are tenpai and not furiten
+                let machi = machi(&TileCounts::from_hai(te.hai()), te.fuuro().len());
+                assert!(!machi.is_empty(), "{}", te.to_mpsz());
+                assert!(
+                    after
+                        .discards(other)
+                        .iter()
+                        .all(|discard| !machi.contains(&discard.hai.into())),
+                    "{}",
+                    te.to_mpsz()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,411 @@
+//! MPSZ text notation for tiles, hands and melds.
+//!
+//! Tiles are written as digits followed by their suit: `m` (萬子), `p` (筒子),
+//! `s` (索子) or `z` (字牌, 1 to 7 for 東南西北白発中). `0` is a red five.
+//! E.g. `123m406p789s1155z`.
+//!
+//! Melds are written between brackets, with `-` after the taken tile and
+//! `+` after the added tile of a shouminkan. The position of the taken tile
+//! tells where it was taken from: first is left (上家), last is right (下家),
+//! otherwise front (対面). E.g. `[1-23m]` (chi), `[55-5p]` (pon),
+//! `[77-77z]` (daiminkan), `[5-5+55s]` (shouminkan) and `[1111z]` (ankan).
+
+use super::game::{Direction, Fuuro, KantsuInner};
+use super::tiles::{Fon, Hai, JiHai, ParseHaiError, Sangen, Suu, SuuHai, Values, VALUES};
+
+fn invalid(string: &str, reason: &'static str) -> ParseHaiError {
+    ParseHaiError::InvalidNotation {
+        string: string.to_owned(),
+        reason,
+    }
+}
+
+fn make_hai(digit: u32, suit: char, string: &str) -> Result<Hai, ParseHaiError> {
+    let suu = match suit {
+        'm' => Suu::Wan,
+        'p' => Suu::Pin,
+        's' => Suu::Sou,
+        'z' => {
+            let ji = match digit {
+                1 => JiHai::Fon(Fon::Ton),
+                2 => JiHai::Fon(Fon::Nan),
+                3 => JiHai::Fon(Fon::Shaa),
+                4 => JiHai::Fon(Fon::Pee),
+                5 => JiHai::Sangen(Sangen::Haku),
+                6 => JiHai::Sangen(Sangen::Hatsu),
+                7 => JiHai::Sangen(Sangen::Chun),
+                _ => return Err(invalid(string, "Honor tiles are numbered 1 to 7")),
+            };
+            return Ok(Hai::Ji(ji));
+        }
+        _ => return Err(invalid(string, "Unknown suit")),
+    };
+    let (value, aka) = if digit == 0 {
+        (Values::Uu, true)
+    } else {
+        (VALUES[digit as usize - 1], false)
+    };
+    Ok(Hai::Suu(SuuHai { suu, value, aka }))
+}
+
+/// Digit and suit letter of a tile
+fn digit_and_suit(hai: Hai) -> (u32, char) {
+    match hai {
+        Hai::Suu(SuuHai { suu, value, aka }) => {
+            let digit = if aka { 0 } else { value as u32 };
+            let suit = match suu {
+                Suu::Wan => 'm',
+                Suu::Pin => 'p',
+                Suu::Sou => 's',
+            };
+            (digit, suit)
+        }
+        Hai::Ji(JiHai::Fon(fon)) => (fon as u32 + 1, 'z'),
+        Hai::Ji(JiHai::Sangen(Sangen::Haku)) => (5, 'z'),
+        Hai::Ji(JiHai::Sangen(Sangen::Hatsu)) => (6, 'z'),
+        Hai::Ji(JiHai::Sangen(Sangen::Chun)) => (7, 'z'),
+    }
+}
+
+/// Is a string written in MPSZ notation (and not with Unicode tiles)
+pub fn is_mpsz(s: &str) -> bool {
+    s.is_ascii()
+}
+
+/// Parse a list of tiles, e.g. `123m406p789s1155z`.
+pub fn parse_hai_list(s: &str) -> Result<Vec<Hai>, ParseHaiError> {
+    let mut out = vec![];
+    let mut digits = vec![];
+    for c in s.chars().filter(|c| !c.is_whitespace()) {
+        if let Some(digit) = c.to_digit(10) {
+            digits.push(digit);
+        } else if digits.is_empty() {
+            return Err(invalid(s, "Expected digits before suit"));
+        } else {
+            for digit in digits.drain(..) {
+                out.push(make_hai(digit, c, s)?);
+            }
+        }
+    }
+    if !digits.is_empty() {
+        return Err(invalid(s, "Missing suit after digits"));
+    }
+    Ok(out)
+}
+
+/// Write a list of tiles, grouping consecutive tiles of the same suit.
+pub fn format_hai_list(hai: &[Hai]) -> String {
+    let mut out = String::new();
+    let mut last_suit = None;
+    for h in hai {
+        let (digit, suit) = digit_and_suit(*h);
+        if let Some(last_suit) = last_suit {
+            if last_suit != suit {
+                out.push(last_suit);
+            }
+        }
+        out.push(std::char::from_digit(digit, 10).expect("Single digit"));
+        last_suit = Some(suit);
+    }
+    if let Some(suit) = last_suit {
+        out.push(suit);
+    }
+    out
+}
+
+/// Parse a meld, e.g. `[1-23m]`.
+pub fn parse_fuuro(s: &str) -> Result<Fuuro, ParseHaiError> {
+    let inner = s
+        .trim()
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .ok_or_else(|| invalid(s, "Melds are written between brackets"))?;
+    if !inner.is_ascii() {
+        return Err(invalid(s, "Unexpected character"));
+    }
+    let suit = inner
+        .chars()
+        .last()
+        .ok_or_else(|| invalid(s, "Empty meld"))?;
+
+    // Tiles besides the added tile, the index of the taken tile and
+    // the added tile
+    let mut hai = vec![];
+    let mut taken = None;
+    let mut added = None;
+    for c in inner[..inner.len() - 1].chars() {
+        match c {
+            '-' => {
+                if hai.is_empty() || taken.is_some() {
+                    return Err(invalid(s, "Expected a single taken tile"));
+                }
+                taken = Some(hai.len() - 1);
+            }
+            '+' => {
+                if taken.is_some() && taken == hai.len().checked_sub(1) {
+                    return Err(invalid(s, "Taken tile cannot be added"));
+                }
+                let hai = hai.pop().ok_or_else(|| invalid(s, "No tile to add"))?;
+                if added.replace(hai).is_some() {
+                    return Err(invalid(s, "Expected a single added tile"));
+                }
+            }
+            _ => {
+                let digit = c
+                    .to_digit(10)
+                    .ok_or_else(|| invalid(s, "Expected digits"))?;
+                hai.push(make_hai(digit, suit, s)?);
+            }
+        }
+    }
+
+    // A tile taken from the left (上家) is a fuuro from the right of the
+    // discarder, see [`Direction`]
+    let from = |taken: usize| {
+        if taken == 0 {
+            Direction::Right
+        } else if taken == hai.len() - 1 {
+            Direction::Left
+        } else {
+            Direction::Front
+        }
+    };
+    let own = |taken: usize| -> Vec<Hai> {
+        hai.iter()
+            .enumerate()
+            .filter(|(i, _)| *i != taken)
+            .map(|(_, hai)| *hai)
+            .collect()
+    };
+    let is_kootsu = hai.iter().all(|h| *h == hai[0]);
+    match (hai.len(), taken, added) {
+        (3, Some(taken), None) => {
+            let own = own(taken);
+            let own = [own[0], own[1]];
+            if is_kootsu {
+                Ok(Fuuro::Kootsu {
+                    own,
+                    taken: hai[taken],
+                    from: from(taken),
+                })
+            } else {
+                let mut shuntsu = hai.clone();
+                shuntsu.sort();
+                if !shuntsu[0].is_suuhai()
+                    || shuntsu[0].next() != shuntsu[1]
+                    || shuntsu[1].next() != shuntsu[2]
+                {
+                    return Err(invalid(s, "Not a valid chi"));
+                }
+                if taken != 0 {
+                    return Err(invalid(s, "Chi is only taken from the left"));
+                }
+                Ok(Fuuro::Shuntsu {
+                    own,
+                    taken: hai[taken],
+                    from: from(taken),
+                })
+            }
+        }
+        (3, Some(taken), Some(added)) if is_kootsu && added == hai[0] => {
+            let own = own(taken);
+            Ok(Fuuro::Kantsu(KantsuInner::ShouMinkan {
+                own: [own[0], own[1]],
+                added,
+                taken: hai[taken],
+                from: from(taken),
+            }))
+        }
+        (4, Some(taken), None) if is_kootsu => {
+            let own = own(taken);
+            Ok(Fuuro::Kantsu(KantsuInner::DaiMinkan {
+                own: [own[0], own[1], own[2]],
+                taken: hai[taken],
+                from: from(taken),
+            }))
+        }
+        (4, None, None) if is_kootsu => Ok(Fuuro::Kantsu(KantsuInner::Ankan {
+            own: [hai[0], hai[1], hai[2], hai[3]],
+        })),
+        _ => Err(invalid(s, "Not a valid meld")),
+    }
+}
+
+/// Write a meld, e.g. `[1-23m]`.
+pub fn format_fuuro(fuuro: &Fuuro) -> String {
+    // Tiles with their mark
+    let mut hai: Vec<(Hai, Option<char>)> = vec![];
+    let (own, taken, from, added) = match fuuro {
+        Fuuro::Shuntsu { own, taken, from } | Fuuro::Kootsu { own, taken, from } => {
+            (own.to_vec(), Some(*taken), Some(*from), None)
+        }
+        Fuuro::Kantsu(KantsuInner::DaiMinkan { own, taken, from }) => {
+            (own.to_vec(), Some(*taken), Some(*from), None)
+        }
+        Fuuro::Kantsu(KantsuInner::ShouMinkan {
+            own,
+            added,
+            taken,
+            from,
+        }) => (own.to_vec(), Some(*taken), Some(*from), Some(*added)),
+        Fuuro::Kantsu(KantsuInner::Ankan { own }) => (own.to_vec(), None, None, None),
+    };
+    hai.extend(own.into_iter().map(|h| (h, None)));
+    if let (Some(taken), Some(from)) = (taken, from) {
+        let index = match from {
+            Direction::Right => 0,
+            Direction::Front => 1,
+            Direction::Left => hai.len(),
+        };
+        hai.insert(index, (taken, Some('-')));
+        if let Some(added) = added {
+            hai.insert(index + 1, (added, Some('+')));
+        }
+    }
+
+    let mut out = String::from("[");
+    let mut suit = 'z';
+    for (h, mark) in hai {
+        let (digit, s) = digit_and_suit(h);
+        out.push(std::char::from_digit(digit, 10).expect("Single digit"));
+        if let Some(mark) = mark {
+            out.push(mark);
+        }
+        suit = s;
+    }
+    out.push(suit);
+    out.push(']');
+    out
+}
+
+/// Hidden tiles, tsumo tile and melds of a hand
+pub type ParsedTe = (Vec<Hai>, Option<Hai>, Vec<Fuuro>);
+
+/// Parse a hand: hidden tiles, an optional tsumo tile, then melds.
+/// E.g. `123m456p789s11z 1z [1-23m]`
+pub fn parse_te(s: &str) -> Result<ParsedTe, ParseHaiError> {
+    let mut hai = None;
+    let mut tsumo = None;
+    let mut fuuro = vec![];
+    let mut rest = s.trim();
+    while !rest.is_empty() {
+        if rest.starts_with('[') {
+            let end = rest
+                .find(']')
+                .ok_or_else(|| invalid(s, "Unclosed bracket"))?;
+            fuuro.push(parse_fuuro(&rest[..=end])?);
+            rest = rest[end + 1..].trim_start();
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == '[')
+                .unwrap_or(rest.len());
+            let tiles = parse_hai_list(&rest[..end])?;
+            if hai.is_none() {
+                hai = Some(tiles);
+            } else if tsumo.is_none() && tiles.len() == 1 {
+                tsumo = Some(tiles[0]);
+            } else {
+                return Err(invalid(s, "Expected hidden tiles then a tsumo tile"));
+            }
+            rest = rest[end..].trim_start();
+        }
+    }
+    Ok((hai.unwrap_or_default(), tsumo, fuuro))
+}
+
+/// Write a hand, in the format read by [`parse_te`].
+pub fn format_te(hai: &[Hai], tsumo: Option<Hai>, fuuro: &[Fuuro]) -> String {
+    let mut parts = vec![format_hai_list(hai)];
+    if let Some(tsumo) = tsumo {
+        parts.push(format_hai_list(&[tsumo]));
+    }
+    parts.extend(fuuro.iter().map(format_fuuro));
+    parts.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hai_list() {
+        let hai = parse_hai_list("123m406p7s1157z").unwrap();
+        assert_eq!(hai.len(), 11);
+        assert_eq!(hai[0].to_char(), '🀇');
+        assert_eq!(hai[3].to_char(), '🀜');
+        assert!(hai[4].is_aka());
+        assert_eq!(hai[4].to_char(), '🀝');
+        assert_eq!(hai[6].to_char(), '🀖');
+        assert_eq!(hai[7], Hai::Ji(JiHai::Fon(Fon::Ton)));
+        assert_eq!(hai[9], Hai::Ji(JiHai::Sangen(Sangen::Haku)));
+        assert_eq!(hai[10], Hai::Ji(JiHai::Sangen(Sangen::Chun)));
+        assert_eq!(format_hai_list(&hai), "123m406p7s1157z");
+    }
+
+    #[test]
+    fn test_parse_hai_list_errors() {
+        assert!(parse_hai_list("123").is_err());
+        assert!(parse_hai_list("m").is_err());
+        assert!(parse_hai_list("8z").is_err());
+        assert!(parse_hai_list("12x").is_err());
+    }
+
+    #[test]
+    fn test_fuuro_round_trip() {
+        for s in [
+            "[1-23m]",
+            "[5-46p]",
+            "[55-5p]",
+            "[5-55z]",
+            "[77-77z]",
+            "[7777-z]",
+            "[5-5+55s]",
+            "[22-2+2m]",
+            "[1111z]",
+            "[0-55p]",
+        ] {
+            let fuuro = parse_fuuro(s).unwrap();
+            assert_eq!(format_fuuro(&fuuro), s);
+        }
+    }
+
+    #[test]
+    fn test_parse_fuuro() {
+        let chi = parse_fuuro("[1-23m]").unwrap();
+        let hai = parse_hai_list("123m").unwrap();
+        assert_eq!(
+            chi,
+            Fuuro::Shuntsu {
+                own: [hai[1], hai[2]],
+                taken: hai[0],
+                from: Direction::Right,
+            }
+        );
+        let pon = parse_fuuro("[555-p]").unwrap();
+        assert!(matches!(
+            pon,
+            Fuuro::Kootsu {
+                from: Direction::Left,
+                ..
+            }
+        ));
+        // Chi is only from the left
+        assert!(parse_fuuro("[45-6p]").is_err());
+        assert!(parse_fuuro("[789-s]").is_err());
+        assert!(parse_fuuro("[1-24m]").is_err());
+        assert!(parse_fuuro("[1-23z]").is_err());
+        assert!(parse_fuuro("[1-2-3m]").is_err());
+        assert!(parse_fuuro("[123m]").is_err());
+        assert!(parse_fuuro("1-23m").is_err());
+    }
+
+    #[test]
+    fn test_te_round_trip() {
+        let s = "234m55p 6p [1-23s] [5-5+55z]";
+        let (hai, tsumo, fuuro) = parse_te(s).unwrap();
+        assert_eq!(hai.len(), 5);
+        assert_eq!(tsumo, Some(parse_hai_list("6p").unwrap()[0]));
+        assert_eq!(fuuro.len(), 2);
+        assert_eq!(format_te(&hai, tsumo, &fuuro), s);
+    }
+}
@@ -1,231 +1,428 @@
-use crate::yaku::YakuValue;
-
-const KO_MANGAN: isize = 8000;
-const OYA_MANGAN: isize = KO_MANGAN * 3 / 2;
-const KO_HANEMAN: isize = 12000;
-const OYA_HANEMAN: isize = KO_HANEMAN * 3 / 2;
-const KO_BAIMAN: isize = 16000;
-const OYA_BAIMAN: isize = KO_BAIMAN * 3 / 2;
-const KO_SANBAIMAN: isize = 24000;
-const OYA_SANBAIMAN: isize = KO_SANBAIMAN * 3 / 2;
-const KO_YAKUMAN: isize = 32000;
-const OYA_YAKUMAN: isize = KO_YAKUMAN * 3 / 2;
-
-pub fn points_ron_oya(han: YakuValue, fu: usize) -> isize {
-    match han {
-        YakuValue::Han(1) => match fu {
-            30 => 1500,
-            40 => 2000,
-            50 => 2400,
-            60 => 2900,
-            70 => 3400,
-            80 => 3900,
-            90 => 4400,
-            100 => 4800,
-            110 => 5300,
-            _ => unreachable!("Impossible fu value for {:?}: {fu}", han),
-        },
-        YakuValue::Han(2) => match fu {
-            25 => 2400,
-            30 => 2900,
-            40 => 3900,
-            50 => 4800,
-            60 => 5800,
-            70 => 6800,
-            80 => 7700,
-            90 => 8700,
-            100 => 9600,
-            110 => 10600,
-            _ => unreachable!("Impossible fu value for {:?}: {fu}", han),
-        },
-        YakuValue::Han(3) => match fu {
-            25 => 4800,
-            30 => 5800,
-            40 => 7700,
-            50 => 9600,
-            60 => 11600,
-            61.. => OYA_MANGAN,
-            _ => unreachable!("Impossible fu value for {:?}: {fu}", han),
-        },
-        YakuValue::Han(4) => match fu {
-            25 => 9600,
-            30 => 11600,
-            31.. => OYA_MANGAN,
-            _ => unreachable!("Impossible fu value for {:?}: {fu}", han),
-        },
-        YakuValue::Han(han) => match han {
-            5 => OYA_MANGAN,
-            6 | 7 => OYA_HANEMAN,
-            8 | 9 | 10 => OYA_BAIMAN,
-            11 | 12 => OYA_SANBAIMAN,
-            13.. => OYA_YAKUMAN,
-            _ => unreachable!("Impossible han value: {han}"),
-        },
-        YakuValue::Yakuman(yakuman) => yakuman as isize * OYA_YAKUMAN,
+use crate::rules::Rules;
+use crate::tiles::Fon;
+use crate::yaku::{Limit, WinContext, WinningMethod, YakuValue};
+
+impl Limit {
+    /// Basic points (基本点) of this limit
+    pub fn basic_points(self) -> isize {
+        match self {
+            Limit::Mangan => 2000,
+            Limit::Haneman => 3000,
+            Limit::Baiman => 4000,
+            Limit::Sanbaiman => 6000,
+            Limit::KazoeYakuman => 8000,
+            Limit::Yakuman(yakuman) => yakuman as isize * 8000,
+        }
     }
 }
 
-pub fn points_ron_ko(han: YakuValue, fu: usize) -> isize {
-    match han {
-        YakuValue::Han(1) => match fu {
-            30 => 1000,
-            40 => 1300,
-            50 => 1600,
-            60 => 2000,
-            70 => 2300,
-            80 => 2600,
-            90 => 2900,
-            100 => 3200,
-            110 => 3600,
-            _ => unreachable!("Impossible fu value for {:?}: {fu}", han),
-        },
-        YakuValue::Han(2) => match fu {
-            25 => 1600,
-            30 => 2000,
-            40 => 2600,
-            50 => 3200,
-            60 => 3900,
-            70 => 4500,
-            80 => 5200,
-            90 => 5800,
-            100 => 6400,
-            110 => 7100,
-            _ => unreachable!("Impossible fu value for {:?}: {fu}", han),
-        },
-        YakuValue::Han(3) => match fu {
-            25 => 3200,
-            30 => 3900,
-            40 => 5200,
-            50 => 6400,
-            60 => 7700,
-            61.. => KO_MANGAN,
-            _ => unreachable!("Impossible fu value for {:?}: {fu}", han),
-        },
-        YakuValue::Han(4) => match fu {
-            25 => 6400,
-            30 => 7700,
-            31.. => KO_MANGAN,
-            _ => unreachable!("Impossible fu value for {:?}: {fu}", han),
-        },
-        YakuValue::Han(han) => match han {
-            5 => KO_MANGAN,
-            6 | 7 => KO_HANEMAN,
-            8 | 9 | 10 => KO_BAIMAN,
-            11 | 12 => KO_SANBAIMAN,
-            13.. => KO_YAKUMAN,
-            _ => unreachable!("Impossible han value: {han}"),
-        },
-        YakuValue::Yakuman(yakuman) => yakuman as isize * KO_YAKUMAN,
+/// Basic points (基本点): fu × 2^(han + 2), or the value of the limit
+/// reached by the hand.
+pub fn basic_points(han: YakuValue, fu: usize, rules: &Rules) -> isize {
+    match (limit(han, fu, rules), han) {
+        (Some(limit), _) => limit.basic_points(),
+        (None, YakuValue::Han(han)) => (fu as isize) << (han + 2),
+        (None, YakuValue::Yakuman(_)) => unreachable!("Yakuman is always a limit"),
     }
 }
 
-pub fn points_tsumo_oya(han: YakuValue, fu: usize) -> isize {
+/// Round up to the next 100 points
+fn round_up(points: isize) -> isize {
+    (points + 99) / 100 * 100
+}
+
+pub fn points_ron_oya(han: YakuValue, fu: usize, rules: &Rules) -> isize {
+    round_up(6 * basic_points(han, fu, rules))
+}
+
+pub fn points_ron_ko(han: YakuValue, fu: usize, rules: &Rules) -> isize {
+    round_up(4 * basic_points(han, fu, rules))
+}
+
+/// Points paid by each ko when oya wins by tsumo
+pub fn points_tsumo_oya(han: YakuValue, fu: usize, rules: &Rules) -> isize {
+    round_up(2 * basic_points(han, fu, rules))
+}
+
+/// Points paid by oya and by each other ko when a ko wins by tsumo
+pub fn points_tsumo_ko(han: YakuValue, fu: usize, rules: &Rules) -> (isize, isize) {
+    let basic_points = basic_points(han, fu, rules);
+    (round_up(2 * basic_points), round_up(basic_points))
+}
+
+/// Limit reached by a hand with the given han and fu, if any.
+pub fn limit(han: YakuValue, fu: usize, rules: &Rules) -> Option<Limit> {
     match han {
-        YakuValue::Han(1) => match fu {
-            30 => 500,
-            40 => 700,
-            50 => 800,
-            60 => 1000,
-            70 => 1200,
-            80 => 1300,
-            90 => 1500,
-            100 => 1600,
-            110 => 1800,
-            _ => unreachable!("Impossible fu value for {:?}: {fu}", han),
-        },
-        YakuValue::Han(2) => match fu {
-            20 => 700,
-            30 => 1000,
-            40 => 1300,
-            50 => 1600,
-            60 => 2000,
-            70 => 2300,
-            80 => 2600,
-            90 => 2900,
-            100 => 3200,
-            110 => 3600,
-            _ => unreachable!("Impossible fu value for {:?}: {fu}", han),
-        },
-        YakuValue::Han(3) => match fu {
-            20 => 1300,
-            25 => 1600,
-            30 => 2000,
-            40 => 2600,
-            50 => 3200,
-            60 => 3900,
-            61.. => OYA_MANGAN / 3,
-            _ => unreachable!("Impossible fu value for {:?}: {fu}", han),
-        },
-        YakuValue::Han(4) => match fu {
-            20 => 2600,
-            25 => 3200,
-            30 => 3900,
-            31.. => OYA_MANGAN / 3,
-            _ => unreachable!("Impossible fu value for {:?}: {fu}", han),
-        },
-        YakuValue::Han(han) => match han {
-            5 => OYA_MANGAN / 3,
-            6 | 7 => OYA_HANEMAN / 3,
-            8 | 9 | 10 => OYA_BAIMAN / 3,
-            11 | 12 => OYA_SANBAIMAN / 3,
-            13.. => OYA_YAKUMAN / 3,
-            _ => unreachable!("Impossible han value: {han}"),
-        },
-        YakuValue::Yakuman(yakuman) => yakuman as isize * OYA_YAKUMAN / 3,
+        YakuValue::Han(han @ 0..=4) => {
+            let basic_points = fu << (han + 2);
+            // 4 han 30 fu and 3 han 60 fu are 1920 basic points
+            let kiriage = rules.kiriage_mangan && basic_points == 1920;
+            (basic_points >= 2000 || kiriage).then_some(Limit::Mangan)
+        }
+        YakuValue::Han(5) => Some(Limit::Mangan),
+        YakuValue::Han(6 | 7) => Some(Limit::Haneman),
+        YakuValue::Han(8..=10) => Some(Limit::Baiman),
+        YakuValue::Han(11 | 12) => Some(Limit::Sanbaiman),
+        YakuValue::Han(_) => Some(Limit::KazoeYakuman),
+        YakuValue::Yakuman(yakuman) => Some(Limit::Yakuman(yakuman)),
+    }
+}
+
+/// Points received (positive) or paid (negative) by each player, indexed by
+/// seat wind, when the player sitting at `context.jikaze` wins.
+pub fn payments(
+    han: YakuValue,
+    fu: usize,
+    method: WinningMethod,
+    context: &WinContext,
+    rules: &Rules,
+) -> [isize; 4] {
+    let mut payments = [0; 4];
+    let winner = context.jikaze;
+    let honba_points = context.honba as isize * 100;
+    match method {
+        WinningMethod::Ron => {
+            let points = if winner == Fon::Ton {
+                points_ron_oya(han, fu, rules)
+            } else {
+                points_ron_ko(han, fu, rules)
+            };
+            let total = points + honba_points * 3;
+            payments[winner as usize] += total;
+            if let Some(loser) = context.discarder {
+                payments[loser as usize] -= total;
+            }
+        }
+        WinningMethod::Tsumo | WinningMethod::Nagashimangan => {
+            if winner == Fon::Ton {
+                let total = points_tsumo_oya(han, fu, rules) + honba_points;
+                for (i, payment) in payments.iter_mut().enumerate() {
+                    if i == winner as usize {
+                        *payment += 3 * total;
+                    } else {
+                        *payment -= total;
+                    }
+                }
+            } else {
+                let (oya_points, ko_points) = points_tsumo_ko(han, fu, rules);
+                for (i, payment) in payments.iter_mut().enumerate() {
+                    if i == winner as usize {
+                        *payment += oya_points + ko_points * 2 + honba_points * 3;
+                    } else {
+                        *payment -= if i == Fon::Ton as usize {
+                            oya_points
+                        } else {
+                            ko_points
+                        } + honba_points;
+                    }
+                }
+            }
+        }
     }
+    payments[winner as usize] += context.riichi_bou as isize * 1000;
+    payments
 }
-pub fn points_tsumo_ko(han: YakuValue, fu: usize) -> (isize, isize) {
-    let oya = points_tsumo_oya(han, fu);
-    let ko = match han {
-        YakuValue::Han(1) => match fu {
-            30 => 300,
-            40 | 50 => 400,
-            60 => 500,
-            70 => 600,
-            80 => 700,
-            90 | 100 => 800,
-            110 => 900,
-            _ => unreachable!("Impossible fu value for {:?}: {fu}", han),
-        },
-        YakuValue::Han(2) => match fu {
-            20 => 400,
-            30 => 500,
-            40 => 700,
-            50 => 800,
-            60 => 1000,
-            70 => 1200,
-            80 => 1300,
-            90 => 1500,
-            100 => 1600,
-            110 => 1800,
-            _ => unreachable!("Impossible fu value for {:?}: {fu}", han),
-        },
-        YakuValue::Han(3) => match fu {
-            20 => 700,
-            25 => 800,
-            30 => 1000,
-            40 => 1300,
-            50 => 1600,
-            60 => 2000,
-            61.. => OYA_MANGAN / 6,
-            _ => unreachable!("Impossible fu value for {:?}: {fu}", han),
-        },
-        YakuValue::Han(4) => match fu {
-            20 => 1300,
-            25 => 1600,
-            30 => 2000,
-            31.. => OYA_MANGAN / 6,
-            _ => unreachable!("Impossible fu value for {:?}: {fu}", han),
-        },
-        YakuValue::Han(han) => match han {
-            5 => OYA_MANGAN / 6,
-            6 | 7 => OYA_HANEMAN / 6,
-            8 | 9 | 10 => OYA_BAIMAN / 6,
-            11 | 12 => OYA_SANBAIMAN / 6,
-            13.. => OYA_YAKUMAN / 6,
-            _ => unreachable!("Impossible han value: {han}"),
-        },
-        YakuValue::Yakuman(yakuman) => yakuman as isize * OYA_YAKUMAN / 6,
-    };
-    (oya, ko)
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_points_match_tables() {
+        let rules = Rules::default();
+        for han in 1..=13 {
+            for fu in [20, 25, 30, 40, 50, 60, 70, 80, 90, 100, 110] {
+                let han = YakuValue::Han(han);
+                if let Some(points) = table_ron_oya(han, fu) {
+                    assert_eq!(points_ron_oya(han, fu, &rules), points, "{:?} {}", han, fu);
+                }
+                if let Some(points) = table_ron_ko(han, fu) {
+                    assert_eq!(points_ron_ko(han, fu, &rules), points, "{:?} {}", han, fu);
+                }
+                if let Some(points) = table_tsumo_oya(han, fu) {
+                    assert_eq!(
+                        points_tsumo_oya(han, fu, &rules),
+                        points,
+                        "{:?} {}",
+                        han,
+                        fu
+                    );
+                }
+                if let Some(points) = table_tsumo_ko(han, fu) {
+                    assert_eq!(points_tsumo_ko(han, fu, &rules), points, "{:?} {}", han, fu);
+                }
+            }
+        }
+        for yakuman in 1..=3 {
+            let han = YakuValue::Yakuman(yakuman);
+            assert_eq!(
+                points_ron_oya(han, 0, &rules),
+                table_ron_oya(han, 0).unwrap()
+            );
+            assert_eq!(points_ron_ko(han, 0, &rules), table_ron_ko(han, 0).unwrap());
+            assert_eq!(
+                points_tsumo_oya(han, 0, &rules),
+                table_tsumo_oya(han, 0).unwrap()
+            );
+            assert_eq!(
+                points_tsumo_ko(han, 0, &rules),
+                table_tsumo_ko(han, 0).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_points_high_fu() {
+        let rules = Rules::default();
+        assert_eq!(points_ron_ko(YakuValue::Han(1), 120, &rules), 3900);
+        assert_eq!(points_ron_oya(YakuValue::Han(1), 130, &rules), 6300);
+        assert_eq!(
+            points_tsumo_ko(YakuValue::Han(2), 120, &rules),
+            (3900, 2000)
+        );
+        assert_eq!(limit(YakuValue::Han(2), 130, &rules), Some(Limit::Mangan));
+    }
+
+    #[test]
+    fn test_kiriage_mangan() {
+        let mut rules = Rules::default();
+        assert_eq!(points_ron_ko(YakuValue::Han(4), 30, &rules), 7700);
+        assert_eq!(points_ron_oya(YakuValue::Han(3), 60, &rules), 11600);
+        rules.kiriage_mangan = true;
+        assert_eq!(points_ron_ko(YakuValue::Han(4), 30, &rules), 8000);
+        assert_eq!(points_ron_oya(YakuValue::Han(3), 60, &rules), 12000);
+        assert_eq!(points_ron_ko(YakuValue::Han(3), 50, &rules), 6400);
+    }
+
+    // Point tables that were used before points were computed, kept as
+    // reference.
+    const KO_MANGAN: isize = 8000;
+    const OYA_MANGAN: isize = KO_MANGAN * 3 / 2;
+    const KO_HANEMAN: isize = 12000;
+    const OYA_HANEMAN: isize = KO_HANEMAN * 3 / 2;
+    const KO_BAIMAN: isize = 16000;
+    const OYA_BAIMAN: isize = KO_BAIMAN * 3 / 2;
+    const KO_SANBAIMAN: isize = 24000;
+    const OYA_SANBAIMAN: isize = KO_SANBAIMAN * 3 / 2;
+    const KO_YAKUMAN: isize = 32000;
+    const OYA_YAKUMAN: isize = KO_YAKUMAN * 3 / 2;
+
+    fn table_ron_oya(han: YakuValue, fu: usize) -> Option<isize> {
+        Some(match han {
+            YakuValue::Han(1) => match fu {
+                30 => 1500,
+                40 => 2000,
+                50 => 2400,
+                60 => 2900,
+                70 => 3400,
+                80 => 3900,
+                90 => 4400,
+                100 => 4800,
+                110 => 5300,
+                _ => return None,
+            },
+            YakuValue::Han(2) => match fu {
+                25 => 2400,
+                30 => 2900,
+                40 => 3900,
+                50 => 4800,
+                60 => 5800,
+                70 => 6800,
+                80 => 7700,
+                90 => 8700,
+                100 => 9600,
+                110 => 10600,
+                _ => return None,
+            },
+            YakuValue::Han(3) => match fu {
+                25 => 4800,
+                30 => 5800,
+                40 => 7700,
+                50 => 9600,
+                60 => 11600,
+                61.. => OYA_MANGAN,
+                _ => return None,
+            },
+            YakuValue::Han(4) => match fu {
+                25 => 9600,
+                30 => 11600,
+                31.. => OYA_MANGAN,
+                _ => return None,
+            },
+            YakuValue::Han(han) => match han {
+                5 => OYA_MANGAN,
+                6 | 7 => OYA_HANEMAN,
+                8 | 9 | 10 => OYA_BAIMAN,
+                11 | 12 => OYA_SANBAIMAN,
+                13.. => OYA_YAKUMAN,
+                _ => return None,
+            },
+            YakuValue::Yakuman(yakuman) => yakuman as isize * OYA_YAKUMAN,
+        })
+    }
+
+    fn table_ron_ko(han: YakuValue, fu: usize) -> Option<isize> {
+        Some(match han {
+            YakuValue::Han(1) => match fu {
+                30 => 1000,
+                40 => 1300,
+                50 => 1600,
+                60 => 2000,
+                70 => 2300,
+                80 => 2600,
+                90 => 2900,
+                100 => 3200,
+                110 => 3600,
+                _ => return None,
+            },
+            YakuValue::Han(2) => match fu {
+                25 => 1600,
+                30 => 2000,
+                40 => 2600,
+                50 => 3200,
+                60 => 3900,
+                70 => 4500,
+                80 => 5200,
+                90 => 5800,
+                100 => 6400,
+                110 => 7100,
+                _ => return None,
+            },
+            YakuValue::Han(3) => match fu {
+                25 => 3200,
+                30 => 3900,
+                40 => 5200,
+                50 => 6400,
+                60 => 7700,
+                61.. => KO_MANGAN,
+                _ => return None,
+            },
+            YakuValue::Han(4) => match fu {
+                25 => 6400,
+                30 => 7700,
+                31.. => KO_MANGAN,
+                _ => return None,
+            },
+            YakuValue::Han(han) => match han {
+                5 => KO_MANGAN,
+                6 | 7 => KO_HANEMAN,
+                8 | 9 | 10 => KO_BAIMAN,
+                11 | 12 => KO_SANBAIMAN,
+                13.. => KO_YAKUMAN,
+                _ => return None,
+            },
+            YakuValue::Yakuman(yakuman) => yakuman as isize * KO_YAKUMAN,
+        })
+    }
+
+    fn table_tsumo_oya(han: YakuValue, fu: usize) -> Option<isize> {
+        Some(match han {
+            YakuValue::Han(1) => match fu {
+                30 => 500,
+                40 => 700,
+                50 => 800,
+                60 => 1000,
+                70 => 1200,
+                80 => 1300,
+                90 => 1500,
+                100 => 1600,
+                110 => 1800,
+                _ => return None,
+            },
+            YakuValue::Han(2) => match fu {
+                20 => 700,
+                30 => 1000,
+                40 => 1300,
+                50 => 1600,
+                60 => 2000,
+                70 => 2300,
+                80 => 2600,
+                90 => 2900,
+                100 => 3200,
+                110 => 3600,
+                _ => return None,
+            },
+            YakuValue::Han(3) => match fu {
+                20 => 1300,
+                25 => 1600,
+                30 => 2000,
+                40 => 2600,
+                50 => 3200,
+                60 => 3900,
+                61.. => OYA_MANGAN / 3,
+                _ => return None,
+            },
+            YakuValue::Han(4) => match fu {
+                20 => 2600,
+                25 => 3200,
+                30 => 3900,
+                31.. => OYA_MANGAN / 3,
+                _ => return None,
+            },
+            YakuValue::Han(han) => match han {
+                5 => OYA_MANGAN / 3,
+                6 | 7 => OYA_HANEMAN / 3,
+                8 | 9 | 10 => OYA_BAIMAN / 3,
+                11 | 12 => OYA_SANBAIMAN / 3,
+                13.. => OYA_YAKUMAN / 3,
+                _ => return None,
+            },
+            YakuValue::Yakuman(yakuman) => yakuman as isize * OYA_YAKUMAN / 3,
+        })
+    }
+    fn table_tsumo_ko(han: YakuValue, fu: usize) -> Option<(isize, isize)> {
+        let oya = table_tsumo_oya(han, fu)?;
+        let ko = match han {
+            YakuValue::Han(1) => match fu {
+                30 => 300,
+                40 | 50 => 400,
+                60 => 500,
+                70 => 600,
+                80 => 700,
+                90 | 100 => 800,
+                110 => 900,
+                _ => return None,
+            },
+            YakuValue::Han(2) => match fu {
+                20 => 400,
+                30 => 500,
+                40 => 700,
+                50 => 800,
+                60 => 1000,
+                70 => 1200,
+                80 => 1300,
+                90 => 1500,
+                100 => 1600,
+                110 => 1800,
+                _ => return None,
+            },
+            YakuValue::Han(3) => match fu {
+                20 => 700,
+                25 => 800,
+                30 => 1000,
+                40 => 1300,
+                50 => 1600,
+                60 => 2000,
+                61.. => OYA_MANGAN / 6,
+                _ => return None,
+            },
+            YakuValue::Han(4) => match fu {
+                20 => 1300,
+                25 => 1600,
+                30 => 2000,
+                31.. => OYA_MANGAN / 6,
+                _ => return None,
+            },
+            YakuValue::Han(han) => match han {
+                5 => OYA_MANGAN / 6,
+                6 | 7 => OYA_HANEMAN / 6,
+                8 | 9 | 10 => OYA_BAIMAN / 6,
+                11 | 12 => OYA_SANBAIMAN / 6,
+                13.. => OYA_YAKUMAN / 6,
+                _ => return None,
+            },
+            YakuValue::Yakuman(yakuman) => yakuman as isize * OYA_YAKUMAN / 6,
+        };
+        Some((oya, ko))
+    }
 }
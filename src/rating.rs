@@ -0,0 +1,295 @@
+//! Tenhou-style R ratings (レーティング) of players and bot configurations,
+//! kept in a RON file across runs.
+//!
+//! After each hanchan, the rating of a player changes by its placement
+//! points, corrected by the difference between the average rating of the
+//! table and its own rating. The change gets smaller as the player plays
+//! more games, down to a fifth after 400 games.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::locale::{write_table, Locale, Localize};
+
+/// Rating of a player before its first game
+pub const INITIAL_RATING: f64 = 1500.0;
+
+/// Rating points of each placement of a hanchan
+const PLACEMENT_POINTS: [f64; 4] = [30.0, 10.0, -10.0, -30.0];
+
+/// Games after which rating changes stop getting smaller
+const EXPERIENCED_GAMES: usize = 400;
+
+/// Weight of a game for a player who already played `games` games
+fn games_correction(games: usize) -> f64 {
+    if games < EXPERIENCED_GAMES {
+        1.0 - games as f64 * 0.002
+    } else {
+        0.2
+    }
+}
+
+/// A rated hanchan of a player
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RatingChange {
+    /// Number of the hanchan among all the rated hanchan
+    pub hanchan: usize,
+    pub placement: usize,
+    pub score: isize,
+    /// Rating after the hanchan
+    pub rating: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerRating {
+    pub rating: f64,
+    pub games: usize,
+    /// Number of 1st, 2nd, 3rd and 4th places
+    pub placements: [usize; 4],
+    pub history: Vec<RatingChange>,
+}
+
+impl Default for PlayerRating {
+    fn default() -> Self {
+        PlayerRating {
+            rating: INITIAL_RATING,
+            games: 0,
+            placements: [0; 4],
+            history: vec![],
+        }
+    }
+}
+
+impl PlayerRating {
+    pub fn average_placement(&self) -> f64 {
+        let sum: usize = (0..4).map(|i| (i + 1) * self.placements[i]).sum();
+        sum as f64 / self.games.max(1) as f64
+    }
+}
+
+/// Ratings of every player, by name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Ratings {
+    /// Number of rated hanchan
+    pub hanchan: usize,
+    pub players: BTreeMap<String, PlayerRating>,
+}
+
+impl Ratings {
+    /// Read ratings from a file. A file which does not exist yet has no
+    /// ratings.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => {
+                ron::from_str(&text).map_err(|e| format!("Bad ratings {}: {}", path.display(), e))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Ratings::default()),
+            Err(e) => Err(format!("Cannot read {}: {}", path.display(), e)),
+        }
+    }
+
+    /// Write the ratings to a file, replacing it at once so that it is never
+    /// left half written
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = ron::ser::to_string_pretty(self, Default::default())
+            .map_err(|e| format!("Cannot serialize ratings: {}", e))?;
+        let tmp = PathBuf::from(format!("{}.tmp", path.display()));
+        std::fs::write(&tmp, text)
+            .and_then(|()| std::fs::rename(&tmp, path))
+            .map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+    }
+
+    /// Rating of a player, who may not have played yet
+    pub fn rating(&self, name: &str) -> f64 {
+        self.players
+            .get(name)
+            .map_or(INITIAL_RATING, |player| player.rating)
+    }
+
+    /// Rate a hanchan from the final score and placement of each player.
+    ///
+    /// A hanchan where the same player sits more than once says nothing
+    /// about its strength: it is not rated and this returns false.
+    pub fn record(&mut self, names: [&str; 4], scores: [isize; 4], placements: [usize; 4]) -> bool {
+        if (1..4).any(|i| names[..i].contains(&names[i])) {
+            return false;
+        }
+        let ratings = names.map(|name| self.rating(name));
+        let average = ratings.iter().sum::<f64>() / 4.0;
+        self.hanchan += 1;
+        for p in 0..4 {
+            let player = self.players.entry(names[p].to_owned()).or_default();
+            let change = PLACEMENT_POINTS[placements[p] - 1] + (average - ratings[p]) / 40.0;
+            player.rating += games_correction(player.games) * change;
+            player.games += 1;
+            player.placements[placements[p] - 1] += 1;
+            player.history.push(RatingChange {
+                hanchan: self.hanchan,
+                placement: placements[p],
+                score: scores[p],
+                rating: player.rating,
+            });
+        }
+        true
+    }
+
+    /// Players from the best rating to the worst
+    pub fn leaderboard(&self) -> Vec<(&str, &PlayerRating)> {
+        let mut players: Vec<_> = self
+            .players
+            .iter()
+            .map(|(name, player)| (name.as_str(), player))
+            .collect();
+        players.sort_by(|a, b| b.1.rating.total_cmp(&a.1.rating));
+        players
+    }
+}
+
+impl Localize for Ratings {
+    fn fmt_localized(&self, f: &mut fmt::Formatter<'_>, locale: Locale) -> fmt::Result {
+        writeln!(
+            f,
+            "{} {}",
+            locale.pick("半荘", "Hanchan", "Games"),
+            self.hanchan
+        )?;
+        writeln!(f)?;
+        let mut rows = vec![vec![
+            locale.pick("打ち手", "Uchite", "Player").to_owned(),
+            "R".to_owned(),
+            locale.pick("対戦数", "Taisen-suu", "Games").to_owned(),
+            locale.pick("1着", "1-chaku", "1st").to_owned(),
+            locale.pick("2着", "2-chaku", "2nd").to_owned(),
+            locale.pick("3着", "3-chaku", "3rd").to_owned(),
+            locale.pick("4着", "4-chaku", "4th").to_owned(),
+            locale
+                .pick("平均着順", "Heikin-chakujun", "Average rank")
+                .to_owned(),
+        ]];
+        for (name, player) in self.leaderboard() {
+            let [first, second, third, fourth] = player.placements;
+            rows.push(vec![
+                name.to_owned(),
+                format!("{:.0}", player.rating),
+                player.games.to_string(),
+                first.to_string(),
+                second.to_string(),
+                third.to_string(),
+                fourth.to_string(),
+                format!("{:.2}", player.average_placement()),
+            ]);
+        }
+        write_table(f, &rows)
+    }
+}
+
+/// Rated hanchan of a player, oldest first
+pub struct History<'a> {
+    pub name: &'a str,
+    pub player: &'a PlayerRating,
+}
+
+impl Localize for History<'_> {
+    fn fmt_localized(&self, f: &mut fmt::Formatter<'_>, locale: Locale) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: R {:.0} ({} {})",
+            self.name,
+            self.player.rating,
+            self.player.games,
+            locale.pick("戦", "sen", "games"),
+        )?;
+        writeln!(f)?;
+        let mut rows = vec![vec![
+            locale.pick("半荘", "Hanchan", "Game").to_owned(),
+            locale.pick("着順", "Chakujun", "Rank").to_owned(),
+            locale.pick("持ち点", "Mochiten", "Score").to_owned(),
+            "R".to_owned(),
+            locale.pick("増減", "Zougen", "Change").to_owned(),
+        ]];
+        let mut previous = INITIAL_RATING;
+        for change in &self.player.history {
+            rows.push(vec![
+                change.hanchan.to_string(),
+                change.placement.to_string(),
+                change.score.to_string(),
+                format!("{:.0}", change.rating),
+                format!("{:+.1}", change.rating - previous),
+            ]);
+            previous = change.rating;
+        }
+        write_table(f, &rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCORES: [isize; 4] = [40000, 30000, 20000, 10000];
+
+    #[test]
+    fn test_record() {
+        let mut ratings = Ratings::default();
+        assert!(ratings.record(["a", "b", "c", "d"], SCORES, [1, 2, 3, 4]));
+        assert_eq!(ratings.rating("a"), 1530.0);
+        assert_eq!(ratings.rating("b"), 1510.0);
+        assert_eq!(ratings.rating("c"), 1490.0);
+        assert_eq!(ratings.rating("d"), 1470.0);
+        assert_eq!(ratings.rating("e"), INITIAL_RATING);
+
+        // The table is weaker than a: it wins less and loses more
+        let reversed = [10000, 20000, 30000, 40000];
+        assert!(ratings.record(["a", "b", "c", "e"], reversed, [4, 3, 2, 1]));
+        let average = (1530.0 + 1510.0 + 1490.0 + 1500.0) / 4.0;
+        let change = games_correction(1) * (-30.0 + (average - 1530.0) / 40.0);
+        assert_eq!(ratings.rating("a"), 1530.0 + change);
+        assert_eq!(
+            ratings.rating("e"),
+            1500.0 + (30.0 + (average - 1500.0) / 40.0)
+        );
+
+        let a = &ratings.players["a"];
+        assert_eq!(a.games, 2);
+        assert_eq!(a.placements, [1, 0, 0, 1]);
+        assert_eq!(a.average_placement(), 2.5);
+        assert_eq!(a.history.len(), 2);
+        assert_eq!(a.history[1].hanchan, 2);
+        assert_eq!(a.history[1].score, 10000);
+        assert_eq!(ratings.hanchan, 2);
+        assert_eq!(ratings.leaderboard()[0].0, "e");
+    }
+
+    #[test]
+    fn test_same_player_twice_is_not_rated() {
+        let mut ratings = Ratings::default();
+        assert!(!ratings.record(["a", "b", "a", "d"], SCORES, [1, 2, 3, 4]));
+        assert_eq!(ratings, Ratings::default());
+    }
+
+    #[test]
+    fn test_games_correction() {
+        assert_eq!(games_correction(0), 1.0);
+        assert!((games_correction(100) - 0.8).abs() < 1e-9);
+        assert!((games_correction(399) - 0.202).abs() < 1e-9);
+        assert_eq!(games_correction(400), 0.2);
+        assert_eq!(games_correction(10000), 0.2);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("mahjong-ratings-{}.ron", std::process::id()));
+        assert_eq!(Ratings::load(&path), Ok(Ratings::default()));
+        let mut ratings = Ratings::default();
+        ratings.record(["a", "b", "c", "d"], SCORES, [2, 1, 4, 3]);
+        ratings.save(&path).unwrap();
+        let loaded = Ratings::load(&path);
+        std::fs::write(&path, "not ratings").unwrap();
+        assert!(Ratings::load(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, Ok(ratings));
+    }
+}
@@ -0,0 +1,100 @@
+//! `mahjong ratings`: leaderboard and history of the ratings file, which
+//! every hanchan updates when `--ratings` is set.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use log::warn;
+
+use mahjong::locale::{Locale, Localize};
+use mahjong::rating::{History, Ratings};
+
+#[derive(Args, Debug)]
+pub struct RatingsArgs {
+    /// Show the history of these players instead of the leaderboard
+    players: Vec<String>,
+    /// Print the ratings as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn run(args: RatingsArgs, path: Option<&Path>, locale: Locale) -> Result<(), String> {
+    let path = path.ok_or("No ratings file: set --ratings or MAHJONG_RATINGS")?;
+    let ratings = Ratings::load(path)?;
+    let players = args
+        .players
+        .iter()
+        .map(|name| {
+            ratings
+                .players
+                .get_key_value(name)
+                .ok_or_else(|| format!("No rating for '{}'", name))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if args.json {
+        let json = if players.is_empty() {
+            serde_json::to_string_pretty(&ratings)
+        } else {
+            serde_json::to_string_pretty(&players.into_iter().collect::<BTreeMap<_, _>>())
+        };
+        println!("{}", json.map_err(|e| e.to_string())?);
+        return Ok(());
+    }
+    if players.is_empty() {
+        print!("{}", ratings.localized(locale));
+    }
+    for (i, (name, player)) in players.into_iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        print!("{}", History { name, player }.localized(locale));
+    }
+    Ok(())
+}
+
+/// Ratings file to update with the results of hanchan, if `--ratings` is
+/// set
+pub struct RatingsFile {
+    path: PathBuf,
+}
+
+impl RatingsFile {
+    /// Check the file before playing, so that a bad file does not waste the
+    /// games
+    pub fn open(path: Option<&Path>) -> Result<Option<Self>, String> {
+        path.map(|path| {
+            Ratings::load(path)?;
+            Ok(RatingsFile {
+                path: path.to_owned(),
+            })
+        })
+        .transpose()
+    }
+
+    /// Rate each hanchan from the names, final scores and placements of
+    /// its players, and save the file.
+    ///
+    /// The file is read again so that edits made while the games were
+    /// played are kept. It is not locked: runs at the same time must use
+    /// different files, or some of their games are lost.
+    pub fn record<'a, I>(self, results: I) -> Result<(), String>
+    where
+        I: IntoIterator<Item = ([&'a str; 4], [isize; 4], [usize; 4])>,
+    {
+        let mut ratings = Ratings::load(&self.path)?;
+        let mut unrated = 0;
+        for (names, scores, placements) in results {
+            if !ratings.record(names, scores, placements) {
+                unrated += 1;
+            }
+        }
+        if unrated > 0 {
+            warn!(
+                "{} hanchan not rated: the same player sat more than once",
+                unrated
+            );
+        }
+        ratings.save(&self.path)
+    }
+}
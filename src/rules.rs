@@ -0,0 +1,91 @@
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::locale::{Locale, Localize};
+
+/// Rules that differ between mahjong parlors and leagues
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Rules {
+    pub chombo: ChomboRule,
+    #[serde(default)]
+    pub shuugi: ShuugiRule,
+    /// Round 4 han 30 fu and 3 han 60 fu up to mangan (切り上げ満貫)
+    #[serde(default)]
+    pub kiriage_mangan: bool,
+}
+
+/// How a chombo (錯和) is settled
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct ChomboRule {
+    pub penalty: Penalty,
+    /// Play the kyoku again (same oya, same honba) after a chombo
+    pub redo: bool,
+}
+
+impl Rules {
+    /// Read rules from a RON file, e.g.
+    /// `(chombo: (penalty: Fixed(8000), redo: false), kiriage_mangan: true)`
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        ron::from_str(&text).map_err(|e| format!("Bad rules {}: {}", path.display(), e))
+    }
+}
+
+impl Default for ChomboRule {
+    fn default() -> Self {
+        Self {
+            penalty: Penalty::Mangan,
+            redo: true,
+        }
+    }
+}
+
+/// Number of bonus chips (祝儀) paid for each event on agari.
+///
+/// On ron, only the player who dealt in pays. On tsumo, every other player
+/// pays. All values default to zero (no chips).
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
+pub struct ShuugiRule {
+    /// Per red five (赤ドラ)
+    pub aka: isize,
+    /// Per ura dora (裏ドラ)
+    pub uradora: isize,
+    /// On ippatsu (一発)
+    pub ippatsu: isize,
+    /// Per yakuman (役満). Doubled on double yakuman.
+    pub yakuman: isize,
+}
+
+impl ShuugiRule {
+    pub fn is_enabled(&self) -> bool {
+        self.aka != 0 || self.uradora != 0 || self.ippatsu != 0 || self.yakuman != 0
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Penalty {
+    /// Pay a mangan to the other players, as if they all won by tsumo (満貫払い)
+    Mangan,
+    /// Deduct a fixed number of points from the offender (罰符). The points
+    /// leave the table: the other players get nothing, as in leagues which
+    /// take a chombo off the final score.
+    Fixed(isize),
+}
+
+impl Localize for Penalty {
+    fn fmt_localized(&self, f: &mut fmt::Formatter<'_>, locale: Locale) -> fmt::Result {
+        match (self, locale) {
+            (Penalty::Mangan, _) => write!(
+                f,
+                "{}",
+                locale.pick("満貫払い", "Mangan barai", "Mangan payment")
+            ),
+            (Penalty::Fixed(points), Locale::Japanese) => write!(f, "罰符{}点", points),
+            (Penalty::Fixed(points), Locale::Romaji) => write!(f, "Bappu {}", points),
+            (Penalty::Fixed(points), Locale::English) => write!(f, "{} point penalty", points),
+        }
+    }
+}
@@ -0,0 +1,414 @@
+//! Danger (危険度) of discarding each tile kind to an opponent, from what a
+//! player can see: the rivers, the riichi tile, the fuuro and the dora.
+
+use serde::Serialize;
+
+use super::ai::TehaiIndex;
+use super::game::{Fuuro, KantsuInner, PlayerView};
+use super::locale::Locale;
+use super::tiles::{Fon, Hai, TileCounts, TileKind};
+
+const WINDS: [Fon; 4] = [Fon::Ton, Fon::Nan, Fon::Shaa, Fon::Pee];
+
+/// Discards of the first row of a river (一段目), which say little about the
+/// wait
+const FIRST_ROW: usize = 6;
+
+/// Number of late tedashi which make the tiles around them more dangerous
+const LATE_TEDASHI: usize = 2;
+
+/// Why a tile is safe or dangerous to discard, roughly from the safest to
+/// the most dangerous
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+pub enum Category {
+    /// Thrown by the target, or by anyone since its riichi (現物): the
+    /// target would be furiten
+    Genbutsu,
+    /// Every ryanmen (両面) on the tile is cut by suji (筋)
+    Suji,
+    /// Every ryanmen on the tile is impossible because all copies of a tile
+    /// it needs are visible (壁)
+    Kabe,
+    /// Every ryanmen on the tile is impossible, some cut by suji and the
+    /// others by a wall (ノーチャンス)
+    NoChance,
+    /// Every ryanmen left on the tile needs a tile with a single unseen
+    /// copy
+    OneChance,
+    /// One of the two ryanmen on the tile is cut by suji (片筋)
+    HalfSuji,
+    /// Not of the suit the target collects in their fuuro (染め手)
+    OtherSuit,
+    /// Honor tile with this many copies visible, own tiles included. Only
+    /// waited on as shanpon or tanki.
+    Honor { visible: usize },
+    /// Nothing cuts the ryanmen on the tile (無筋)
+    Musuji,
+    /// Half suji or musuji tile which is a dora or next to one (ドラそば): a
+    /// wait on it is worth more
+    NearDora,
+}
+
+impl Category {
+    pub fn name(self, locale: Locale) -> String {
+        let name = match self {
+            Category::Genbutsu => locale.pick("現物", "Genbutsu", "Safe"),
+            Category::Suji => locale.pick("筋", "Suji", "Suji"),
+            Category::Kabe => locale.pick("壁", "Kabe", "Wall"),
+            Category::NoChance => locale.pick("ノーチャンス", "No chance", "No chance"),
+            Category::OneChance => locale.pick("ワンチャンス", "One chance", "One chance"),
+            Category::HalfSuji => locale.pick("片筋", "Katasuji", "Half suji"),
+            Category::OtherSuit => locale.pick("他色", "Tashoku", "Other suit"),
+            Category::Honor { visible } => {
+                return format!("{} {}", locale.pick("字牌", "Jihai", "Honor"), visible)
+            }
+            Category::Musuji => locale.pick("無筋", "Musuji", "No suji"),
+            Category::NearDora => locale.pick("ドラそば", "Dora soba", "Next to dora"),
+        };
+        name.to_owned()
+    }
+}
+
+/// Safety of discarding a tile kind
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TileSafety {
+    pub hai: Hai,
+    pub category: Category,
+    /// Dora or next to a dora (ドラそば)
+    pub near_dora: bool,
+    /// Within two of a tile the target threw from its te late, before its
+    /// riichi: the wait is often next to the last tiles taken out
+    pub near_late_tedashi: bool,
+    /// Estimated danger: 0 is safe, higher is more dangerous. Only the
+    /// relative order is meaningful.
+    pub danger: u32,
+}
+
+/// How a ryanmen which could wait on a tile is ruled out
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Ryanmen {
+    Suji,
+    Kabe,
+    OneChance,
+    Open,
+}
+
+/// Safety of discarding each tile kind to `target`, in kind order.
+///
+/// Looks at the waits which could hit each tile: ryanmen unless cut by suji
+/// or kabe, and kanchan, penchan, shanpon and tanki unless the tiles they
+/// need are all visible. Tiles around the riichi tile (跨ぎ筋), around the
+/// last tiles the target threw from its te after the first row of its river
+/// (手出し), and near dora are more dangerous.
+pub fn analyze_safety(view: &PlayerView, target: Fon) -> Vec<TileSafety> {
+    let discards = view.discards(target);
+    let riichi = discards.iter().find(|discard| discard.riichi);
+    let riichi_hai = riichi.map(|discard| TileKind::from(discard.hai));
+    // Tiles the target threw, called ones included, and tiles the others
+    // threw since its riichi: the target would be furiten on all of them
+    let after_riichi = WINDS
+        .iter()
+        .filter(|p| **p != target)
+        .flat_map(|p| view.discards(*p))
+        .filter(|discard| riichi.map_or(false, |riichi| discard.order > riichi.order));
+    // Last tedashi before the riichi tile, the riichi tile aside
+    let mut late_tedashi: Vec<TileKind> = discards
+        .iter()
+        .enumerate()
+        .take_while(|(_, discard)| !discard.riichi)
+        .filter(|(i, discard)| *i >= FIRST_ROW && discard.tedashi)
+        .map(|(_, discard)| discard.hai.into())
+        .collect();
+    late_tedashi.drain(..late_tedashi.len().saturating_sub(LATE_TEDASHI));
+    let genbutsu: TileCounts = discards
+        .iter()
+        .chain(after_riichi)
+        .map(|discard| discard.hai)
+        .collect();
+    let context = view.win_context();
+    let dora: Vec<TileKind> = context.dora().into_iter().map(TileKind::from).collect();
+    let suit = collected_suit(view.fuuro(target));
+
+    let te = view.te();
+    let own: TileCounts = te
+        .hai()
+        .iter()
+        .copied()
+        .chain(te.get(TehaiIndex::Tsumohai))
+        .chain(te.fuuro().iter().flat_map(Fuuro::hai))
+        .collect();
+    // Copies the player cannot see
+    let unseen = |kind: TileKind| {
+        4usize.saturating_sub(own[kind] as usize + view.visible_count(kind.to_hai()))
+    };
+    let unseen_at = |kind: TileKind, offset: isize| kind.offset(offset).map_or(0, unseen);
+
+    TileKind::all()
+        .map(|kind| {
+            let near = |tiles: &[TileKind]| {
+                [-2, -1, 1, 2]
+                    .iter()
+                    .any(|i| kind.offset(*i).map_or(false, |kind| tiles.contains(&kind)))
+            };
+            let near_late_tedashi = near(&late_tedashi);
+            let near_dora = dora
+                .iter()
+                .any(|dora| [-1, 0, 1].iter().any(|i| kind.offset(*i) == Some(*dora)));
+            let dora_danger = if dora.contains(&kind) {
+                2
+            } else {
+                near_dora as u32
+            };
+
+            let (category, danger) = if genbutsu[kind] > 0 {
+                (Category::Genbutsu, 0)
+            } else if let Some(value) = kind.value() {
+                // Ryanmen below and above the tile, with their suji
+                let mut ryanmen = vec![];
+                for (lower, upper, suji) in [(-2, -1, -3), (1, 2, 3)] {
+                    let suji = match kind.offset(suji) {
+                        Some(suji) => suji,
+                        None => continue,
+                    };
+                    let taatsu = unseen_at(kind, lower).min(unseen_at(kind, upper));
+                    ryanmen.push(if genbutsu[suji] > 0 {
+                        Ryanmen::Suji
+                    } else if taatsu == 0 {
+                        Ryanmen::Kabe
+                    } else if taatsu == 1 {
+                        Ryanmen::OneChance
+                    } else {
+                        Ryanmen::Open
+                    });
+                }
+
+                let mut danger: u32 = ryanmen
+                    .iter()
+                    .map(|ryanmen| match ryanmen {
+                        Ryanmen::Suji | Ryanmen::Kabe => 0,
+                        Ryanmen::OneChance => 2,
+                        Ryanmen::Open => 4,
+                    })
+                    .sum();
+                // Kanchan (嵌張)
+                if unseen_at(kind, -1) > 0 && unseen_at(kind, 1) > 0 {
+                    danger += 1;
+                }
+                // Penchan (辺張)
+                if (value == 3 && unseen_at(kind, -1) > 0 && unseen_at(kind, -2) > 0)
+                    || (value == 7 && unseen_at(kind, 1) > 0 && unseen_at(kind, 2) > 0)
+                {
+                    danger += 1;
+                }
+                // Shanpon (双碰) and tanki (単騎)
+                danger += [0, 1, 2, 2, 2][unseen(kind)];
+                if riichi_hai.map_or(false, |riichi_hai| near(&[riichi_hai])) {
+                    danger += 1;
+                }
+                if near_late_tedashi {
+                    danger += 1;
+                }
+                danger += dora_danger;
+
+                let cut = |ryanmen: &Ryanmen| matches!(ryanmen, Ryanmen::Suji | Ryanmen::Kabe);
+                let category = if suit.map_or(false, |suit| kind.index() / 9 != suit) {
+                    danger /= 4;
+                    Category::OtherSuit
+                } else if ryanmen.iter().all(|ryanmen| *ryanmen == Ryanmen::Suji) {
+                    Category::Suji
+                } else if ryanmen.iter().all(|ryanmen| *ryanmen == Ryanmen::Kabe) {
+                    Category::Kabe
+                } else if ryanmen.iter().all(cut) {
+                    Category::NoChance
+                } else if ryanmen
+                    .iter()
+                    .all(|ryanmen| cut(ryanmen) || *ryanmen == Ryanmen::OneChance)
+                {
+                    Category::OneChance
+                } else if near_dora {
+                    Category::NearDora
+                } else if ryanmen.contains(&Ryanmen::Suji) {
+                    Category::HalfSuji
+                } else {
+                    Category::Musuji
+                };
+                (category, danger)
+            } else {
+                let unseen = unseen(kind);
+                let mut danger = [0, 1, 3, 5, 5][unseen];
+                // A pon would give the target a yaku
+                if unseen >= 2 && kind.to_hai().is_yakuhai(context.bakaze, target) {
+                    danger += 1;
+                }
+                let visible = 4 - unseen;
+                (Category::Honor { visible }, danger + dora_danger)
+            };
+
+            TileSafety {
+                hai: kind.to_hai(),
+                category,
+                near_dora,
+                near_late_tedashi,
+                danger,
+            }
+        })
+        .collect()
+}
+
+/// Suit (0 to 2 for man, pin and sou) of all the open calls of a player
+/// with at least 2 of them, honors aside
+fn collected_suit(fuuro: &[Fuuro]) -> Option<usize> {
+    let open: Vec<_> = fuuro
+        .iter()
+        .filter(|fuuro| !matches!(fuuro, Fuuro::Kantsu(KantsuInner::Ankan { .. })))
+        .collect();
+    if open.len() < 2 {
+        return None;
+    }
+    let mut suits = open
+        .iter()
+        .flat_map(|fuuro| fuuro.hai())
+        .map(TileKind::from)
+        .filter(|kind| kind.is_suuhai())
+        .map(|kind| kind.index() / 9);
+    let suit = suits.next()?;
+    suits.all(|other| other == suit).then_some(suit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::tiles::hai_list_from_str;
+
+    fn kind(s: &str) -> TileKind {
+        hai_list_from_str(s).unwrap()[0].into()
+    }
+
+    #[test]
+    fn test_analyze_safety() {
+        let mut game = Game::default();
+        *game.player_te_mut(Fon::Nan) = "4m1s7z".parse().unwrap();
+        game.throw_tile(Fon::Nan, TehaiIndex::Tehai(0), false);
+        game.throw_tile(Fon::Nan, TehaiIndex::Tehai(0), false);
+        game.throw_tile(Fon::Nan, TehaiIndex::Tehai(0), true);
+        *game.player_te_mut(Fon::Ton) = "3333p777s".parse().unwrap();
+        let view = game.player_view(Fon::Ton);
+        let safety = analyze_safety(&view, Fon::Nan);
+        let get = |s| &safety[kind(s).index()];
+        let category = |s| get(s).category;
+
+        assert_eq!(category("4m"), Category::Genbutsu);
+        assert_eq!(category("7z"), Category::Genbutsu);
+        assert_eq!(get("7z").danger, 0);
+        assert_eq!(category("1m"), Category::Suji);
+        assert_eq!(category("7m"), Category::Suji);
+        assert_eq!(category("4s"), Category::HalfSuji);
+        assert_eq!(category("5m"), Category::Musuji);
+        // All the 3p are in the te: no ryanmen on 1p and 2p
+        assert_eq!(category("1p"), Category::Kabe);
+        assert_eq!(category("2p"), Category::Kabe);
+        // A single 7s left for a 78s taatsu
+        assert_eq!(category("9s"), Category::OneChance);
+        assert_eq!(category("1z"), Category::Honor { visible: 0 });
+
+        assert!(get("1m").danger < get("2m").danger);
+        assert!(get("7m").danger < get("6m").danger);
+        assert!(get("4s").danger < get("5s").danger);
+        assert!(get("2p").danger < get("5p").danger);
+        assert!(get("2z").danger < get("5s").danger);
+    }
+
+    #[test]
+    fn test_genbutsu_called_and_after_riichi() {
+        let mut game = Game::default();
+        *game.player_te_mut(Fon::Nan) = "4m5p1z".parse().unwrap();
+        *game.player_te_mut(Fon::Shaa) = "44m1z".parse().unwrap();
+        *game.player_te_mut(Fon::Pee) = "2s9s".parse().unwrap();
+        game.throw_tile(Fon::Pee, TehaiIndex::Tehai(0), false);
+        game.throw_tile(Fon::Nan, TehaiIndex::Tehai(0), false);
+        game.turn = Fon::Shaa;
+        game.call_pon(Fon::Shaa);
+        game.throw_tile(Fon::Nan, TehaiIndex::Tehai(0), true);
+        game.throw_tile(Fon::Pee, TehaiIndex::Tehai(0), false);
+        let view = game.player_view(Fon::Ton);
+        assert!(view.river(Fon::Nan).len() == 1);
+        let safety = analyze_safety(&view, Fon::Nan);
+        let category = |s| safety[kind(s).index()].category;
+        // Called by Shaa
+        assert_eq!(category("4m"), Category::Genbutsu);
+        // Riichi tile
+        assert_eq!(category("5p"), Category::Genbutsu);
+        // Thrown by Pee after the riichi
+        assert_eq!(category("9s"), Category::Genbutsu);
+        // Thrown by Pee before the riichi
+        assert_ne!(category("2s"), Category::Genbutsu);
+    }
+
+    #[test]
+    fn test_late_tedashi() {
+        let mut game = Game::default();
+        let mut throw = |te: &str, i: TehaiIndex, riichi: bool| {
+            *game.player_te_mut(Fon::Nan) = te.parse().unwrap();
+            game.throw_tile(Fon::Nan, i, riichi);
+        };
+        // First row, with a tedashi 5s
+        for te in ["5s", "1z", "2z", "3z", "4z", "5z"] {
+            throw(te, TehaiIndex::Tehai(0), false);
+        }
+        throw("5m", TehaiIndex::Tehai(0), false);
+        // Tsumogiri
+        throw("1m 7p", TehaiIndex::Tsumohai, false);
+        throw("9s", TehaiIndex::Tehai(0), true);
+        let view = game.player_view(Fon::Ton);
+        let safety = analyze_safety(&view, Fon::Nan);
+        let get = |s| &safety[kind(s).index()];
+
+        assert!(get("3m").near_late_tedashi);
+        assert!(get("7m").near_late_tedashi);
+        assert!(!get("3s").near_late_tedashi);
+        assert!(!get("6p").near_late_tedashi);
+        assert!(!get("1z").near_late_tedashi);
+        assert_eq!(get("3m").danger, get("3s").danger + 1);
+    }
+
+    #[test]
+    fn test_analyze_safety_other_suit() {
+        let mut game = Game::default();
+        *game.player_te_mut(Fon::Nan) = "1z [2-34m] [55-5m]".parse().unwrap();
+        let view = game.player_view(Fon::Ton);
+        let safety = analyze_safety(&view, Fon::Nan);
+        let get = |s| &safety[kind(s).index()];
+        assert_eq!(get("5p").category, Category::OtherSuit);
+        assert_eq!(get("5m").category, Category::Musuji);
+        assert!(get("5p").danger < get("6m").danger);
+    }
+
+    #[test]
+    fn test_kabe_and_near_dora() {
+        let mut game = Game::default();
+        assert_eq!(game.dora(), hai_list_from_str("2p").unwrap());
+        *game.player_te_mut(Fon::Nan) = "1p9s".parse().unwrap();
+        game.throw_tile(Fon::Nan, TehaiIndex::Tehai(0), false);
+        game.throw_tile(Fon::Nan, TehaiIndex::Tehai(0), true);
+        *game.player_te_mut(Fon::Ton) = "6666p".parse().unwrap();
+        let view = game.player_view(Fon::Ton);
+        let safety = analyze_safety(&view, Fon::Nan);
+        let get = |s| &safety[kind(s).index()];
+
+        // All the 6p are in the te: no ryanmen on 7p and 8p
+        assert_eq!(get("7p").category, Category::Kabe);
+        assert_eq!(get("8p").category, Category::Kabe);
+        // 23p cut by the suji 1p, 56p by the wall
+        assert_eq!(get("4p").category, Category::NoChance);
+        assert_eq!(get("5p").category, Category::Musuji);
+        // Dora and next to the dora
+        assert_eq!(get("2p").category, Category::NearDora);
+        assert_eq!(get("3p").category, Category::NearDora);
+        assert_eq!(get("3s").category, Category::Musuji);
+
+        assert!(get("8p").danger < get("3s").danger);
+        assert!(get("4p").danger < get("3s").danger);
+        assert_eq!(get("3p").danger, get("3s").danger + 1);
+    }
+}
@@ -0,0 +1,206 @@
+//! `mahjong score`: score an arbitrary winning hand.
+
+use clap::Args;
+
+use mahjong::game::Fuuro;
+use mahjong::locale::{Label, Locale, Localize};
+use mahjong::mpsz;
+use mahjong::rules::Rules;
+use mahjong::tiles::{self, Fon, Hai};
+use mahjong::yaku::{self, WinContext, WinningMethod};
+
+#[derive(Args, Debug)]
+pub struct ScoreArgs {
+    /// Hidden tiles, without the winning tile (e.g. 123456789m1239p)
+    #[arg(long, value_parser = parse_tiles)]
+    hand: Tiles,
+    /// Winning tile
+    #[arg(long, value_parser = parse_hai)]
+    winning_tile: Hai,
+    /// Called meld in MPSZ notation (e.g. [1-23m], [55-5p], [1111z]).
+    /// Can be repeated.
+    #[arg(long = "meld", value_parser = mpsz::parse_fuuro)]
+    melds: Vec<Fuuro>,
+    /// Win by tsumo (ron otherwise)
+    #[arg(long)]
+    tsumo: bool,
+    /// Seat wind of the winner
+    #[arg(long, value_enum, default_value_t = Wind::East)]
+    seat_wind: Wind,
+    /// Round wind
+    #[arg(long, value_enum, default_value_t = Wind::East)]
+    round_wind: Wind,
+    /// Seat wind of the player who dealt in on ron
+    #[arg(long, value_enum)]
+    discarder: Option<Wind>,
+    /// Dora indicators
+    #[arg(long, value_parser = parse_tiles, default_value = "")]
+    dora: Tiles,
+    /// Ura dora indicators (only counted in riichi)
+    #[arg(long, value_parser = parse_tiles, default_value = "")]
+    uradora: Tiles,
+    #[arg(long)]
+    riichi: bool,
+    #[arg(long)]
+    double_riichi: bool,
+    #[arg(long)]
+    ippatsu: bool,
+    /// Win on the last tile (haitei on tsumo, houtei on ron)
+    #[arg(long)]
+    last_tile: bool,
+    /// Tsumo on the kan supplementary tile
+    #[arg(long)]
+    rinshan: bool,
+    /// Ron on kakan
+    #[arg(long)]
+    chankan: bool,
+    #[arg(long, default_value_t = 0)]
+    honba: usize,
+    /// Riichi sticks on the table
+    #[arg(long, default_value_t = 0)]
+    riichi_bou: usize,
+    /// Round 4 han 30 fu and 3 han 60 fu up to mangan
+    #[arg(long)]
+    kiriage_mangan: bool,
+    /// Print the breakdown as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Wind {
+    East,
+    South,
+    West,
+    North,
+}
+
+impl From<Wind> for Fon {
+    fn from(wind: Wind) -> Self {
+        match wind {
+            Wind::East => Fon::Ton,
+            Wind::South => Fon::Nan,
+            Wind::West => Fon::Shaa,
+            Wind::North => Fon::Pee,
+        }
+    }
+}
+
+/// List of tiles given as a single argument
+#[derive(Debug, Clone)]
+struct Tiles(Vec<Hai>);
+
+fn parse_tiles(s: &str) -> Result<Tiles, tiles::ParseHaiError> {
+    tiles::hai_list_from_str(s).map(Tiles)
+}
+
+fn parse_hai(s: &str) -> Result<Hai, tiles::ParseHaiError> {
+    s.parse()
+}
+
+fn check_tile_count(args: &ScoreArgs) -> Result<(), String> {
+    let expected = 13 - 3 * args.melds.len().min(4);
+    if args.hand.0.len() == expected {
+        Ok(())
+    } else {
+        Err(format!(
+            "Expected {} tiles in hand besides the winning tile, got {}",
+            expected,
+            args.hand.0.len()
+        ))
+    }
+}
+
+/// Reject flags which cannot go together
+fn check_flags(args: &ScoreArgs) -> Result<(), String> {
+    if args.ippatsu && !args.riichi && !args.double_riichi {
+        return Err("--ippatsu needs --riichi or --double-riichi".to_owned());
+    }
+    match args.discarder {
+        Some(_) if args.tsumo => Err("--discarder is only for ron".to_owned()),
+        Some(discarder) if discarder == args.seat_wind => {
+            Err("The discarder cannot be the winner".to_owned())
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn run(args: ScoreArgs, locale: Locale) -> Result<(), String> {
+    check_tile_count(&args)?;
+    check_flags(&args)?;
+    let method = if args.tsumo {
+        WinningMethod::Tsumo
+    } else {
+        WinningMethod::Ron
+    };
+    let context = WinContext {
+        bakaze: args.round_wind.into(),
+        jikaze: args.seat_wind.into(),
+        dora_indicators: args.dora.0,
+        uradora_indicators: args.uradora.0,
+        riichi: args.riichi || args.double_riichi,
+        double_riichi: args.double_riichi,
+        ippatsu: args.ippatsu,
+        haitei: args.last_tile && args.tsumo,
+        houtei: args.last_tile && !args.tsumo,
+        rinshan: args.rinshan,
+        chankan: args.chankan,
+        discarder: args.discarder.map(Fon::from),
+        honba: args.honba,
+        riichi_bou: args.riichi_bou,
+        ..Default::default()
+    };
+    let rules = Rules {
+        kiriage_mangan: args.kiriage_mangan,
+        ..Default::default()
+    };
+    let breakdown = yaku::score(
+        &args.hand.0,
+        &args.melds,
+        args.winning_tile,
+        method,
+        &context,
+        &rules,
+    );
+    if args.json {
+        let json = serde_json::to_string_pretty(&breakdown).map_err(|e| e.to_string())?;
+        println!("{}", json);
+    } else if breakdown.is_agari() {
+        println!("{}", breakdown.localized(locale));
+    } else {
+        println!("{}", Label::NoYaku.text(locale));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        args: ScoreArgs,
+    }
+
+    fn check(flags: &[&str]) -> Result<(), String> {
+        let hand = ["score", "--hand", "123456789m1239p", "--winning-tile", "9p"];
+        let cli = Cli::try_parse_from(hand.iter().chain(flags)).expect("Valid arguments");
+        check_tile_count(&cli.args)?;
+        check_flags(&cli.args)
+    }
+
+    #[test]
+    fn test_check_flags() {
+        assert_eq!(check(&[]), Ok(()));
+        assert_eq!(check(&["--riichi", "--ippatsu"]), Ok(()));
+        assert_eq!(check(&["--double-riichi", "--ippatsu"]), Ok(()));
+        assert!(check(&["--ippatsu"]).is_err());
+        assert_eq!(check(&["--discarder", "west"]), Ok(()));
+        assert!(check(&["--discarder", "east"]).is_err());
+        assert!(check(&["--seat-wind", "west", "--discarder", "west"]).is_err());
+        assert!(check(&["--tsumo", "--discarder", "west"]).is_err());
+        assert_eq!(check(&["--tsumo"]), Ok(()));
+    }
+}
@@ -0,0 +1,307 @@
+//! Fast shanten (向聴数) calculation on tile counts.
+//!
+//! Mentsu and taatsu never span two suits, so each suit is decomposed on
+//! its own. The best decompositions of each suit are looked up in a table
+//! filled on first use, then the 4 suits are combined.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use super::tiles::{Hai, TileCounts, TileKind};
+
+/// Shanten of hidden tiles, 3 less per fuuro: 0 when tenpai, -1 for a
+/// complete te.
+pub fn shanten(te: &[Hai]) -> isize {
+    let melds = (14 - te.len().min(14)) / 3;
+    shanten_counts(&TileCounts::from_hai(te), melds)
+}
+
+/// Shanten of hidden tiles given as counts, with `melds` fuuro.
+pub fn shanten_counts(counts: &TileCounts, melds: usize) -> isize {
+    let mut shanten = normal_shanten(counts, melds);
+    if melds == 0 && counts.total() >= 13 {
+        shanten = shanten
+            .min(chiitoitsu_shanten(counts))
+            .min(kokushimusou_shanten(counts));
+    }
+    shanten
+}
+
+/// Shanten of the 4 mentsu 1 jantou form only
+pub fn normal_shanten(counts: &TileCounts, melds: usize) -> isize {
+    let counts = counts.as_array();
+    let melds = melds.min(4);
+    // Most taatsu for each number of mentsu and jantou (-1 if impossible),
+    // combining the suits one after the other
+    let mut total = SuitValue::empty();
+    total.best[0][melds] = 0;
+    SUIT_TABLE.with(|table| {
+        let mut table = table.borrow_mut();
+        for (start, len) in [(0, 9), (9, 9), (18, 9), (27, 7)] {
+            let suit = table.get(&counts[start..start + len], start < 27);
+            total = total.combine(&suit);
+        }
+    });
+
+    let mut best = 8;
+    for (head, taatsu_by_mentsu) in total.best.iter().enumerate() {
+        for (mentsu, taatsu) in taatsu_by_mentsu.iter().enumerate() {
+            if *taatsu >= 0 {
+                let taatsu = (*taatsu as usize).min(4 - mentsu);
+                let shanten = 8 - 2 * mentsu as isize - taatsu as isize - head as isize;
+                best = best.min(shanten);
+            }
+        }
+    }
+    best
+}
+
+pub fn chiitoitsu_shanten(counts: &TileCounts) -> isize {
+    let toitsu = counts.iter().filter(|(_, c)| *c >= 2).count() as isize;
+    let kinds = counts.iter().count() as isize;
+    6 - toitsu + (7 - kinds).max(0)
+}
+
+pub fn kokushimusou_shanten(counts: &TileCounts) -> isize {
+    let yaochuu: Vec<_> = counts
+        .iter()
+        .filter(|(kind, _)| kind.is_jihai_or_1_9())
+        .collect();
+    let kinds = yaochuu.len() as isize;
+    let toitsu = yaochuu.iter().any(|(_, c)| *c >= 2);
+    13 - kinds - toitsu as isize
+}
+
+/// Kinds which complete a tenpai te (hidden tiles, 3 less per fuuro),
+/// excluding those the te already holds 4 of
+pub fn machi(counts: &TileCounts, melds: usize) -> Vec<TileKind> {
+    let mut counts = *counts;
+    let mut machi = vec![];
+    for kind in TileKind::all() {
+        if counts[kind] >= 4 {
+            continue;
+        }
+        counts.add(kind);
+        if shanten_counts(&counts, melds) == -1 {
+            machi.push(kind);
+        }
+        counts.remove(kind);
+    }
+    machi
+}
+
+thread_local! {
+    static SUIT_TABLE: RefCell<SuitTable> = RefCell::new(SuitTable::default());
+}
+
+/// Best decompositions of each suit already seen, keyed by the counts of
+/// the suit in base 5
+#[derive(Default)]
+struct SuitTable {
+    suuhai: HashMap<u32, SuitValue>,
+    jihai: HashMap<u32, SuitValue>,
+}
+
+impl SuitTable {
+    fn get(&mut self, counts: &[u8], is_suuhai: bool) -> SuitValue {
+        let key = counts.iter().fold(0, |key, c| 5 * key + *c as u32);
+        let table = if is_suuhai {
+            &mut self.suuhai
+        } else {
+            &mut self.jihai
+        };
+        *table
+            .entry(key)
+            .or_insert_with(|| SuitValue::compute(counts, is_suuhai))
+    }
+}
+
+/// Most taatsu that can be made in a suit, for each number of jantou (0 or
+/// 1) and mentsu (0 to 4). -1 if the suit cannot hold as many mentsu.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct SuitValue {
+    best: [[i8; 5]; 2],
+}
+
+impl SuitValue {
+    fn empty() -> Self {
+        Self { best: [[-1; 5]; 2] }
+    }
+
+    fn compute(counts: &[u8], is_suuhai: bool) -> Self {
+        let mut value = Self::empty();
+        let mut counts = counts.to_vec();
+        value.search(&mut counts, 0, is_suuhai, 0, 0, 0);
+        value
+    }
+
+    /// Take away groups from `counts`, starting from kind `k`
+    fn search(
+        &mut self,
+        counts: &mut [u8],
+        mut k: usize,
+        is_suuhai: bool,
+        mentsu: usize,
+        taatsu: usize,
+        head: usize,
+    ) {
+        while k < counts.len() && counts[k] == 0 {
+            k += 1;
+        }
+        if k == counts.len() {
+            let best = &mut self.best[head][mentsu];
+            *best = (*best).max(taatsu.min(4) as i8);
+            return;
+        }
+        let len = counts.len();
+
+        if mentsu < 4 {
+            if counts[k] >= 3 {
+                counts[k] -= 3;
+                self.search(counts, k, is_suuhai, mentsu + 1, taatsu, head);
+                counts[k] += 3;
+            }
+            if is_suuhai && k + 2 < len && counts[k + 1] > 0 && counts[k + 2] > 0 {
+                counts[k] -= 1;
+                counts[k + 1] -= 1;
+                counts[k + 2] -= 1;
+                self.search(counts, k, is_suuhai, mentsu + 1, taatsu, head);
+                counts[k] += 1;
+                counts[k + 1] += 1;
+                counts[k + 2] += 1;
+            }
+        }
+        if counts[k] >= 2 {
+            counts[k] -= 2;
+            if head == 0 {
+                self.search(counts, k, is_suuhai, mentsu, taatsu, 1);
+            }
+            self.search(counts, k, is_suuhai, mentsu, taatsu + 1, head);
+            counts[k] += 2;
+        }
+        if is_suuhai && k + 1 < len && counts[k + 1] > 0 {
+            counts[k] -= 1;
+            counts[k + 1] -= 1;
+            self.search(counts, k, is_suuhai, mentsu, taatsu + 1, head);
+            counts[k] += 1;
+            counts[k + 1] += 1;
+        }
+        if is_suuhai && k + 2 < len && counts[k + 2] > 0 {
+            counts[k] -= 1;
+            counts[k + 2] -= 1;
+            self.search(counts, k, is_suuhai, mentsu, taatsu + 1, head);
+            counts[k] += 1;
+            counts[k + 2] += 1;
+        }
+        // Leave this tile isolated
+        counts[k] -= 1;
+        self.search(counts, k, is_suuhai, mentsu, taatsu, head);
+        counts[k] += 1;
+    }
+
+    /// Best decompositions of the tiles of both `self` and `other`
+    fn combine(&self, other: &Self) -> Self {
+        let mut out = Self::empty();
+        for h1 in 0..2 {
+            for h2 in 0..2 - h1 {
+                for m1 in 0..5 {
+                    for m2 in 0..5 - m1 {
+                        let (t1, t2) = (self.best[h1][m1], other.best[h2][m2]);
+                        if t1 >= 0 && t2 >= 0 {
+                            let best = &mut out.best[h1 + h2][m1 + m2];
+                            *best = (*best).max((t1 + t2).min(4));
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::tests::{count_shanten_with_solver, find_machi_with_solver};
+    use crate::tiles::{hai_list_from_str, make_all_tiles};
+    use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+    #[test]
+    fn test_shanten() {
+        let shanten_of = |s| shanten(&hai_list_from_str(s).unwrap());
+        assert_eq!(shanten_of("123m456p789s11222z"), -1);
+        assert_eq!(shanten_of("123m456p789s1122z"), 0);
+        assert_eq!(shanten_of("1199m2288p3377s5z"), 0);
+        assert_eq!(shanten_of("19m19p19s1234567z"), 0);
+        assert_eq!(shanten_of("19m19p19s12345677z"), -1);
+        assert_eq!(shanten_of("1357m2468p1357s1z"), 4);
+        assert_eq!(shanten_of("1111m"), 0);
+        // Open te with 2 fuuro
+        assert_eq!(shanten_of("456p789s1z"), 0);
+        assert_eq!(shanten_of("456p789s11z"), -1);
+    }
+
+    /// Compare with the tree solver, which stops at 0 on a complete te and
+    /// finds nothing (`usize::MAX`) in a te without any group
+    fn check_against_solver(te: &[Hai]) {
+        let shanten = shanten(te);
+        match count_shanten_with_solver(te) {
+            // Only single tiles: each fuuro less to make costs 2
+            usize::MAX => {
+                let melds = (14 - te.len()) / 3;
+                assert_eq!(shanten, 8 - 2 * melds as isize, "{:?}", te);
+            }
+            // Complete: some tile is a wait of the te without it
+            0 if shanten == -1 => assert!(
+                (0..te.len()).any(|i| {
+                    let mut rest = te.to_vec();
+                    let hai = rest.remove(i);
+                    find_machi_with_solver(&rest).contains(&hai)
+                }),
+                "{:?}",
+                te
+            ),
+            expected => assert_eq!(shanten, expected as isize, "{:?}", te),
+        }
+    }
+
+    /// Compare with the tree solver on random te, closed and open, and on
+    /// complete te
+    #[test]
+    fn test_shanten_agrees_with_solver() {
+        for te in [
+            "123m456p789s11222z",
+            "111m345p789555s11z",
+            "456p789s11z",
+            "123m11z",
+        ] {
+            let te = hai_list_from_str(te).unwrap();
+            assert_eq!(shanten(&te), -1);
+            check_against_solver(&te);
+        }
+
+        let mut rng = StdRng::seed_from_u64(39);
+        let mut tiles = make_all_tiles();
+        for i in 0..120 {
+            tiles.shuffle(&mut rng);
+            let len = [13, 14, 10, 11, 7, 8, 4, 5][i % 8];
+            check_against_solver(&tiles[..len]);
+        }
+    }
+
+    #[test]
+    fn test_shanten_agrees_with_solver_near_tenpai() {
+        let mut rng = StdRng::seed_from_u64(3939);
+        let all = make_all_tiles();
+        for _ in 0..60 {
+            // Draw from a single suit and the honors to get connected te
+            let mut tiles: Vec<_> = all[..9]
+                .iter()
+                .chain(&all[27..34])
+                .flat_map(|hai| [*hai; 4])
+                .collect();
+            tiles.shuffle(&mut rng);
+            check_against_solver(&tiles[..13]);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! `mahjong simulate`: play many hanchan between bots without interface.
+
+use std::path::Path;
+
+use clap::Args;
+
+use mahjong::locale::{Locale, Localize};
+use mahjong::simulation::{self, Stats};
+
+use super::ratings_cmd::RatingsFile;
+use super::{make_ai_server, parse_player, threads, Player};
+
+#[derive(Args, Debug)]
+pub struct SimulateArgs {
+    /// Number of hanchan to play
+    #[arg(long, default_value_t = 100)]
+    games: usize,
+    /// Seed of the walls. The same seed plays the same games.
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Number of threads (number of CPUs by default, 1 with an mjai-tcp
+    /// bot)
+    #[arg(long)]
+    threads: Option<usize>,
+    /// Player 1, first oya: a bot spec (see --list-bots)
+    #[arg(long, value_parser = parse_player, default_value = "dumb-caller")]
+    p1: Player,
+    #[arg(long, value_parser = parse_player, default_value = "dumb-caller")]
+    p2: Player,
+    #[arg(long, value_parser = parse_player, default_value = "dumb-caller")]
+    p3: Player,
+    #[arg(long, value_parser = parse_player, default_value = "dumb-caller")]
+    p4: Player,
+    /// Print the stats as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn run(args: SimulateArgs, ratings: Option<&Path>, locale: Locale) -> Result<(), String> {
+    let players = [args.p1, args.p2, args.p3, args.p4];
+    if players
+        .iter()
+        .any(|player| matches!(player, Player::CursiveHuman))
+    {
+        return Err("cursive-human cannot play in a simulation".to_owned());
+    }
+    let ratings = RatingsFile::open(ratings)?;
+    let threads = threads(args.threads, &players)?;
+
+    let records = simulation::simulate(args.games, args.seed, threads, || {
+        [0, 1, 2, 3].map(|i| make_ai_server(&players[i], locale))
+    });
+    if let Some(ratings) = ratings {
+        let specs = [0, 1, 2, 3].map(|i| players[i].to_string());
+        let specs = [0, 1, 2, 3].map(|i| specs[i].as_str());
+        ratings.record(
+            records
+                .iter()
+                .map(|record| (specs, record.scores, record.placements)),
+        )?;
+    }
+    let names = [0, 1, 2, 3].map(|i| format!("p{} ({})", i + 1, players[i]));
+    let stats = Stats::new(names, &records);
+
+    if args.json {
+        let json = serde_json::to_string_pretty(&stats).map_err(|e| e.to_string())?;
+        println!("{}", json);
+    } else {
+        print!("{}", stats.localized(locale));
+    }
+    Ok(())
+}
@@ -0,0 +1,341 @@
+//! Headless hanchan between bots, with aggregate stats.
+//!
+//! Players are indexed by their first seat: player 0 is the first oya
+//! (起家). Records and stats always follow players, not seats.
+
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Serialize;
+
+use super::ai::AiServer;
+use super::game::{Fuuro, Game, KantsuInner, KyokuResult};
+use super::locale::{Locale, Localize};
+use super::tiles::Fon;
+
+const WINDS: [Fon; 4] = [Fon::Ton, Fon::Nan, Fon::Shaa, Fon::Pee];
+
+/// What each player did in a kyoku
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct KyokuRecord {
+    pub ryukyoku: bool,
+    pub chombo: bool,
+    pub agari: [bool; 4],
+    /// Discarded the winning tile of a ron (放銃)
+    pub deal_in: [bool; 4],
+    pub riichi: [bool; 4],
+    /// Made at least one open call (副露)
+    pub called: [bool; 4],
+}
+
+impl KyokuRecord {
+    fn new(game: &Game, result: &KyokuResult) -> Self {
+        let mut record = KyokuRecord::default();
+        // The first oya is at index `kyoku`
+        let seat = |player: usize| WINDS[(player + game.kyoku()) % 4];
+        let player = |fon: Fon| (fon as usize + 4 - game.kyoku() % 4) % 4;
+        match result {
+            KyokuResult::Agari { winners, .. } => {
+                for (winner, breakdown) in winners {
+                    record.agari[player(*winner)] = true;
+                    // Only the discarder pays for a ron
+                    let payers: Vec<_> = (0..4).filter(|i| breakdown.payments[*i] < 0).collect();
+                    if let [discarder] = payers[..] {
+                        record.deal_in[player(WINDS[discarder])] = true;
+                    }
+                }
+            }
+            KyokuResult::Ryukyoku { .. } => record.ryukyoku = true,
+            KyokuResult::Chombo { .. } => record.chombo = true,
+        }
+        for p in 0..4 {
+            let fon = seat(p);
+            record.riichi[p] = game.player_is_riichi(fon);
+            record.called[p] = game
+                .player_te_(fon)
+                .fuuro()
+                .iter()
+                .any(|fuuro| !matches!(fuuro, Fuuro::Kantsu(KantsuInner::Ankan { .. })));
+        }
+        record
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HanchanRecord {
+    pub seed: u64,
+    /// Final score of each player
+    pub scores: [isize; 4],
+    /// Placement of each player, from 1 to 4. Ties go to the player who
+    /// sat first (上家取り).
+    pub placements: [usize; 4],
+    /// Riichi sticks left on the table at the end of the hanchan (供託)
+    pub riichi_bou: usize,
+    pub kyoku: Vec<KyokuRecord>,
+}
+
+/// Play a hanchan with the wall shuffled from `seed`. `bots[0]` is the
+/// first oya.
+pub fn play_hanchan(seed: u64, bots: [AiServer; 4]) -> HanchanRecord {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut game = Game::new(&mut rng);
+    let mut kyoku = vec![];
+    game.play_hanchan_with(bots, &mut rng, |game, result| {
+        kyoku.push(KyokuRecord::new(game, result))
+    });
+
+    let (scores, placements) = final_results(&game);
+    HanchanRecord {
+        seed,
+        scores,
+        placements,
+        riichi_bou: game.riichi_bou_count(),
+        kyoku,
+    }
+}
+
+/// Final score and placement of each player of a hanchan which started at
+/// the first kyoku. Ties go to the player who sat first.
+pub fn final_results(game: &Game) -> ([isize; 4], [usize; 4]) {
+    let scores = [0, 1, 2, 3].map(|p| game.player_score(WINDS[(p + game.kyoku()) % 4]));
+    let mut order = [0, 1, 2, 3];
+    order.sort_by_key(|p| std::cmp::Reverse(scores[*p]));
+    let mut placements = [0; 4];
+    for (i, p) in order.iter().enumerate() {
+        placements[*p] = i + 1;
+    }
+    (scores, placements)
+}
+
+/// Play `games` hanchan on `threads` threads, with new bots from
+/// `make_bots` for each hanchan.
+///
+/// The seed of each hanchan only depends on `seed` and its index, so the
+/// records are the same whatever the number of threads.
+pub fn simulate<F>(games: usize, seed: u64, threads: usize, make_bots: F) -> Vec<HanchanRecord>
+where
+    F: Fn() -> [AiServer; 4] + Sync,
+{
+    play_games(&seeds(games, seed), threads, |_| make_bots())
+}
+
+/// Seeds of `count` walls, derived from `seed`
+pub fn seeds(count: usize, seed: u64) -> Vec<u64> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..count).map(|_| rng.gen()).collect()
+}
+
+/// Play a hanchan for each of `seeds` on `threads` threads. `make_bots`
+/// gets the index of the hanchan and returns its bots. Records are in the
+/// order of `seeds`.
+pub fn play_games<F>(seeds: &[u64], threads: usize, make_bots: F) -> Vec<HanchanRecord>
+where
+    F: Fn(usize) -> [AiServer; 4] + Sync,
+{
+    let games = seeds.len();
+    let next = AtomicUsize::new(0);
+    let records = Mutex::new((0..games).map(|_| None).collect::<Vec<_>>());
+    std::thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, games.max(1)) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= games {
+                    return;
+                }
+                let record = play_hanchan(seeds[i], make_bots(i));
+                records.lock().expect("Not poisoned")[i] = Some(record);
+            });
+        }
+    });
+    records
+        .into_inner()
+        .expect("Not poisoned")
+        .into_iter()
+        .map(|record| record.expect("Played"))
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PlayerStats {
+    pub name: String,
+    /// Number of 1st, 2nd, 3rd and 4th places
+    pub placements: [usize; 4],
+    pub average_placement: f64,
+    pub average_score: f64,
+    /// Rates per kyoku
+    pub agari_rate: f64,
+    pub deal_in_rate: f64,
+    pub riichi_rate: f64,
+    pub call_rate: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Stats {
+    pub games: usize,
+    pub kyoku: usize,
+    pub ryukyoku_rate: f64,
+    pub players: [PlayerStats; 4],
+}
+
+impl Stats {
+    pub fn new(names: [String; 4], records: &[HanchanRecord]) -> Self {
+        let games = records.len();
+        let all_kyoku: Vec<_> = records.iter().flat_map(|r| &r.kyoku).collect();
+        let kyoku = all_kyoku.len();
+        let rate = |count: usize, total: usize| {
+            if total == 0 {
+                0.0
+            } else {
+                count as f64 / total as f64
+            }
+        };
+        let kyoku_rate = |f: &dyn Fn(&KyokuRecord) -> bool| {
+            rate(all_kyoku.iter().filter(|k| f(k)).count(), kyoku)
+        };
+
+        let mut players: [PlayerStats; 4] = Default::default();
+        for (p, (stats, name)) in players.iter_mut().zip(names).enumerate() {
+            stats.name = name;
+            for record in records {
+                stats.placements[record.placements[p] - 1] += 1;
+            }
+            let placement_sum: usize = records.iter().map(|r| r.placements[p]).sum();
+            let score_sum: isize = records.iter().map(|r| r.scores[p]).sum();
+            stats.average_placement = rate(placement_sum, games);
+            stats.average_score = if games == 0 {
+                0.0
+            } else {
+                score_sum as f64 / games as f64
+            };
+            stats.agari_rate = kyoku_rate(&|k| k.agari[p]);
+            stats.deal_in_rate = kyoku_rate(&|k| k.deal_in[p]);
+            stats.riichi_rate = kyoku_rate(&|k| k.riichi[p]);
+            stats.call_rate = kyoku_rate(&|k| k.called[p]);
+        }
+
+        Stats {
+            games,
+            kyoku,
+            ryukyoku_rate: kyoku_rate(&|k| k.ryukyoku),
+            players,
+        }
+    }
+}
+
+impl Localize for Stats {
+    fn fmt_localized(&self, f: &mut fmt::Formatter<'_>, locale: Locale) -> fmt::Result {
+        writeln!(
+            f,
+            "{} {} / {} {} / {} {:.1}%",
+            locale.pick("半荘", "Hanchan", "Games"),
+            self.games,
+            locale.pick("局", "Kyoku", "Rounds"),
+            self.kyoku,
+            locale.pick("流局率", "Ryuukyoku-ritsu", "Draw rate"),
+            100.0 * self.ryukyoku_rate,
+        )?;
+        for stats in &self.players {
+            writeln!(f)?;
+            writeln!(f, "{}", stats.name)?;
+            let [first, second, third, fourth] = stats.placements;
+            writeln!(
+                f,
+                "  {}: {} / {} / {} / {} ({} {:.2})",
+                locale.pick("着順", "Chakujun", "Placements"),
+                first,
+                second,
+                third,
+                fourth,
+                locale.pick("平均", "heikin", "average"),
+                stats.average_placement,
+            )?;
+            writeln!(
+                f,
+                "  {}: {:.0}",
+                locale.pick("平均点", "Heikin-ten", "Average score"),
+                stats.average_score,
+            )?;
+            writeln!(
+                f,
+                "  {} {:.1}%  {} {:.1}%  {} {:.1}%  {} {:.1}%",
+                locale.pick("和了率", "Agari-ritsu", "Win"),
+                100.0 * stats.agari_rate,
+                locale.pick("放銃率", "Houjuu-ritsu", "Deal-in"),
+                100.0 * stats.deal_in_rate,
+                locale.pick("立直率", "Riichi-ritsu", "Riichi"),
+                100.0 * stats.riichi_rate,
+                locale.pick("副露率", "Fuuro-ritsu", "Call"),
+                100.0 * stats.call_rate,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::{dump_caller_bot, null_bot};
+
+    fn bots() -> [AiServer; 4] {
+        [dump_caller_bot(), null_bot(), dump_caller_bot(), null_bot()]
+    }
+
+    #[test]
+    fn test_simulate_is_deterministic() {
+        let records = simulate(2, 41, 2, bots);
+        assert_eq!(records, simulate(2, 41, 1, bots));
+        for record in &records {
+            let mut placements = record.placements;
+            placements.sort();
+            assert_eq!(placements, [1, 2, 3, 4]);
+            assert!(!record.kyoku.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_stats() {
+        let ron = KyokuRecord {
+            agari: [true, false, false, false],
+            deal_in: [false, false, true, false],
+            riichi: [true, false, false, false],
+            called: [false, true, true, false],
+            ..Default::default()
+        };
+        let ryukyoku = KyokuRecord {
+            ryukyoku: true,
+            ..Default::default()
+        };
+        let records = [
+            HanchanRecord {
+                seed: 0,
+                scores: [40000, 30000, 20000, 10000],
+                placements: [1, 2, 3, 4],
+                riichi_bou: 0,
+                kyoku: vec![ron.clone(), ryukyoku.clone()],
+            },
+            HanchanRecord {
+                seed: 1,
+                scores: [10000, 30000, 20000, 40000],
+                placements: [4, 2, 3, 1],
+                riichi_bou: 0,
+                kyoku: vec![ron, ryukyoku.clone(), ryukyoku],
+            },
+        ];
+        let names = ["a", "b", "c", "d"].map(String::from);
+        let stats = Stats::new(names, &records);
+        assert_eq!(stats.games, 2);
+        assert_eq!(stats.kyoku, 5);
+        assert_eq!(stats.ryukyoku_rate, 0.6);
+        assert_eq!(stats.players[0].placements, [1, 0, 0, 1]);
+        assert_eq!(stats.players[0].average_placement, 2.5);
+        assert_eq!(stats.players[0].average_score, 25000.0);
+        assert_eq!(stats.players[0].agari_rate, 0.4);
+        assert_eq!(stats.players[0].riichi_rate, 0.4);
+        assert_eq!(stats.players[2].deal_in_rate, 0.4);
+        assert_eq!(stats.players[1].call_rate, 0.4);
+        assert_eq!(stats.players[3].call_rate, 0.0);
+    }
+}
@@ -1,4 +1,7 @@
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::locale::Locale;
+use super::mpsz;
 use std::{
     fmt::{self, Debug},
     str::FromStr,
@@ -16,7 +19,7 @@ pub enum Values {
     Paa = 8,
     Kyuu = 9,
 }
-const VALUES: [Values; 9] = [
+pub(crate) const VALUES: [Values; 9] = [
     Values::Ii,
     Values::Ryan,
     Values::San,
@@ -57,8 +60,9 @@ impl PartialEq for SuuHai {
 }
 impl Eq for SuuHai {}
 
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Debug, Default, Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone)]
 pub enum Fon {
+    #[default]
     Ton = 0,
     Nan = 1,
     Shaa = 2,
@@ -103,6 +107,25 @@ impl Fon {
             Fon::Pee => "北",
         }
     }
+
+    /// Name of the wind in the given locale
+    pub fn name(self, locale: Locale) -> &'static str {
+        match locale {
+            Locale::Japanese => self.to_kanji(),
+            Locale::Romaji => match self {
+                Fon::Ton => "Ton",
+                Fon::Nan => "Nan",
+                Fon::Shaa => "Shaa",
+                Fon::Pee => "Pei",
+            },
+            Locale::English => match self {
+                Fon::Ton => "East",
+                Fon::Nan => "South",
+                Fon::Shaa => "West",
+                Fon::Pee => "North",
+            },
+        }
+    }
 }
 
 const FON: [Fon; 4] = [Fon::Ton, Fon::Nan, Fon::Shaa, Fon::Pee];
@@ -185,6 +208,11 @@ impl Hai {
         !self.is_suuhai()
     }
 
+    /// Is a red five (赤ドラ)
+    pub fn is_aka(self) -> bool {
+        matches!(self, Hai::Suu(SuuHai { aka: true, .. }))
+    }
+
     pub fn is_1_9(self) -> bool {
         matches!(
             self,
@@ -362,15 +390,208 @@ pub fn make_all_tiles() -> [Hai; 136] {
     hai
 }
 
+/// One tile of each of the 34 kinds, without red fives
+pub fn all_hai_kinds() -> [Hai; 34] {
+    let mut kinds = [Hai::Ji(JiHai::Sangen(Sangen::Hatsu)); 34];
+    kinds.copy_from_slice(&make_all_tiles()[..34]);
+    kinds
+}
+
+/// Kind of a tile (0 to 33), ignoring red fives, in the order of
+/// [`all_hai_kinds`]: man, pin and sou from 1 to 9, then the winds and the
+/// dragons.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone)]
+pub struct TileKind(u8);
+
+impl TileKind {
+    pub const COUNT: usize = 34;
+
+    pub fn new(index: usize) -> Option<Self> {
+        if index < Self::COUNT {
+            Some(TileKind(index as u8))
+        } else {
+            None
+        }
+    }
+
+    /// All 34 kinds, in order
+    pub fn all() -> impl Iterator<Item = TileKind> {
+        (0..Self::COUNT as u8).map(TileKind)
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn is_suuhai(self) -> bool {
+        self.0 < 27
+    }
+
+    pub fn is_jihai_or_1_9(self) -> bool {
+        !self.is_suuhai() || matches!(self.0 % 9, 0 | 8)
+    }
+
+    /// Value from 1 to 9 of a suuhai
+    pub fn value(self) -> Option<usize> {
+        if self.is_suuhai() {
+            Some(self.0 as usize % 9 + 1)
+        } else {
+            None
+        }
+    }
+
+    /// Kind `n` steps higher in the same suit, if any
+    pub fn offset(self, n: isize) -> Option<Self> {
+        let value = self.value()? as isize + n;
+        if (1..=9).contains(&value) {
+            Some(TileKind((self.0 as isize + n) as u8))
+        } else {
+            None
+        }
+    }
+
+    /// A tile of this kind (never a red five)
+    pub fn to_hai(self) -> Hai {
+        let i = self.index();
+        match i {
+            0..=26 => Hai::Suu(SuuHai {
+                suu: SUU[i / 9],
+                value: VALUES[i % 9],
+                aka: false,
+            }),
+            27..=30 => Hai::Ji(JiHai::Fon(FON[i - 27])),
+            _ => Hai::Ji(JiHai::Sangen(SANGEN[i - 31])),
+        }
+    }
+}
+
+impl From<Hai> for TileKind {
+    fn from(hai: Hai) -> Self {
+        TileKind(match hai {
+            Hai::Suu(SuuHai { suu, value, .. }) => 9 * suu as u8 + value as u8 - 1,
+            Hai::Ji(JiHai::Fon(fon)) => 27 + fon as u8,
+            Hai::Ji(JiHai::Sangen(sangen)) => 31 + sangen as u8,
+        })
+    }
+}
+
+/// Number of tiles of each kind in a set of tiles
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+pub struct TileCounts([u8; TileKind::COUNT]);
+
+impl Default for TileCounts {
+    fn default() -> Self {
+        TileCounts([0; TileKind::COUNT])
+    }
+}
+
+impl TileCounts {
+    pub fn from_hai(hai: &[Hai]) -> Self {
+        hai.iter().copied().collect()
+    }
+
+    pub fn as_array(&self) -> &[u8; TileKind::COUNT] {
+        &self.0
+    }
+
+    pub fn add(&mut self, kind: TileKind) {
+        self.0[kind.index()] += 1;
+    }
+
+    pub fn remove(&mut self, kind: TileKind) {
+        self.0[kind.index()] -= 1;
+    }
+
+    /// Total number of tiles
+    pub fn total(&self) -> usize {
+        self.0.iter().map(|c| *c as usize).sum()
+    }
+
+    /// Kinds with at least one tile, with their count
+    pub fn iter(&self) -> impl Iterator<Item = (TileKind, u8)> + '_ {
+        TileKind::all()
+            .zip(self.0.iter().copied())
+            .filter(|(_, count)| *count > 0)
+    }
+}
+
+impl std::ops::Index<TileKind> for TileCounts {
+    type Output = u8;
+
+    fn index(&self, kind: TileKind) -> &u8 {
+        &self.0[kind.index()]
+    }
+}
+
+impl std::ops::IndexMut<TileKind> for TileCounts {
+    fn index_mut(&mut self, kind: TileKind) -> &mut u8 {
+        &mut self.0[kind.index()]
+    }
+}
+
+impl FromIterator<Hai> for TileCounts {
+    fn from_iter<I: IntoIterator<Item = Hai>>(iter: I) -> Self {
+        let mut counts = TileCounts::default();
+        for hai in iter {
+            counts.add(hai.into());
+        }
+        counts
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ParseHaiError {
     EmptyString,
-    NoMahjongCharFound { string: String },
+    NoMahjongCharFound {
+        string: String,
+    },
+    /// Invalid MPSZ notation
+    InvalidNotation {
+        string: String,
+        reason: &'static str,
+    },
+}
+
+impl fmt::Display for ParseHaiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseHaiError::EmptyString => write!(f, "No tile found in empty string"),
+            ParseHaiError::NoMahjongCharFound { string } => {
+                write!(f, "No mahjong tile found in \"{}\"", string)
+            }
+            ParseHaiError::InvalidNotation { string, reason } => {
+                write!(f, "Invalid tiles \"{}\": {}", string, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseHaiError {}
+
+/// Parse a list of tiles written either with Unicode mahjong characters or
+/// in MPSZ notation (e.g. `123m456p`).
+pub fn hai_list_from_str(s: &str) -> Result<Vec<Hai>, ParseHaiError> {
+    if mpsz::is_mpsz(s) {
+        mpsz::parse_hai_list(s)
+    } else {
+        s.chars().map(|c| c.to_string().parse()).collect()
+    }
 }
 
 impl FromStr for Hai {
     type Err = ParseHaiError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.is_empty() && mpsz::is_mpsz(s) {
+            let hai = mpsz::parse_hai_list(s)?;
+            return if hai.len() == 1 {
+                Ok(hai[0])
+            } else {
+                Err(ParseHaiError::InvalidNotation {
+                    string: s.to_owned(),
+                    reason: "Expected a single tile",
+                })
+            };
+        }
         if let Some(c) = s.chars().next() {
             for hai in make_all_tiles().iter() {
                 if hai.to_char() == c {
@@ -418,3 +639,30 @@ impl<'de> Deserialize<'de> for Fon {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_kind() {
+        for (i, hai) in all_hai_kinds().iter().enumerate() {
+            let kind = TileKind::from(*hai);
+            assert_eq!(kind.index(), i);
+            assert_eq!(kind.to_hai(), *hai);
+            assert_eq!(kind.is_jihai_or_1_9(), hai.is_jihai_or_1_9());
+        }
+        let kind = |s: &str| TileKind::from(s.parse::<Hai>().unwrap());
+        assert_eq!(kind("0p"), kind("5p"));
+        assert_eq!(kind("8s").offset(1), Some(kind("9s")));
+        assert_eq!(kind("8s").offset(2), None);
+        assert_eq!(kind("2m").offset(-2), None);
+        assert_eq!(kind("1z").offset(1), None);
+
+        let counts = TileCounts::from_hai(&hai_list_from_str("1123m05p").unwrap());
+        assert_eq!(counts[kind("1m")], 2);
+        assert_eq!(counts[kind("5p")], 2);
+        assert_eq!(counts.total(), 6);
+        assert_eq!(counts.iter().count(), 4);
+    }
+}
@@ -0,0 +1,341 @@
+//! Duplicate tournament between four bots.
+//!
+//! Each wall is played once for every seating of the bots, so that all of
+//! them get the same tiles from every seat and the luck of the wall cancels
+//! out. Walls are derived from a seed, which reproduces the whole
+//! tournament.
+//!
+//! Walls follow each other in the order of the kyoku, whoever is oya: the
+//! deals only line up until a renchan (連荘) happens in some seatings and
+//! not in the others, after which the same walls go to a different oya.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use super::ai::AiServer;
+use super::locale::{write_table, Locale, Localize};
+use super::simulation::{self, HanchanRecord};
+
+/// Score of each player at the start of a hanchan (配給原点)
+const START_SCORE: isize = 25000;
+
+/// z-score of a 95% confidence interval
+const Z_95: f64 = 1.96;
+
+/// Every seating of the bots: `seating[player]` is the bot in that seat,
+/// player 0 being the first oya
+pub fn seatings() -> Vec<[usize; 4]> {
+    let mut seatings = vec![];
+    for a in 0..4 {
+        for b in (0..4).filter(|b| *b != a) {
+            for c in (0..4).filter(|c| *c != a && *c != b) {
+                let d = 6 - a - b - c;
+                seatings.push([a, b, c, d]);
+            }
+        }
+    }
+    seatings
+}
+
+/// A hanchan of the tournament. Scores and placements are by bot, not by
+/// seat.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TournamentGame {
+    /// Index of the wall
+    pub wall: usize,
+    pub seed: u64,
+    /// Bot in each seat, the first one being the first oya
+    pub seating: [usize; 4],
+    pub scores: [isize; 4],
+    pub placements: [usize; 4],
+    /// Riichi sticks left on the table at the end of the hanchan (供託)
+    pub riichi_bou: usize,
+}
+
+impl TournamentGame {
+    fn new(wall: usize, seating: [usize; 4], record: &HanchanRecord) -> Self {
+        let mut scores = [0; 4];
+        let mut placements = [0; 4];
+        for (player, bot) in seating.iter().enumerate() {
+            scores[*bot] = record.scores[player];
+            placements[*bot] = record.placements[player];
+        }
+        TournamentGame {
+            wall,
+            seed: record.seed,
+            seating,
+            scores,
+            placements,
+            riichi_bou: record.riichi_bou,
+        }
+    }
+}
+
+/// Play each of `walls` walls derived from `seed` with every seating of
+/// the bots, on `threads` threads. `make_bot` gets the index of a bot and
+/// returns a new instance of it.
+///
+/// Games are ordered by wall, then by seating as in [`seatings`].
+pub fn play_tournament<F>(
+    walls: usize,
+    seed: u64,
+    threads: usize,
+    make_bot: F,
+) -> Vec<TournamentGame>
+where
+    F: Fn(usize) -> AiServer + Sync,
+{
+    let seatings = seatings();
+    let wall_seeds = simulation::seeds(walls, seed);
+    let seeds: Vec<u64> = wall_seeds
+        .iter()
+        .flat_map(|seed| seatings.iter().map(move |_| *seed))
+        .collect();
+    let records = simulation::play_games(&seeds, threads, |i| {
+        seatings[i % seatings.len()].map(&make_bot)
+    });
+    records
+        .iter()
+        .enumerate()
+        .map(|(i, record)| {
+            TournamentGame::new(i / seatings.len(), seatings[i % seatings.len()], record)
+        })
+        .collect()
+}
+
+/// Mean of samples, with its 95% confidence interval
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Estimate {
+    pub mean: f64,
+    /// Half-width of the interval, if there are at least two samples
+    pub ci95: Option<f64>,
+}
+
+impl Estimate {
+    pub fn new(samples: &[f64]) -> Self {
+        let n = samples.len() as f64;
+        if samples.is_empty() {
+            return Estimate::default();
+        }
+        let mean = samples.iter().sum::<f64>() / n;
+        let ci95 = if samples.len() < 2 {
+            None
+        } else {
+            let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+            Some(Z_95 * (variance / n).sqrt())
+        };
+        Estimate { mean, ci95 }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BotStandings {
+    pub name: String,
+    pub games: usize,
+    /// Number of 1st, 2nd, 3rd and 4th places
+    pub placements: [usize; 4],
+    pub average_placement: Estimate,
+    /// Final score minus the starting score
+    pub point_differential: Estimate,
+}
+
+/// Results of a tournament, best average placement first.
+///
+/// Games on the same wall are not independent, so confidence intervals are
+/// computed on the average of each bot on each wall.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Standings {
+    pub seed: u64,
+    pub walls: usize,
+    pub games: usize,
+    pub bots: Vec<BotStandings>,
+}
+
+impl Standings {
+    pub fn new(names: [String; 4], seed: u64, games: &[TournamentGame]) -> Self {
+        let walls = games.iter().map(|game| game.wall + 1).max().unwrap_or(0);
+        let mut bots: Vec<BotStandings> = names
+            .into_iter()
+            .enumerate()
+            .map(|(bot, name)| {
+                let mut standings = BotStandings {
+                    name,
+                    games: games.len(),
+                    ..Default::default()
+                };
+                let mut placement_sums = vec![(0, 0); walls];
+                let mut differential_sums = vec![0; walls];
+                for game in games {
+                    standings.placements[game.placements[bot] - 1] += 1;
+                    placement_sums[game.wall].0 += game.placements[bot];
+                    placement_sums[game.wall].1 += 1;
+                    differential_sums[game.wall] += game.scores[bot] - START_SCORE;
+                }
+                let per_wall = |sum: f64, count: usize| sum / count.max(1) as f64;
+                let placements: Vec<f64> = placement_sums
+                    .iter()
+                    .map(|(sum, count)| per_wall(*sum as f64, *count))
+                    .collect();
+                let differentials: Vec<f64> = differential_sums
+                    .iter()
+                    .zip(&placement_sums)
+                    .map(|(sum, (_, count))| per_wall(*sum as f64, *count))
+                    .collect();
+                standings.average_placement = Estimate::new(&placements);
+                standings.point_differential = Estimate::new(&differentials);
+                standings
+            })
+            .collect();
+        bots.sort_by(|a, b| {
+            a.average_placement
+                .mean
+                .total_cmp(&b.average_placement.mean)
+        });
+        Standings {
+            seed,
+            walls,
+            games: games.len(),
+            bots,
+        }
+    }
+}
+
+impl Localize for Standings {
+    fn fmt_localized(&self, f: &mut fmt::Formatter<'_>, locale: Locale) -> fmt::Result {
+        writeln!(
+            f,
+            "{} {} / {} {} / {} {}",
+            locale.pick("牌山", "Haiyama", "Walls"),
+            self.walls,
+            locale.pick("半荘", "Hanchan", "Games"),
+            self.games,
+            locale.pick("シード", "Shiido", "Seed"),
+            self.seed,
+        )?;
+        writeln!(f)?;
+        let ci = |estimate: &Estimate, precision: usize| match estimate.ci95 {
+            Some(ci) => format!("± {:.*}", precision, ci),
+            None => "± ?".to_owned(),
+        };
+        let header = [
+            locale.pick("打ち手", "Uchite", "Bot"),
+            locale.pick("1着", "1-chaku", "1st"),
+            locale.pick("2着", "2-chaku", "2nd"),
+            locale.pick("3着", "3-chaku", "3rd"),
+            locale.pick("4着", "4-chaku", "4th"),
+            locale.pick("平均着順", "Heikin-chakujun", "Average rank"),
+            locale.pick("平均収支", "Heikin-shuushi", "Point diff"),
+        ]
+        .map(String::from);
+        let mut rows = vec![header.to_vec()];
+        for bot in &self.bots {
+            let [first, second, third, fourth] = bot.placements;
+            rows.push(vec![
+                bot.name.clone(),
+                first.to_string(),
+                second.to_string(),
+                third.to_string(),
+                fourth.to_string(),
+                format!(
+                    "{:.2} {}",
+                    bot.average_placement.mean,
+                    ci(&bot.average_placement, 2)
+                ),
+                format!(
+                    "{:+.0} {}",
+                    bot.point_differential.mean,
+                    ci(&bot.point_differential, 0)
+                ),
+            ]);
+        }
+        write_table(f, &rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::{dump_caller_bot, null_bot};
+
+    #[test]
+    fn test_seatings() {
+        let seatings = seatings();
+        assert_eq!(seatings.len(), 24);
+        for (i, seating) in seatings.iter().enumerate() {
+            let mut bots = *seating;
+            bots.sort();
+            assert_eq!(bots, [0, 1, 2, 3]);
+            assert!(!seatings[..i].contains(seating));
+        }
+        for bot in 0..4 {
+            for seat in 0..4 {
+                let count = seatings.iter().filter(|s| s[seat] == bot).count();
+                assert_eq!(count, 6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_estimate() {
+        let estimate = Estimate::new(&[1.0, 2.0, 3.0]);
+        assert_eq!(estimate.mean, 2.0);
+        assert_eq!(estimate.ci95, Some(Z_95 / 3f64.sqrt()));
+        assert_eq!(Estimate::new(&[4.0]).ci95, None);
+        assert_eq!(Estimate::new(&[]), Estimate::default());
+    }
+
+    #[test]
+    fn test_identical_bots_cancel_out() {
+        let games = play_tournament(1, 7, 4, |_| null_bot());
+        assert_eq!(games.len(), 24);
+        assert!(games.iter().all(|game| game.seed == games[0].seed));
+        let standings = Standings::new(["a", "b", "c", "d"].map(String::from), 7, &games);
+        assert_eq!(standings.walls, 1);
+        for bot in &standings.bots {
+            assert_eq!(bot.games, 24);
+            assert_eq!(bot.placements, [6, 6, 6, 6]);
+            assert_eq!(bot.average_placement.mean, 2.5);
+            assert_eq!(bot.point_differential.mean, 0.0);
+            assert_eq!(bot.point_differential.ci95, None);
+        }
+    }
+
+    #[test]
+    fn test_tournament_is_deterministic() {
+        let make_bot = |bot: usize| {
+            if [0, 2].contains(&bot) {
+                dump_caller_bot()
+            } else {
+                null_bot()
+            }
+        };
+        let games = play_tournament(2, 3, 3, make_bot);
+        assert_eq!(games, play_tournament(2, 3, 1, make_bot));
+        assert_eq!(games.len(), 48);
+        assert_ne!(games[0].seed, games[24].seed);
+        for game in &games {
+            assert_eq!(game.wall, usize::from(game.seed != games[0].seed));
+            let mut placements = game.placements;
+            placements.sort();
+            assert_eq!(placements, [1, 2, 3, 4]);
+            let riichi_bou = game.riichi_bou as isize * 1000;
+            assert_eq!(
+                game.scores.iter().sum::<isize>() + riichi_bou,
+                4 * START_SCORE
+            );
+        }
+        let standings = Standings::new(["a", "b", "c", "d"].map(String::from), 3, &games);
+        assert_eq!(standings.walls, 2);
+        let differential: f64 = standings
+            .bots
+            .iter()
+            .map(|bot| bot.point_differential.mean)
+            .sum();
+        assert!(differential.abs() < 1e-6);
+        assert!(standings
+            .bots
+            .iter()
+            .all(|bot| bot.average_placement.ci95.is_some()));
+    }
+}
@@ -0,0 +1,86 @@
+//! `mahjong tournament`: duplicate tournament between four bots.
+
+use std::path::Path;
+
+use clap::Args;
+
+use mahjong::locale::{Locale, Localize};
+use mahjong::tournament::{self, Standings, TournamentGame};
+use serde::Serialize;
+
+use super::ratings_cmd::RatingsFile;
+use super::{make_ai_server, parse_player, threads, Player};
+
+#[derive(Args, Debug)]
+pub struct TournamentArgs {
+    /// Number of walls. Each wall is played with the 24 seatings of the bots,
+    /// with the same deals until a renchan happens in some seatings only.
+    #[arg(long, default_value_t = 10)]
+    walls: usize,
+    /// Seed of the walls. The same seed plays the same tournament.
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Number of threads (number of CPUs by default, 1 with an mjai-tcp
+    /// bot)
+    #[arg(long)]
+    threads: Option<usize>,
+    /// The four bots: bot specs (see --list-bots)
+    #[arg(value_name = "BOT", value_parser = parse_player, num_args = 4, required = true)]
+    bots: Vec<Player>,
+    /// Print the standings and every game as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    standings: &'a Standings,
+    games: &'a [TournamentGame],
+}
+
+pub fn run(args: TournamentArgs, ratings: Option<&Path>, locale: Locale) -> Result<(), String> {
+    let bots = args.bots;
+    if bots.len() != 4 {
+        return Err(format!("Expected 4 bots, got {}", bots.len()));
+    }
+    if bots.iter().any(|bot| matches!(bot, Player::CursiveHuman)) {
+        return Err("cursive-human cannot play in a tournament".to_owned());
+    }
+    let ratings = RatingsFile::open(ratings)?;
+    let threads = threads(args.threads, &bots)?;
+
+    let games = tournament::play_tournament(args.walls, args.seed, threads, |bot| {
+        make_ai_server(&bots[bot], locale)
+    });
+    if let Some(ratings) = ratings {
+        let specs = [0, 1, 2, 3].map(|i| bots[i].to_string());
+        let specs = [0, 1, 2, 3].map(|i| specs[i].as_str());
+        ratings.record(
+            games
+                .iter()
+                .map(|game| (specs, game.scores, game.placements)),
+        )?;
+    }
+    // The same spec may play several times
+    let names = [0, 1, 2, 3].map(|i| {
+        let name = bots[i].to_string();
+        if bots.iter().filter(|bot| bot.to_string() == name).count() > 1 {
+            format!("{} #{}", name, i + 1)
+        } else {
+            name
+        }
+    });
+    let standings = Standings::new(names, args.seed, &games);
+
+    if args.json {
+        let report = Report {
+            standings: &standings,
+            games: &games,
+        };
+        let json = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+        println!("{}", json);
+    } else {
+        print!("{}", standings.localized(locale));
+    }
+    Ok(())
+}
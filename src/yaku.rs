@@ -1,8 +1,301 @@
+use std::fmt;
+
 use log::{debug, trace};
+use serde::Serialize;
 
 use super::game::{Fuuro, Game, KantsuInner, Te};
-use super::tiles::{Fon, Hai, JiHai, SuuHai};
+use super::locale::{Locale, Localize};
+use super::points;
+use super::rules::Rules;
+use super::tiles::{Fon, Hai, JiHai, SuuHai, TileCounts, TileKind};
+
+/// Situation in which a hand is won, besides the tiles themselves.
+///
+/// This is all that is needed to score a hand, so that a hand can be
+/// scored without a [`Game`] (e.g. from a log or a quiz).
+#[derive(Debug, Clone, Default)]
+pub struct WinContext {
+    /// Round wind (場風)
+    pub bakaze: Fon,
+    /// Seat wind (自風)
+    pub jikaze: Fon,
+    /// Dora indicators (ドラ表示牌)
+    pub dora_indicators: Vec<Hai>,
+    /// Ura dora indicators (裏ドラ表示牌). Only counted in riichi.
+    pub uradora_indicators: Vec<Hai>,
+    /// Winner is in riichi (立直). Should also be set on double riichi.
+    pub riichi: bool,
+    /// Riichi was declared on the first uninterrupted turn (ダブル立直)
+    pub double_riichi: bool,
+    /// Win within one go-around after riichi (一発)
+    pub ippatsu: bool,
+    /// Tsumo on the last tile of the wall (海底摸月)
+    pub haitei: bool,
+    /// Ron on the last discard (河底撈魚)
+    pub houtei: bool,
+    /// Tsumo on kan supplementary tile (嶺上開花)
+    pub rinshan: bool,
+    /// Ron on kakan (搶槓)
+    pub chankan: bool,
+    /// Oya wins on the first uninterrupted draw (天和)
+    pub tenhou: bool,
+    /// Ko wins on the first uninterrupted draw (地和)
+    pub chihou: bool,
+    /// Ko wins by ron before their first uninterrupted draw (人和)
+    pub renhou: bool,
+    /// Player who dealt in on ron (放銃者). If unknown, the breakdown
+    /// only contains the payment received by the winner.
+    pub discarder: Option<Fon>,
+    /// Number of honba (本場)
+    pub honba: usize,
+    /// Number of riichi sticks on the table (供託), going to the winner
+    pub riichi_bou: usize,
+}
+
+impl WinContext {
+    pub fn dora(&self) -> Vec<Hai> {
+        self.dora_indicators
+            .iter()
+            .copied()
+            .map(Hai::next)
+            .collect()
+    }
+    pub fn uradora(&self) -> Vec<Hai> {
+        self.uradora_indicators
+            .iter()
+            .copied()
+            .map(Hai::next)
+            .collect()
+    }
+}
+
+/// Itemized result of scoring a winning hand.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScoreBreakdown {
+    /// Each yaku with its han (adjusted for open hands)
+    pub yaku: Vec<(Yaku, YakuValue)>,
+    /// Number of dora (ドラ)
+    pub dora: usize,
+    /// Number of ura dora (裏ドラ)
+    pub uradora: usize,
+    /// Number of red fives (赤ドラ)
+    pub aka: usize,
+    /// Value of the hand, dora included
+    pub han: YakuValue,
+    pub fu: Fu,
+    /// Decomposition of the hand used for scoring
+    pub combination: Option<WinningCombination>,
+    /// Wait completed by the winning tile, as counted for fu
+    pub machi: Option<Machi>,
+    /// Limit reached by the hand (満貫 and above)
+    pub limit: Option<Limit>,
+    /// Points received (positive) or paid (negative) by each player,
+    /// indexed by seat wind. Includes honba and riichi sticks.
+    pub payments: [isize; 4],
+}
+
+impl ScoreBreakdown {
+    /// Breakdown of a nagashi mangan (流し満貫).
+    pub fn nagashimangan(context: &WinContext, rules: &Rules) -> Self {
+        let yaku = Yaku::Nagashimangan;
+        let han = yaku.han(true);
+        let fu = Fu::default();
+        Self {
+            yaku: vec![(yaku, han)],
+            dora: 0,
+            uradora: 0,
+            aka: 0,
+            han,
+            limit: points::limit(han, fu.total(), rules),
+            payments: points::payments(
+                han,
+                fu.total(),
+                WinningMethod::Nagashimangan,
+                context,
+                rules,
+            ),
+            fu,
+            combination: None,
+            machi: None,
+        }
+    }
+
+    /// List of yaku without their han
+    pub fn yaku(&self) -> Vec<Yaku> {
+        self.yaku.iter().map(|(yaku, _)| *yaku).collect()
+    }
+
+    /// Can the hand win (has at least one yaku)
+    pub fn is_agari(&self) -> bool {
+        !self.yaku.is_empty()
+    }
+}
+
+impl fmt::Display for ScoreBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_localized(f, Locale::Japanese)
+    }
+}
+
+impl Localize for ScoreBreakdown {
+    fn fmt_localized(&self, f: &mut fmt::Formatter<'_>, locale: Locale) -> fmt::Result {
+        for (yaku, han) in &self.yaku {
+            writeln!(f, "{} {}", yaku.name(locale), han.localized(locale))?;
+        }
+        for (name, count) in [
+            (locale.pick("ドラ", "Dora", "Dora"), self.dora),
+            (locale.pick("裏ドラ", "Uradora", "Ura dora"), self.uradora),
+            (locale.pick("赤ドラ", "Akadora", "Red fives"), self.aka),
+        ] {
+            if count > 0 {
+                writeln!(f, "{} {}", name, count)?;
+            }
+        }
+        match locale {
+            Locale::Japanese => write!(f, "{}符", self.fu.total())?,
+            Locale::Romaji | Locale::English => write!(f, "{} fu", self.fu.total())?,
+        }
+        write!(f, " {}", self.han.localized(locale))?;
+        if let Some(limit) = self.limit {
+            write!(f, " {}", limit.name(locale))?;
+        }
+        writeln!(f)?;
+        writeln!(f, "  {}", self.fu.localized(locale))?;
+        let mut fon = Fon::Ton;
+        let mut payments = Vec::with_capacity(4);
+        for payment in self.payments {
+            payments.push(format!("{} {:+}", fon.to_char(), payment));
+            fon = fon.next();
+        }
+        write!(f, "{}", payments.join(" "))
+    }
+}
+
+/// Itemized fu (符)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Fu {
+    /// 副底 (20, or 25 for chiitoitsu)
+    pub base: usize,
+    /// 門前加符 (ron with a closed hand)
+    pub menzen_ron: usize,
+    /// ツモ符
+    pub tsumo: usize,
+    /// Fu of each mentsu, melds included
+    pub mentsu: Vec<usize>,
+    /// 雀頭符 (pair)
+    pub atama: usize,
+    /// 待ち符 (wait)
+    pub machi: usize,
+    /// Rounding up to the next 10 (up to 30 for an open hand without fu)
+    pub rounding: usize,
+}
+
+impl fmt::Display for Fu {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_localized(f, Locale::Japanese)
+    }
+}
+
+impl Localize for Fu {
+    fn fmt_localized(&self, f: &mut fmt::Formatter<'_>, locale: Locale) -> fmt::Result {
+        let mentsu = locale.pick("面子", "Mentsu", "Set");
+        let mut items = vec![(locale.pick("副底", "Fuutei", "Base"), self.base)];
+        items.push((
+            locale.pick("門前加符", "Menzen kafu", "Closed ron"),
+            self.menzen_ron,
+        ));
+        items.push((locale.pick("ツモ符", "Tsumo fu", "Tsumo"), self.tsumo));
+        items.extend(self.mentsu.iter().map(|fu| (mentsu, *fu)));
+        items.push((locale.pick("雀頭", "Jantou", "Pair"), self.atama));
+        items.push((locale.pick("待ち", "Machi", "Wait"), self.machi));
+        items.push((
+            locale.pick("切り上げ", "Kiriage", "Rounding"),
+            self.rounding,
+        ));
+        let items: Vec<_> = items
+            .into_iter()
+            .filter(|(_, fu)| *fu > 0)
+            .map(|(name, fu)| format!("{} {}", name, fu))
+            .collect();
+        write!(f, "{}", items.join(", "))
+    }
+}
 
+impl Fu {
+    pub fn total(&self) -> usize {
+        self.base
+            + self.menzen_ron
+            + self.tsumo
+            + self.mentsu.iter().sum::<usize>()
+            + self.atama
+            + self.machi
+            + self.rounding
+    }
+}
+
+/// Limit hands (満貫 and above)
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize)]
+pub enum Limit {
+    Mangan,
+    Haneman,
+    Baiman,
+    Sanbaiman,
+    /// Yakuman reached by counting han (数え役満)
+    KazoeYakuman,
+    Yakuman(usize),
+}
+
+impl Limit {
+    pub fn name(self, locale: Locale) -> &'static str {
+        match self {
+            Limit::Mangan => locale.pick("満貫", "Mangan", "Mangan"),
+            Limit::Haneman => locale.pick("跳満", "Haneman", "Haneman"),
+            Limit::Baiman => locale.pick("倍満", "Baiman", "Baiman"),
+            Limit::Sanbaiman => locale.pick("三倍満", "Sanbaiman", "Sanbaiman"),
+            Limit::KazoeYakuman => locale.pick("数え役満", "Kazoe yakuman", "Counted yakuman"),
+            Limit::Yakuman(1) => locale.pick("役満", "Yakuman", "Yakuman"),
+            Limit::Yakuman(2) => locale.pick("ダブル役満", "Daburu yakuman", "Double yakuman"),
+            Limit::Yakuman(3) => locale.pick("トリプル役満", "Toripuru yakuman", "Triple yakuman"),
+            Limit::Yakuman(_) => locale.pick("複合役満", "Fukugou yakuman", "Multiple yakuman"),
+        }
+    }
+}
+
+/// Score a winning hand.
+///
+/// `hand` are the hidden tiles without the winning tile.
+/// If the hand cannot win, the returned breakdown has no yaku.
+pub fn score(
+    hand: &[Hai],
+    fuuro: &[Fuuro],
+    winning_tile: Hai,
+    method: WinningMethod,
+    context: &WinContext,
+    rules: &Rules,
+) -> ScoreBreakdown {
+    Agari {
+        hai: hand,
+        fuuro,
+        agarihai: winning_tile,
+        method,
+        context,
+        rules,
+    }
+    .score()
+}
+
+/// Winning hand with its context, which is being scored.
+#[derive(Debug, Copy, Clone)]
+struct Agari<'t> {
+    hai: &'t [Hai],
+    fuuro: &'t [Fuuro],
+    agarihai: Hai,
+    method: WinningMethod,
+    context: &'t WinContext,
+    rules: &'t Rules,
+}
+
+/// Adapter to score the te of a player in a [`Game`].
 #[derive(Debug, Copy, Clone)]
 pub struct AgariTe<'t, 'g> {
     game: &'g Game,
@@ -17,14 +310,14 @@ pub struct AgariTe<'t, 'g> {
     rinshankaihou: bool,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Serialize)]
 pub enum WinningMethod {
     Ron,
     Tsumo,
     Nagashimangan,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Serialize)]
 pub enum Yaku {
     Menzentsumo,
     Riichi,
@@ -134,66 +427,95 @@ impl Yaku {
         matches!(self.han(true), Yakuman(_))
     }
 
-    pub fn name(self) -> &'static str {
+    pub fn name(self, locale: Locale) -> &'static str {
         match self {
-            Menzentsumo => "門前自摸",
-            Riichi => "立直",
-            Ippatsu => "一発",
-            Tanyao => "断么九",
-            Pinfu => "平和",
-            Iipeikou => "一盃口",
-            Haku => "白",
-            Hatsu => "発",
-            Chun => "中",
-            BaNoKaze => "場の風",
-            JibunNoKaze => "自分の風",
-            Chankan => "搶槓",
-            RinshanKaihou => "嶺上開花",
-            Haiteiraoyue => "海底摸月",
-            Houteiraoyui => "河底撈魚 ",
-            Daburii => "ダブル立直",
-            Chiitoitsu => "七対子",
-            Toitoi => "対々和",
-            SanAnkou => "三暗刻",
-            SanshokuDoukou => "三色同刻",
-            SanshokuDoujun => "三色同順",
-            Honroutou => "混老頭",
-            Ittsuu => "一気通貫",
-            Chanta => "混全帯么九",
-            Shousangen => "小三元",
-            Sankantsu => "三槓子",
-            HonItsu => "混一色",
-            Junchan => "純全帯么九",
-            Ryanpeikou => "二盃口",
-            Nagashimangan => "流し満貫",
-            ChinItsu => "清一色",
-            Tenhou => "天和",
-            Chihou => "地和",
-            Renhou => "人和",
-            Ryuuiisou => "緑一色",
-            Daisangen => "大三元",
-            Shousuushii => "小四喜",
-            Tsuuiisou => "字一色",
-            Kokushimusou => "国士無双",
-            Chuurenpoutou => "九蓮宝燈",
-            Suuankou => "四暗刻",
-            Chinroutou => "清老頭",
-            Suukantsu => "四槓子",
-            SuuankouTanki => "四暗刻単騎",
-            Daisuushii => "大四喜",
-            JunseiChuurenpoutou => "純正九蓮宝燈",
-            KokushimusouJuusanmen => "国士無双十三面",
+            Menzentsumo => locale.pick("門前自摸", "Menzen tsumo", "Fully concealed hand"),
+            Riichi => locale.pick("立直", "Riichi", "Riichi"),
+            Ippatsu => locale.pick("一発", "Ippatsu", "One shot"),
+            Tanyao => locale.pick("断么九", "Tanyao", "All simples"),
+            Pinfu => locale.pick("平和", "Pinfu", "Pinfu"),
+            Iipeikou => locale.pick("一盃口", "Iipeikou", "Pure double sequence"),
+            Haku => locale.pick("白", "Haku", "White dragon"),
+            Hatsu => locale.pick("発", "Hatsu", "Green dragon"),
+            Chun => locale.pick("中", "Chun", "Red dragon"),
+            BaNoKaze => locale.pick("場の風", "Bakaze", "Round wind"),
+            JibunNoKaze => locale.pick("自分の風", "Jikaze", "Seat wind"),
+            Chankan => locale.pick("搶槓", "Chankan", "Robbing a kan"),
+            RinshanKaihou => locale.pick("嶺上開花", "Rinshan kaihou", "After a kan"),
+            Haiteiraoyue => locale.pick("海底摸月", "Haitei raoyue", "Under the sea"),
+            Houteiraoyui => locale.pick("河底撈魚", "Houtei raoyui", "Under the river"),
+            Daburii => locale.pick("ダブル立直", "Daburu riichi", "Double riichi"),
+            Chiitoitsu => locale.pick("七対子", "Chiitoitsu", "Seven pairs"),
+            Toitoi => locale.pick("対々和", "Toitoihou", "All triplets"),
+            SanAnkou => locale.pick("三暗刻", "Sanankou", "Three concealed triplets"),
+            SanshokuDoukou => locale.pick("三色同刻", "Sanshoku doukou", "Triple triplets"),
+            SanshokuDoujun => locale.pick("三色同順", "Sanshoku doujun", "Mixed triple sequence"),
+            Honroutou => locale.pick("混老頭", "Honroutou", "All terminals and honors"),
+            Ittsuu => locale.pick("一気通貫", "Ikkitsuukan", "Pure straight"),
+            Chanta => locale.pick("混全帯么九", "Honchantaiyaochuu", "Half outside hand"),
+            Shousangen => locale.pick("小三元", "Shousangen", "Little three dragons"),
+            Sankantsu => locale.pick("三槓子", "Sankantsu", "Three quads"),
+            HonItsu => locale.pick("混一色", "Honiisou", "Half flush"),
+            Junchan => locale.pick("純全帯么九", "Junchantaiyaochuu", "Fully outside hand"),
+            Ryanpeikou => locale.pick("二盃口", "Ryanpeikou", "Twice pure double sequence"),
+            Nagashimangan => locale.pick("流し満貫", "Nagashi mangan", "Mangan at draw"),
+            ChinItsu => locale.pick("清一色", "Chiniisou", "Full flush"),
+            Tenhou => locale.pick("天和", "Tenhou", "Blessing of heaven"),
+            Chihou => locale.pick("地和", "Chiihou", "Blessing of earth"),
+            Renhou => locale.pick("人和", "Renhou", "Blessing of man"),
+            Ryuuiisou => locale.pick("緑一色", "Ryuuiisou", "All green"),
+            Daisangen => locale.pick("大三元", "Daisangen", "Big three dragons"),
+            Shousuushii => locale.pick("小四喜", "Shousuushii", "Little four winds"),
+            Tsuuiisou => locale.pick("字一色", "Tsuuiisou", "All honors"),
+            Kokushimusou => locale.pick("国士無双", "Kokushi musou", "Thirteen orphans"),
+            Chuurenpoutou => locale.pick("九蓮宝燈", "Chuuren poutou", "Nine gates"),
+            Suuankou => locale.pick("四暗刻", "Suuankou", "Four concealed triplets"),
+            Chinroutou => locale.pick("清老頭", "Chinroutou", "All terminals"),
+            Suukantsu => locale.pick("四槓子", "Suukantsu", "Four quads"),
+            SuuankouTanki => locale.pick(
+                "四暗刻単騎",
+                "Suuankou tanki",
+                "Four concealed triplets single wait",
+            ),
+            Daisuushii => locale.pick("大四喜", "Daisuushii", "Big four winds"),
+            JunseiChuurenpoutou => {
+                locale.pick("純正九蓮宝燈", "Junsei chuuren poutou", "True nine gates")
+            }
+            KokushimusouJuusanmen => locale.pick(
+                "国士無双十三面",
+                "Kokushi musou juusanmen",
+                "Thirteen-wait thirteen orphans",
+            ),
         }
     }
 }
 
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone)]
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone, Serialize)]
 pub enum YakuValue {
     Han(usize),
     Yakuman(usize),
 }
 use YakuValue::*;
 
+impl fmt::Display for YakuValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_localized(f, Locale::Japanese)
+    }
+}
+
+impl Localize for YakuValue {
+    fn fmt_localized(&self, f: &mut fmt::Formatter<'_>, locale: Locale) -> fmt::Result {
+        match (self, locale) {
+            (Han(han), Locale::Japanese) => write!(f, "{}翻", han),
+            (Han(han), _) => write!(f, "{} han", han),
+            (Yakuman(1), Locale::Japanese) => write!(f, "役満"),
+            (Yakuman(1), _) => write!(f, "Yakuman"),
+            (Yakuman(yakuman), Locale::Japanese) => write!(f, "{}倍役満", yakuman),
+            (Yakuman(yakuman), _) => write!(f, "{}x yakuman", yakuman),
+        }
+    }
+}
+
 impl std::ops::Add for YakuValue {
     type Output = Self;
 
@@ -208,7 +530,7 @@ impl std::ops::Add for YakuValue {
     }
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Serialize)]
 pub enum WinningCombination {
     Chiitoitsu([[Hai; 2]; 7]),
     Kokushimusou([Hai; 14]),
@@ -287,6 +609,56 @@ impl<'t, 'g> AgariTe<'t, 'g> {
         self
     }
 
+    /// Read the context of the win from the game.
+    pub fn context(&self) -> WinContext {
+        let riichi = self.game.player_riichi(self.wind);
+        let last_tile = self.game.next_tsumohai_index().is_none();
+        let first_turn = self.game.first_uninterrupted_turn();
+        let tsumo = self.method == WinningMethod::Tsumo;
+        let ron = self.method == WinningMethod::Ron;
+        WinContext {
+            bakaze: self.game.wind,
+            jikaze: self.wind,
+            dora_indicators: self.game.dora_indicator(),
+            uradora_indicators: self.game.uradora_indicator(),
+            riichi: riichi.is_some(),
+            double_riichi: riichi.map(|riichi| riichi.double).unwrap_or(false),
+            ippatsu: riichi.map(|riichi| riichi.ippatsu).unwrap_or(false),
+            haitei: tsumo && last_tile,
+            houtei: ron && last_tile,
+            rinshan: self.rinshankaihou,
+            chankan: self.chankan,
+            tenhou: tsumo && first_turn && self.wind == Fon::Ton,
+            chihou: tsumo && first_turn && self.wind != Fon::Ton,
+            renhou: ron && first_turn && self.game.tsumo_cnt() <= self.wind as usize,
+            discarder: if ron {
+                Some(self.game.turn.prev())
+            } else {
+                None
+            },
+            honba: self.game.honba(),
+            riichi_bou: self.game.riichi_bou_count(),
+        }
+    }
+
+    pub fn score(&self) -> ScoreBreakdown {
+        self.score_with_context(&self.context())
+    }
+
+    /// Score with a context amended by the caller.
+    pub fn score_with_context(&self, context: &WinContext) -> ScoreBreakdown {
+        score(
+            self.hai,
+            self.fuuro,
+            self.agarihai,
+            self.method,
+            context,
+            self.game.rules(),
+        )
+    }
+}
+
+impl<'t> Agari<'t> {
     /// Iterate over hidden tiles.
     fn hai(&self) -> impl Iterator<Item = Hai> + '_ {
         AgariTeHaiIter {
@@ -297,40 +669,10 @@ impl<'t, 'g> AgariTe<'t, 'g> {
 
     /// Iterate over all tiles, including called ones.
     fn hai_all(&self) -> impl Iterator<Item = Hai> + '_ {
-        let mut open_hai = Vec::with_capacity(4 * self.fuuro.len());
-        for fuuro in self.fuuro {
-            match fuuro {
-                Fuuro::Shuntsu { own, taken, .. } | Fuuro::Kootsu { own, taken, .. } => {
-                    open_hai.push(own[0]);
-                    open_hai.push(own[1]);
-                    open_hai.push(*taken);
-                }
-                Fuuro::Kantsu(KantsuInner::Ankan { own }) => {
-                    open_hai.push(own[0]);
-                    open_hai.push(own[1]);
-                    open_hai.push(own[2]);
-                    open_hai.push(own[3]);
-                }
-                Fuuro::Kantsu(KantsuInner::DaiMinkan { own, taken, .. }) => {
-                    open_hai.push(own[0]);
-                    open_hai.push(own[1]);
-                    open_hai.push(own[2]);
-                    open_hai.push(*taken);
-                }
-                Fuuro::Kantsu(KantsuInner::ShouMinkan {
-                    own, taken, added, ..
-                }) => {
-                    open_hai.push(own[0]);
-                    open_hai.push(own[1]);
-                    open_hai.push(*added);
-                    open_hai.push(*taken);
-                }
-            }
-        }
-        self.hai().chain(open_hai)
+        self.hai().chain(self.fuuro.iter().flat_map(Fuuro::hai))
     }
 
-    fn combinations(&self) -> Vec<AgariTeCombination<'_, 't, 'g>> {
+    fn combinations(&self) -> Vec<AgariTeCombination<'_, 't>> {
         let te: Vec<_> = self.hai().collect();
         let max = match self.fuuro.len() {
             0 => 4,
@@ -343,34 +685,86 @@ impl<'t, 'g> AgariTe<'t, 'g> {
         let mut out = vec![];
         for combination in winning_combinations(&te, max) {
             out.push(AgariTeCombination {
-                agari_te: self,
+                agari: self,
                 combination,
             });
         }
         out
     }
 
-    fn best_combination(&self) -> Option<AgariTeCombination<'_, 't, 'g>> {
+    fn best_combination(&self) -> Option<AgariTeCombination<'_, 't>> {
         self.combinations()
             .into_iter()
             .max_by_key(AgariTeCombination::points)
     }
 
-    pub fn points(&self) -> (Vec<Yaku>, YakuValue, usize) {
+    fn uradora_count(&self) -> usize {
+        if !self.context.riichi && !self.context.double_riichi {
+            return 0;
+        }
+        let uradora = self.context.uradora();
+        self.hai_all()
+            .map(|hai| uradora.iter().filter(|ura| **ura == hai).count())
+            .sum()
+    }
+
+    fn aka_count(&self) -> usize {
+        self.hai_all().filter(|hai| hai.is_aka()).count()
+    }
+
+    fn score(&self) -> ScoreBreakdown {
         if let Some(comb) = self.best_combination() {
-            let yaku = comb.yaku();
+            let closed = comb.closed();
+            let yaku: Vec<_> = comb
+                .yaku()
+                .into_iter()
+                .map(|yaku| (yaku, yaku.han(closed)))
+                .collect();
             trace!("Got yaku: {:?}", &yaku);
-            (yaku, comb.han(), comb.fu())
+            let han = comb.han();
+            let fu = comb.fu();
+            let machi = comb.machi();
+            let (limit, payments) = if yaku.is_empty() {
+                (None, [0; 4])
+            } else {
+                (
+                    points::limit(han, fu.total(), self.rules),
+                    points::payments(han, fu.total(), self.method, self.context, self.rules),
+                )
+            };
+            ScoreBreakdown {
+                yaku,
+                dora: comb.dora_count(),
+                uradora: self.uradora_count(),
+                aka: self.aka_count(),
+                han,
+                fu,
+                combination: Some(comb.combination),
+                machi: Some(machi),
+                limit,
+                payments,
+            }
         } else {
             trace!("No combination");
-            (vec![], YakuValue::Han(0), 0)
+            ScoreBreakdown {
+                yaku: vec![],
+                dora: 0,
+                uradora: 0,
+                aka: 0,
+                han: YakuValue::Han(0),
+                fu: Fu::default(),
+                combination: None,
+                machi: None,
+                limit: None,
+                payments: [0; 4],
+            }
         }
     }
 }
 
 #[derive(Debug, Clone)]
-struct AgariTeCombination<'a, 't: 'a, 'g: 't> {
-    agari_te: &'a AgariTe<'t, 'g>,
+struct AgariTeCombination<'a, 't: 'a> {
+    agari: &'a Agari<'t>,
     combination: WinningCombination,
 }
 
@@ -440,7 +834,7 @@ impl Mentsu_ {
     }
 }
 
-impl<'a, 't, 'g> AgariTeCombination<'a, 't, 'g> {
+impl<'a, 't> AgariTeCombination<'a, 't> {
     fn yaku(&self) -> Vec<Yaku> {
         let mut yakus = vec![];
 
@@ -604,46 +998,48 @@ impl<'a, 't, 'g> AgariTeCombination<'a, 't, 'g> {
     }
 
     fn dora_count(&self) -> usize {
-        let mut dora = self.agari_te.game.dora();
-        if self.riichi() || self.daburii() {
-            dora.extend(self.agari_te.game.uradora());
-        }
-
-        let mut dora_cnt = 0;
-        for hai in self.agari_te.hai_all() {
-            if dora.contains(&hai) {
-                dora_cnt += 1;
-            }
-        }
-        dora_cnt
+        let dora = self.agari.context.dora();
+        self.agari
+            .hai_all()
+            .map(|hai| dora.iter().filter(|dora| **dora == hai).count())
+            .sum()
     }
 
     fn han(&self) -> YakuValue {
         let closed = self.closed();
-        let dora_cnt = self.dora_count();
+        let dora_cnt = self.dora_count() + self.agari.uradora_count() + self.agari.aka_count();
         self.yaku()
             .iter()
             .fold(YakuValue::Han(dora_cnt), |acc, yaku| acc + yaku.han(closed))
     }
 
     /// From https://majandofu.com/fu-calculation#001
-    fn fu(&self) -> usize {
+    fn fu(&self) -> Fu {
         if self.pinfu() && self.menzentsumo() {
-            20
+            Fu {
+                base: 20,
+                ..Default::default()
+            }
         } else if self.chiitoitsu() {
-            25
+            Fu {
+                base: 25,
+                ..Default::default()
+            }
         } else {
-            let fuutei = 20;
-            let agari_fu = match self.agari_te.method {
-                WinningMethod::Ron => 10,
-                WinningMethod::Tsumo => 2,
-                WinningMethod::Nagashimangan => 0,
+            let mut fu = Fu {
+                base: 20,
+                ..Default::default()
             };
+            match self.agari.method {
+                WinningMethod::Ron if self.closed() => fu.menzen_ron = 10,
+                WinningMethod::Ron => {}
+                WinningMethod::Tsumo => fu.tsumo = 2,
+                WinningMethod::Nagashimangan => {}
+            }
 
-            let mut mentsu_fu = 0;
             if let Some(mentsu) = self.mentsu() {
                 for m in mentsu {
-                    mentsu_fu += match m {
+                    fu.mentsu.push(match m {
                         Mentsu_::Ankan([hai, _, _, _]) => {
                             if hai.is_jihai_or_1_9() {
                                 32
@@ -673,18 +1069,17 @@ impl<'a, 't, 'g> AgariTeCombination<'a, 't, 'g> {
                             }
                         }
                         Mentsu_::Anshun(_) | Mentsu_::Minshun(_) => 0,
-                    };
+                    });
                 }
             }
 
-            let mut atama_fu = 0;
             if let WinningCombination::Normal { toitsu, .. } = &self.combination {
-                if toitsu[0].is_yakuhai(self.agari_te.game.wind, self.agari_te.wind) {
-                    atama_fu = 2;
+                if toitsu[0].is_yakuhai(self.agari.context.bakaze, self.agari.context.jikaze) {
+                    fu.atama = 2;
                 }
             }
 
-            let machi_fu = match self.machi() {
+            fu.machi = match self.machi() {
                 Machi::Tanki | Machi::Penchan | Machi::Kanchan => 2,
                 Machi::Ryanmen
                 | Machi::Shanpon
@@ -692,9 +1087,15 @@ impl<'a, 't, 'g> AgariTeCombination<'a, 't, 'g> {
                 | Machi::KokushimusouJuusanmen => 0,
             };
 
-            let fu = fuutei + agari_fu + mentsu_fu + atama_fu + machi_fu;
-            // Round up to the next decade
-            ((fu + 9) / 10) * 10
+            let total = fu.total();
+            // Round up to the next decade. An open hand without any fu is
+            // counted as 30 fu (喰い平和).
+            fu.rounding = if total == 20 {
+                10
+            } else {
+                ((total + 9) / 10) * 10 - total
+            };
+            fu
         }
     }
 
@@ -707,13 +1108,13 @@ impl<'a, 't, 'g> AgariTeCombination<'a, 't, 'g> {
                     // to get more han.
                     Machi::Ryanmen
                 } else {
-                    let machis = machi(*toitsu, mentsu, self.agari_te.agarihai);
+                    let machis = machi(*toitsu, mentsu, self.agari.agarihai);
                     // Return the machi with the highest fu count
                     machis.into_iter().min().expect("Has machi")
                 }
             }
             WinningCombination::Kokushimusou(_) => {
-                if self.agari_te.hai.contains(&self.agari_te.agarihai) {
+                if self.agari.hai.contains(&self.agari.agarihai) {
                     Machi::KokushimusouJuusanmen
                 } else {
                     Machi::KokushimusouNormal
@@ -727,19 +1128,19 @@ impl<'a, 't, 'g> AgariTeCombination<'a, 't, 'g> {
     }
 
     fn points(&self) -> (YakuValue, usize) {
-        (self.han(), self.fu())
+        (self.han(), self.fu().total())
     }
 
     fn closed(&self) -> bool {
-        self.agari_te.fuuro.is_empty()
+        self.agari.fuuro.is_empty()
     }
 
     fn mentsu(&self) -> Option<impl Iterator<Item = Mentsu_>> {
         if let WinningCombination::Normal { mentsu, .. } = &self.combination {
             let mut out = vec![];
 
-            let hupai = self.agari_te.agarihai;
-            let ron = self.agari_te.method == WinningMethod::Ron;
+            let hupai = self.agari.agarihai;
+            let ron = self.agari.method == WinningMethod::Ron;
             for m in mentsu {
                 let mentsu_ = if is_kootsu(m) {
                     let minkoo = ron
@@ -760,7 +1161,7 @@ impl<'a, 't, 'g> AgariTeCombination<'a, 't, 'g> {
                 out.push(mentsu_);
             }
 
-            for fuuro in self.agari_te.fuuro {
+            for fuuro in self.agari.fuuro {
                 let mentsu = match fuuro {
                     Fuuro::Shuntsu { own, taken, .. } => Mentsu_::Minshun([own[0], own[1], *taken]),
                     Fuuro::Kootsu { own, taken, .. } => Mentsu_::Minkou([own[0], own[1], *taken]),
@@ -806,23 +1207,19 @@ impl<'a, 't, 'g> AgariTeCombination<'a, 't, 'g> {
     }
 
     fn menzentsumo(&self) -> bool {
-        self.closed() && self.agari_te.method == WinningMethod::Tsumo
+        self.closed() && self.agari.method == WinningMethod::Tsumo
     }
 
     fn riichi(&self) -> bool {
-        self.agari_te.game.player_is_riichi(self.agari_te.wind) && !self.daburii()
+        self.agari.context.riichi && !self.daburii()
     }
 
     fn ippatsu(&self) -> bool {
-        if let Some(riichi) = self.agari_te.game.player_riichi(self.agari_te.wind) {
-            riichi.ippatsu
-        } else {
-            false
-        }
+        self.agari.context.ippatsu
     }
 
     fn tanyao(&self) -> bool {
-        self.agari_te.hai_all().all(|hai| !hai.is_jihai_or_1_9())
+        self.agari.hai_all().all(|hai| !hai.is_jihai_or_1_9())
     }
 
     fn iipeikou(&self) -> bool {
@@ -853,10 +1250,10 @@ impl<'a, 't, 'g> AgariTeCombination<'a, 't, 'g> {
 
     fn pinfu(&self) -> bool {
         if let WinningCombination::Normal { toitsu, mentsu } = &self.combination {
-            let machis = machi(*toitsu, mentsu, self.agari_te.agarihai);
+            let machis = machi(*toitsu, mentsu, self.agari.agarihai);
             self.closed()
                 && machis.contains(&Machi::Ryanmen)
-                && !toitsu[0].is_yakuhai(self.agari_te.game.wind, self.agari_te.wind)
+                && !toitsu[0].is_yakuhai(self.agari.context.bakaze, self.agari.context.jikaze)
                 && mentsu.iter().all(|m| !is_kootsu(m))
         } else {
             false
@@ -893,7 +1290,7 @@ impl<'a, 't, 'g> AgariTeCombination<'a, 't, 'g> {
 
     fn ba_no_kaze(&self) -> bool {
         if let Some(mut mentsu) = self.mentsu() {
-            mentsu.any(|m| m.count_as_kootsu_with(|hai| hai.is_fon(self.agari_te.game.wind)))
+            mentsu.any(|m| m.count_as_kootsu_with(|hai| hai.is_fon(self.agari.context.bakaze)))
         } else {
             false
         }
@@ -901,36 +1298,30 @@ impl<'a, 't, 'g> AgariTeCombination<'a, 't, 'g> {
 
     fn jibun_no_kaze(&self) -> bool {
         if let Some(mut mentsu) = self.mentsu() {
-            mentsu.any(|m| m.count_as_kootsu_with(|hai| hai.is_fon(self.agari_te.wind)))
+            mentsu.any(|m| m.count_as_kootsu_with(|hai| hai.is_fon(self.agari.context.jikaze)))
         } else {
             false
         }
     }
 
     fn chankan(&self) -> bool {
-        self.agari_te.chankan
+        self.agari.context.chankan
     }
 
     fn rinshankaihou(&self) -> bool {
-        self.agari_te.rinshankaihou
+        self.agari.context.rinshan
     }
 
     fn haiteiraoyue(&self) -> bool {
-        self.agari_te.method == WinningMethod::Tsumo
-            && self.agari_te.game.next_tsumohai_index().is_none()
+        self.agari.method == WinningMethod::Tsumo && self.agari.context.haitei
     }
 
     fn houteiraoyui(&self) -> bool {
-        self.agari_te.method == WinningMethod::Ron
-            && self.agari_te.game.next_tsumohai_index().is_none()
+        self.agari.method == WinningMethod::Ron && self.agari.context.houtei
     }
 
     fn daburii(&self) -> bool {
-        if let Some(riichi) = self.agari_te.game.player_riichi(self.agari_te.wind) {
-            riichi.double
-        } else {
-            false
-        }
+        self.agari.context.double_riichi
     }
 
     fn chiitoitsu(&self) -> bool {
@@ -987,7 +1378,7 @@ impl<'a, 't, 'g> AgariTeCombination<'a, 't, 'g> {
     }
 
     fn honroutou(&self) -> bool {
-        self.agari_te.hai_all().all(Hai::is_jihai_or_1_9)
+        self.agari.hai_all().all(Hai::is_jihai_or_1_9)
     }
 
     fn ittsuu(&self) -> bool {
@@ -1048,7 +1439,7 @@ impl<'a, 't, 'g> AgariTeCombination<'a, 't, 'g> {
     fn honitsu(&self) -> bool {
         let mut has_jihai = false;
         let mut suu_found = None;
-        for hai in self.agari_te.hai_all() {
+        for hai in self.agari.hai_all() {
             match hai {
                 Hai::Ji(_) => has_jihai = true,
                 Hai::Suu(SuuHai { suu, .. }) => {
@@ -1068,7 +1459,7 @@ impl<'a, 't, 'g> AgariTeCombination<'a, 't, 'g> {
             if let Some(mut mentsu) = self.mentsu() {
                 toitsu.iter().all(|h| h.is_1_9())
                     && mentsu.all(|m| Mentsu_::is_extremity(&m))
-                    && self.agari_te.hai_all().all(Hai::is_suuhai)
+                    && self.agari.hai_all().all(Hai::is_suuhai)
             } else {
                 false
             }
@@ -1106,7 +1497,7 @@ impl<'a, 't, 'g> AgariTeCombination<'a, 't, 'g> {
 
     fn chinitsu(&self) -> bool {
         let mut suu_found = None;
-        for hai in self.agari_te.hai_all() {
+        for hai in self.agari.hai_all() {
             match hai {
                 Hai::Suu(SuuHai { suu, .. }) => {
                     if suu_found.is_none() {
@@ -1122,25 +1513,19 @@ impl<'a, 't, 'g> AgariTeCombination<'a, 't, 'g> {
     }
 
     fn tenhou(&self) -> bool {
-        self.agari_te.wind == Fon::Ton
-            && self.agari_te.game.first_uninterrupted_turn()
-            && self.agari_te.method == WinningMethod::Tsumo
+        self.agari.method == WinningMethod::Tsumo && self.agari.context.tenhou
     }
 
     fn chihou(&self) -> bool {
-        self.agari_te.wind != Fon::Ton
-            && self.agari_te.game.first_uninterrupted_turn()
-            && self.agari_te.method == WinningMethod::Tsumo
+        self.agari.method == WinningMethod::Tsumo && self.agari.context.chihou
     }
 
     fn renhou(&self) -> bool {
-        self.agari_te.game.first_uninterrupted_turn()
-            && self.agari_te.method == WinningMethod::Ron
-            && self.agari_te.game.tsumo_cnt() <= self.agari_te.wind as usize
+        self.agari.method == WinningMethod::Ron && self.agari.context.renhou
     }
 
     fn ryuuiisou(&self) -> bool {
-        self.agari_te.hai_all().all(Hai::is_green)
+        self.agari.hai_all().all(Hai::is_green)
     }
 
     fn daisangen(&self) -> bool {
@@ -1199,7 +1584,7 @@ impl<'a, 't, 'g> AgariTeCombination<'a, 't, 'g> {
     }
 
     fn tsuuiisou(&self) -> bool {
-        self.agari_te.hai_all().all(Hai::is_jihai)
+        self.agari.hai_all().all(Hai::is_jihai)
     }
 
     fn kokushimusou(&self) -> bool {
@@ -1220,12 +1605,12 @@ impl<'a, 't, 'g> AgariTeCombination<'a, 't, 'g> {
         }
 
         let mut count = [0usize; 9];
-        let found_suu = if let Some(Hai::Suu(SuuHai { suu, .. })) = self.agari_te.hai_all().next() {
+        let found_suu = if let Some(Hai::Suu(SuuHai { suu, .. })) = self.agari.hai_all().next() {
             suu
         } else {
             return false;
         };
-        for hai in self.agari_te.hai_all() {
+        for hai in self.agari.hai_all() {
             if let Hai::Suu(SuuHai { suu, value, .. }) = hai {
                 if suu != found_suu {
                     return false;
@@ -1243,7 +1628,7 @@ impl<'a, 't, 'g> AgariTeCombination<'a, 't, 'g> {
     }
 
     fn chinroutou(&self) -> bool {
-        self.agari_te.hai_all().all(Hai::is_1_9)
+        self.agari.hai_all().all(Hai::is_1_9)
     }
 
     fn suukantsuu(&self) -> bool {
@@ -1277,12 +1662,12 @@ impl<'a, 't, 'g> AgariTeCombination<'a, 't, 'g> {
 
         // Contrary to normal chuurenpoutou, we exclude hupai on counting tiles
         let mut count = [0usize; 9];
-        let found_suu = if let Hai::Suu(SuuHai { suu, .. }) = self.agari_te.hai[0] {
+        let found_suu = if let Hai::Suu(SuuHai { suu, .. }) = self.agari.hai[0] {
             suu
         } else {
             return false;
         };
-        for hai in self.agari_te.hai {
+        for hai in self.agari.hai {
             if let Hai::Suu(SuuHai { suu, value, .. }) = *hai {
                 if suu != found_suu {
                     return false;
@@ -1343,7 +1728,8 @@ fn machi(toitsu: [Hai; 2], mentsu: &[[Hai; 3]], agari_hai: Hai) -> Vec<Machi> {
     machis
 }
 
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Copy, Clone)]
+/// Shape of a wait (待ち)
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Copy, Clone, Serialize)]
 pub enum Machi {
     Tanki,
     Penchan,
@@ -1356,6 +1742,22 @@ pub enum Machi {
     KokushimusouJuusanmen,
 }
 
+impl Machi {
+    pub fn name(self, locale: Locale) -> &'static str {
+        match self {
+            Machi::Tanki => locale.pick("単騎", "Tanki", "Single wait"),
+            Machi::Penchan => locale.pick("辺張", "Penchan", "Edge wait"),
+            Machi::Kanchan => locale.pick("嵌張", "Kanchan", "Closed wait"),
+            Machi::Ryanmen => locale.pick("両面", "Ryanmen", "Open wait"),
+            Machi::Shanpon => locale.pick("双碰", "Shanpon", "Dual pon wait"),
+            Machi::KokushimusouNormal => locale.pick("国士無双", "Kokushi", "Thirteen orphans"),
+            Machi::KokushimusouJuusanmen => {
+                locale.pick("十三面", "Juusanmen", "Thirteen-sided wait")
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct AgariTeHaiIter<'t> {
     te: std::slice::Iter<'t, Hai>,
@@ -1388,335 +1790,372 @@ fn winning_combinations(te: &[Hai], max: usize) -> Vec<WinningCombination> {
 }
 
 fn try_normal_combinations(te: &[Hai], max: usize) -> Vec<WinningCombination> {
+    let mut counts = TileCounts::from_hai(te);
     let mut combs = vec![];
-    for head in all_heads(te) {
-        for comb in pickup_mentsu_comb(&head.remaining, max) {
-            combs.push(WinningCombination::Normal {
-                toitsu: head.head,
-                mentsu: comb,
-            })
+    if counts.total() != 3 * max + 2 {
+        return combs;
+    }
+    let aka: TileCounts = te.iter().copied().filter(|hai| hai.is_aka()).collect();
+    for head in TileKind::all() {
+        if counts[head] < 2 {
+            continue;
+        }
+        counts[head] -= 2;
+        let mut all_mentsu = vec![];
+        pickup_mentsu_comb(&mut counts, 0, &mut vec![], &mut all_mentsu);
+        counts[head] += 2;
+
+        let mut head_combs = vec![];
+        for mentsu in all_mentsu {
+            let mut aka = aka;
+            let mut take = |kind| take_hai(&mut aka, kind);
+            let toitsu = [take(head), take(head)];
+            // Sort tiles to have a pretty result
+            let mut mentsu: Vec<_> = mentsu
+                .into_iter()
+                .map(|[k1, k2, k3]| [take(k1), take(k2), take(k3)])
+                .collect();
+            mentsu.sort();
+            head_combs.push((toitsu, mentsu));
+        }
+        // Kootsu then shuntsu of the same kind, or the other way around
+        head_combs.sort();
+        head_combs.dedup();
+        for (toitsu, mentsu) in head_combs {
+            combs.push(WinningCombination::Normal { toitsu, mentsu })
         }
     }
     combs
 }
 
-fn pickup_mentsu_comb(remaining: &[Hai], max: usize) -> Vec<Vec<[Hai; 3]>> {
-    let mut out = vec![];
-
-    // Find all possible kootsu with a given te
-    let all_kootsu_ = all_kootsu(remaining);
-    for kootsu in all_kootsu_ {
-        // Find all possible shuntsu with a given te
-        for shuntsu in all_shuntsu(&kootsu.remaining) {
-            if kootsu.mentsu.len() + shuntsu.mentsu.len() == max {
-                let mut mentsu_4 = Vec::with_capacity(max);
-                for mentsu in kootsu.mentsu.iter().chain(shuntsu.mentsu.iter()) {
-                    mentsu_4.push(*mentsu);
-                }
-                // Sort tiles to have a pretty result
-                mentsu_4.sort();
-                out.push(mentsu_4);
+/// Find every way to split `counts` into mentsu. The lowest remaining kind
+/// is either in a kootsu or starts a shuntsu.
+fn pickup_mentsu_comb(
+    counts: &mut TileCounts,
+    from: usize,
+    current: &mut Vec<[TileKind; 3]>,
+    out: &mut Vec<Vec<[TileKind; 3]>>,
+) {
+    let lowest = TileKind::all().skip(from).find(|kind| counts[*kind] > 0);
+    let kind = if let Some(kind) = lowest {
+        kind
+    } else {
+        out.push(current.clone());
+        return;
+    };
+
+    if counts[kind] >= 3 {
+        counts[kind] -= 3;
+        current.push([kind; 3]);
+        pickup_mentsu_comb(counts, kind.index(), current, out);
+        current.pop();
+        counts[kind] += 3;
+    }
+    if let (Some(next), Some(next2)) = (kind.offset(1), kind.offset(2)) {
+        if counts[next] > 0 && counts[next2] > 0 {
+            let shuntsu = [kind, next, next2];
+            for k in shuntsu {
+                counts.remove(k);
             }
-        }
-    }
-
-    // Reverse
-    let all_shuntsu_ = all_shuntsu(remaining);
-    for shuntsu in all_shuntsu_ {
-        for kootsu in all_kootsu(&shuntsu.remaining) {
-            if kootsu.mentsu.len() + shuntsu.mentsu.len() == max {
-                let mut mentsu_4 = Vec::with_capacity(max);
-                for mentsu in kootsu.mentsu.iter().chain(shuntsu.mentsu.iter()) {
-                    mentsu_4.push(*mentsu);
-                }
-                mentsu_4.sort();
-                out.push(mentsu_4);
+            current.push(shuntsu);
+            pickup_mentsu_comb(counts, kind.index(), current, out);
+            current.pop();
+            for k in shuntsu {
+                counts.add(k);
             }
         }
     }
-
-    // Normalize
-    out.sort();
-    out.dedup();
-
-    out
-}
-
-#[derive(PartialEq, Eq, PartialOrd, Ord)]
-struct Mentsu {
-    mentsu: Vec<[Hai; 3]>,
-    remaining: Vec<Hai>,
 }
 
-impl Mentsu {
-    fn normalize(&mut self) {
-        self.mentsu.sort();
-        self.remaining.sort();
+/// A tile of kind `kind`, red if any red one of this kind is left in `aka`
+fn take_hai(aka: &mut TileCounts, kind: TileKind) -> Hai {
+    match kind.to_hai() {
+        Hai::Suu(suu) if aka[kind] > 0 => {
+            aka.remove(kind);
+            Hai::Suu(SuuHai { aka: true, ..suu })
+        }
+        hai => hai,
     }
 }
 
-use std::fmt;
-impl fmt::Debug for Mentsu {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut mentsu_list = Vec::with_capacity(self.mentsu.len());
-        for mentsu in &self.mentsu {
-            mentsu_list.push(format!(
-                "{}{}{}",
-                mentsu[0].to_char(),
-                mentsu[1].to_char(),
-                mentsu[2].to_char()
-            ));
-        }
-        let mut remaining = String::new();
-        for hai in &self.remaining {
-            remaining.push(hai.to_char());
-        }
-        f.debug_struct("Mentsu")
-            .field("mentsu", &mentsu_list)
-            .field("remaining", &remaining)
-            .finish()
+/// Seven distinct pairs. Four of a kind is not two pairs.
+fn try_chiitoitsu(te: &[Hai]) -> Option<[[Hai; 2]; 7]> {
+    let counts = TileCounts::from_hai(te);
+    if te.len() != 14 || counts.iter().any(|(_, count)| count != 2) {
+        return None;
+    }
+    let mut aka: TileCounts = te.iter().copied().filter(|hai| hai.is_aka()).collect();
+    let mut chiitoitsu = [[te[0]; 2]; 7];
+    for (toitsu, (kind, _)) in chiitoitsu.iter_mut().zip(counts.iter()) {
+        *toitsu = [take_hai(&mut aka, kind), take_hai(&mut aka, kind)];
     }
+    Some(chiitoitsu)
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
-struct Head {
-    head: [Hai; 2],
-    remaining: Vec<Hai>,
+/// All 13 terminal and honor kinds, one of them twice
+fn try_kokushimuso(te: &[Hai]) -> Option<[Hai; 14]> {
+    let counts = TileCounts::from_hai(te);
+    let kinds = counts.iter().filter(|(kind, _)| kind.is_jihai_or_1_9());
+    if te.len() != 14 || kinds.count() != 13 || counts.iter().count() != 13 {
+        return None;
+    }
+    let mut kokushimuso = [te[0]; 14];
+    kokushimuso.copy_from_slice(te);
+    Some(kokushimuso)
 }
 
-fn all_heads(te: &[Hai]) -> Vec<Head> {
-    // Find all possible heads
-    let mut heads = vec![];
-    for hai in te {
-        let mut te_ = te.to_owned();
-        if let Some(pos) = te_.iter().position(|x| x == hai) {
-            te_.swap_remove(pos);
-        } else {
-            unreachable!("Hai should be there");
+#[cfg(test)]
+mod tests {
+    use super::super::game::tests::te_from_string;
+    use super::*;
+
+    /// Split into mentsu with lists of tiles, kept to check
+    /// [`pickup_mentsu_comb`] against it.
+    fn pickup_mentsu_comb_with_lists(remaining: &[Hai], max: usize) -> Vec<Vec<[Hai; 3]>> {
+        let mut out = vec![];
+
+        // Find all possible kootsu with a given te
+        let all_kootsu_ = all_kootsu(remaining);
+        for kootsu in all_kootsu_ {
+            // Find all possible shuntsu with a given te
+            for shuntsu in all_shuntsu(&kootsu.remaining) {
+                if kootsu.mentsu.len() + shuntsu.mentsu.len() == max {
+                    let mut mentsu_4 = Vec::with_capacity(max);
+                    for mentsu in kootsu.mentsu.iter().chain(shuntsu.mentsu.iter()) {
+                        mentsu_4.push(*mentsu);
+                    }
+                    // Sort tiles to have a pretty result
+                    mentsu_4.sort();
+                    out.push(mentsu_4);
+                }
+            }
         }
 
-        if let Some(pos) = te_.iter().position(|x| x == hai) {
-            let hai2 = te_.swap_remove(pos);
-            heads.push(Head {
-                head: [*hai, hai2],
-                remaining: te_,
-            });
+        // Reverse
+        let all_shuntsu_ = all_shuntsu(remaining);
+        for shuntsu in all_shuntsu_ {
+            for kootsu in all_kootsu(&shuntsu.remaining) {
+                if kootsu.mentsu.len() + shuntsu.mentsu.len() == max {
+                    let mut mentsu_4 = Vec::with_capacity(max);
+                    for mentsu in kootsu.mentsu.iter().chain(shuntsu.mentsu.iter()) {
+                        mentsu_4.push(*mentsu);
+                    }
+                    mentsu_4.sort();
+                    out.push(mentsu_4);
+                }
+            }
         }
-    }
 
-    // Normalize
-    heads.sort();
-    heads.dedup();
+        // Normalize
+        out.sort();
+        out.dedup();
 
-    heads
-}
+        out
+    }
 
-fn all_kootsu(te: &[Hai]) -> Vec<Mentsu> {
-    let mut kootsu = vec![Mentsu {
-        // Include the trivial mentsu (all remainining and no mentsu)
-        mentsu: vec![],
-        remaining: te.to_owned(),
-    }];
-    for hai in te {
-        let mut te_ = te.to_owned();
-        if let Some(pos) = te_.iter().position(|x| x == hai) {
-            te_.swap_remove(pos);
-        } else {
-            unreachable!("Hai should be there");
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    struct Mentsu {
+        mentsu: Vec<[Hai; 3]>,
+        remaining: Vec<Hai>,
+    }
+
+    impl Mentsu {
+        fn normalize(&mut self) {
+            self.mentsu.sort();
+            self.remaining.sort();
         }
+    }
 
-        if let Some(pos) = te_.iter().position(|x| x == hai) {
-            let hai2 = te_.swap_remove(pos);
-            if let Some(pos) = te_.iter().position(|x| x == hai) {
-                let hai3 = te_.swap_remove(pos);
-                let this_kootsu = [*hai, hai2, hai3];
-                let all_remaining_kootsu = all_kootsu(&te_);
-                kootsu.push(Mentsu {
-                    mentsu: vec![this_kootsu],
-                    remaining: te_,
-                });
-                for remaining_kootsu in all_remaining_kootsu {
-                    let mut mentsu = vec![this_kootsu];
-                    mentsu.extend(remaining_kootsu.mentsu);
-                    kootsu.push(Mentsu {
-                        mentsu,
-                        remaining: remaining_kootsu.remaining,
-                    })
-                }
+    impl fmt::Debug for Mentsu {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let mut mentsu_list = Vec::with_capacity(self.mentsu.len());
+            for mentsu in &self.mentsu {
+                mentsu_list.push(format!(
+                    "{}{}{}",
+                    mentsu[0].to_char(),
+                    mentsu[1].to_char(),
+                    mentsu[2].to_char()
+                ));
             }
+            let mut remaining = String::new();
+            for hai in &self.remaining {
+                remaining.push(hai.to_char());
+            }
+            f.debug_struct("Mentsu")
+                .field("mentsu", &mentsu_list)
+                .field("remaining", &remaining)
+                .finish()
         }
     }
 
-    for i in kootsu.iter_mut() {
-        i.normalize()
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+    struct Head {
+        head: [Hai; 2],
+        remaining: Vec<Hai>,
     }
-    kootsu.sort();
-    kootsu.dedup();
 
-    kootsu
-}
+    fn all_heads(te: &[Hai]) -> Vec<Head> {
+        // Find all possible heads
+        let mut heads = vec![];
+        for hai in te {
+            let mut te_ = te.to_owned();
+            if let Some(pos) = te_.iter().position(|x| x == hai) {
+                te_.swap_remove(pos);
+            } else {
+                unreachable!("Hai should be there");
+            }
 
-fn all_shuntsu(te: &[Hai]) -> Vec<Mentsu> {
-    fn possible_shuntsu(hai: Hai) -> Vec<[Hai; 3]> {
-        use super::tiles::Values;
-        match hai {
-            Hai::Suu(SuuHai { value, .. }) => {
-                let right = [hai.prev().prev(), hai.prev(), hai];
-                let middle = [hai.prev(), hai, hai.next()];
-                let left = [hai, hai.next(), hai.next().next()];
-
-                match value {
-                    Values::Ii => vec![left],
-                    Values::Ryan => vec![middle, left],
-                    Values::Paa => vec![right, middle],
-                    Values::Kyuu => vec![right],
-                    _ => vec![right, middle, left],
-                }
+            if let Some(pos) = te_.iter().position(|x| x == hai) {
+                let hai2 = te_.swap_remove(pos);
+                heads.push(Head {
+                    head: [*hai, hai2],
+                    remaining: te_,
+                });
             }
-            Hai::Ji(..) => vec![],
         }
-    }
 
-    #[derive(Debug)]
-    struct ShuntsuList {
-        shuntsu: [Hai; 3],
-        next: Vec<ShuntsuList>,
-        remaining: Vec<Hai>,
+        // Normalize
+        heads.sort();
+        heads.dedup();
+
+        heads
     }
 
-    fn find_shuntsu(te: &[Hai]) -> Vec<ShuntsuList> {
-        let mut out_shuntsu = vec![];
+    fn all_kootsu(te: &[Hai]) -> Vec<Mentsu> {
+        let mut kootsu = vec![Mentsu {
+            // Include the trivial mentsu (all remainining and no mentsu)
+            mentsu: vec![],
+            remaining: te.to_owned(),
+        }];
         for hai in te {
-            for shuntsu in possible_shuntsu(*hai) {
-                let mut te_ = te.to_owned();
-                let mut matched_shuntsu = true;
-                for hai in &shuntsu {
-                    if let Some(pos) = te_.iter().position(|x| x == hai) {
-                        te_.swap_remove(pos);
-                    } else {
-                        matched_shuntsu = false;
-                    }
-                }
-                if matched_shuntsu {
-                    let next = find_shuntsu(&te_);
-                    out_shuntsu.push(ShuntsuList {
-                        shuntsu,
-                        next,
+            let mut te_ = te.to_owned();
+            if let Some(pos) = te_.iter().position(|x| x == hai) {
+                te_.swap_remove(pos);
+            } else {
+                unreachable!("Hai should be there");
+            }
+
+            if let Some(pos) = te_.iter().position(|x| x == hai) {
+                let hai2 = te_.swap_remove(pos);
+                if let Some(pos) = te_.iter().position(|x| x == hai) {
+                    let hai3 = te_.swap_remove(pos);
+                    let this_kootsu = [*hai, hai2, hai3];
+                    let all_remaining_kootsu = all_kootsu(&te_);
+                    kootsu.push(Mentsu {
+                        mentsu: vec![this_kootsu],
                         remaining: te_,
                     });
+                    for remaining_kootsu in all_remaining_kootsu {
+                        let mut mentsu = vec![this_kootsu];
+                        mentsu.extend(remaining_kootsu.mentsu);
+                        kootsu.push(Mentsu {
+                            mentsu,
+                            remaining: remaining_kootsu.remaining,
+                        })
+                    }
                 }
             }
         }
-        out_shuntsu
-    }
 
-    fn shuntsu_list_to_mentsu(head: ShuntsuList) -> Vec<Mentsu> {
-        let mut out = vec![];
-        out.push(Mentsu {
-            mentsu: vec![head.shuntsu],
-            remaining: head.remaining,
-        });
-        for li in head.next {
-            let mentsu_li = shuntsu_list_to_mentsu(li);
-            for mentsu in mentsu_li {
-                let mut shuntsu_list = vec![head.shuntsu];
-                shuntsu_list.extend(mentsu.mentsu);
-                out.push(Mentsu {
-                    mentsu: shuntsu_list,
-                    remaining: mentsu.remaining,
-                })
-            }
+        for i in kootsu.iter_mut() {
+            i.normalize()
         }
-        out
-    }
+        kootsu.sort();
+        kootsu.dedup();
 
-    let mut out = vec![Mentsu {
-        // Include the trivial mentsu (all remainining and no mentsu)
-        mentsu: vec![],
-        remaining: te.to_owned(),
-    }];
-    for shuntsu in find_shuntsu(te) {
-        out.extend(shuntsu_list_to_mentsu(shuntsu));
+        kootsu
     }
-    for i in out.iter_mut() {
-        i.normalize()
-    }
-    out.sort();
-    out.dedup();
-
-    out
-}
 
-fn try_chiitoitsu(te: &[Hai]) -> Option<[[Hai; 2]; 7]> {
-    let mut chiitoitsu = [[Option::<Hai>::None; 2]; 7];
-    for hai in te {
-        if let Some(pos) = chiitoitsu
-            .iter()
-            .position(|[some_hai1, some_hai2]| *some_hai1 == Some(*hai) && some_hai2.is_none())
-        {
-            chiitoitsu[pos][1] = Some(*hai);
-        } else if let Some(empty_pos) = chiitoitsu
-            .iter()
-            .position(|[some_hai1, some_hai2]| some_hai1.is_none() && some_hai2.is_none())
-        {
-            if !chiitoitsu
-                .iter()
-                .any(|[some_hai1, _]| some_hai1 == &Some(*hai))
-            {
-                chiitoitsu[empty_pos][0] = Some(*hai);
+    fn all_shuntsu(te: &[Hai]) -> Vec<Mentsu> {
+        fn possible_shuntsu(hai: Hai) -> Vec<[Hai; 3]> {
+            use crate::tiles::Values;
+            match hai {
+                Hai::Suu(SuuHai { value, .. }) => {
+                    let right = [hai.prev().prev(), hai.prev(), hai];
+                    let middle = [hai.prev(), hai, hai.next()];
+                    let left = [hai, hai.next(), hai.next().next()];
+
+                    match value {
+                        Values::Ii => vec![left],
+                        Values::Ryan => vec![middle, left],
+                        Values::Paa => vec![right, middle],
+                        Values::Kyuu => vec![right],
+                        _ => vec![right, middle, left],
+                    }
+                }
+                Hai::Ji(..) => vec![],
             }
         }
-    }
-    match chiitoitsu {
-        [[Some(hai1), Some(hai2)], [Some(hai3), Some(hai4)], [Some(hai5), Some(hai6)], [Some(hai7), Some(hai8)], [Some(hai9), Some(hai10)], [Some(hai11), Some(hai12)], [Some(hai13), Some(hai14)]] => {
-            Some([
-                [hai1, hai2],
-                [hai3, hai4],
-                [hai5, hai6],
-                [hai7, hai8],
-                [hai9, hai10],
-                [hai11, hai12],
-                [hai13, hai14],
-            ])
-        }
-        _ => None,
-    }
-}
 
-fn try_kokushimuso(te: &[Hai]) -> Option<[Hai; 14]> {
-    let mut kokushimuso = [Option::<Hai>::None; 14];
-    let mut head_found = false;
-    for hai in te {
-        if hai.is_jihai_or_1_9() {
-            if kokushimuso.contains(&Some(*hai)) {
-                if head_found {
-                    // Cannot have two heads!
-                    return None;
+        #[derive(Debug)]
+        struct ShuntsuList {
+            shuntsu: [Hai; 3],
+            next: Vec<ShuntsuList>,
+            remaining: Vec<Hai>,
+        }
+
+        fn find_shuntsu(te: &[Hai]) -> Vec<ShuntsuList> {
+            let mut out_shuntsu = vec![];
+            for hai in te {
+                for shuntsu in possible_shuntsu(*hai) {
+                    let mut te_ = te.to_owned();
+                    let mut matched_shuntsu = true;
+                    for hai in &shuntsu {
+                        if let Some(pos) = te_.iter().position(|x| x == hai) {
+                            te_.swap_remove(pos);
+                        } else {
+                            matched_shuntsu = false;
+                        }
+                    }
+                    if matched_shuntsu {
+                        let next = find_shuntsu(&te_);
+                        out_shuntsu.push(ShuntsuList {
+                            shuntsu,
+                            next,
+                            remaining: te_,
+                        });
+                    }
                 }
-                head_found = true;
             }
-            if let Some(empty_pos) = kokushimuso.iter().position(Option::is_none) {
-                kokushimuso[empty_pos] = Some(*hai)
+            out_shuntsu
+        }
+
+        fn shuntsu_list_to_mentsu(head: ShuntsuList) -> Vec<Mentsu> {
+            let mut out = vec![];
+            out.push(Mentsu {
+                mentsu: vec![head.shuntsu],
+                remaining: head.remaining,
+            });
+            for li in head.next {
+                let mentsu_li = shuntsu_list_to_mentsu(li);
+                for mentsu in mentsu_li {
+                    let mut shuntsu_list = vec![head.shuntsu];
+                    shuntsu_list.extend(mentsu.mentsu);
+                    out.push(Mentsu {
+                        mentsu: shuntsu_list,
+                        remaining: mentsu.remaining,
+                    })
+                }
             }
-        } else {
-            return None;
+            out
         }
-    }
-    match kokushimuso {
-        [Some(hai1), Some(hai2), Some(hai3), Some(hai4), Some(hai5), Some(hai6), Some(hai7), Some(hai8), Some(hai9), Some(hai10), Some(hai11), Some(hai12), Some(hai13), Some(hai14)] => {
-            Some([
-                hai1, hai2, hai3, hai4, hai5, hai6, hai7, hai8, hai9, hai10, hai11, hai12, hai13,
-                hai14,
-            ])
+
+        let mut out = vec![Mentsu {
+            // Include the trivial mentsu (all remainining and no mentsu)
+            mentsu: vec![],
+            remaining: te.to_owned(),
+        }];
+        for shuntsu in find_shuntsu(te) {
+            out.extend(shuntsu_list_to_mentsu(shuntsu));
         }
-        _ => None,
-    }
-}
+        for i in out.iter_mut() {
+            i.normalize()
+        }
+        out.sort();
+        out.dedup();
 
-#[cfg(test)]
-mod tests {
-    use super::super::game::tests::te_from_string;
-    use super::*;
+        out
+    }
 
     #[test]
     fn test_chitoitsu_agari() {
@@ -1805,6 +2244,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_find_winning_comb_agrees_with_lists() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(40);
+        let mut checked = 0;
+        while checked < 40 {
+            // A complete te in a single suit, with many ways to split it
+            let mut te = vec![];
+            let head = rng.gen_range(0..9);
+            te.extend([head; 2]);
+            for _ in 0..4 {
+                let start = rng.gen_range(0..9);
+                if start < 7 && rng.gen() {
+                    te.extend([start, start + 1, start + 2]);
+                } else {
+                    te.extend([start; 3]);
+                }
+            }
+            te.sort();
+            let te: Vec<_> = te
+                .into_iter()
+                .map(|k| TileKind::new(k).unwrap().to_hai())
+                .collect();
+            if TileCounts::from_hai(&te).iter().any(|(_, count)| count > 4) {
+                continue;
+            }
+            checked += 1;
+
+            let mut expected = vec![];
+            for head in all_heads(&te) {
+                for mentsu in pickup_mentsu_comb_with_lists(&head.remaining, 4) {
+                    expected.push(WinningCombination::Normal {
+                        toitsu: head.head,
+                        mentsu,
+                    });
+                }
+            }
+            assert_eq!(try_normal_combinations(&te, 4), expected, "{:?}", te);
+        }
+    }
+
     #[test]
     fn test_find_winning_comb_ryanpeikou() {
         let te = te_from_string("🀇🀇🀈🀈🀉🀉🀊🀊🀋🀋🀌🀌🀍🀍").unwrap();
@@ -2034,6 +2515,189 @@ mod tests {
         assert_eq!(fu, 40);
     }
 
+    #[test]
+    fn test_score_without_game() {
+        let hand = te_from_string("🀇🀈🀉🀊🀋🀌🀍🀎🀏🀙🀚🀛🀡").unwrap();
+        let winning_tile = te_from_string("🀡").unwrap()[0];
+        let context = WinContext {
+            jikaze: Fon::Nan,
+            dora_indicators: te_from_string("🀠").unwrap(),
+            uradora_indicators: te_from_string("🀀").unwrap(),
+            riichi: true,
+            ippatsu: true,
+            discarder: Some(Fon::Ton),
+            honba: 1,
+            riichi_bou: 1,
+            ..Default::default()
+        };
+        let breakdown = score(
+            &hand,
+            &[],
+            winning_tile,
+            WinningMethod::Ron,
+            &context,
+            &Rules::default(),
+        );
+        assert_eq!(
+            breakdown.yaku,
+            vec![
+                (Yaku::Riichi, YakuValue::Han(1)),
+                (Yaku::Ippatsu, YakuValue::Han(1)),
+                (Yaku::Ittsuu, YakuValue::Han(2)),
+            ]
+        );
+        assert_eq!(breakdown.dora, 2);
+        assert_eq!(breakdown.uradora, 0);
+        assert_eq!(breakdown.han, YakuValue::Han(6));
+        assert_eq!(
+            breakdown.fu,
+            Fu {
+                base: 20,
+                menzen_ron: 10,
+                tsumo: 0,
+                mentsu: vec![0, 0, 0, 0],
+                atama: 0,
+                machi: 2,
+                rounding: 8,
+            }
+        );
+        assert_eq!(breakdown.fu.total(), 40);
+        assert_eq!(breakdown.limit, Some(Limit::Haneman));
+        // Haneman + 1 honba + 1 riichi stick
+        assert_eq!(breakdown.payments, [-12300, 13300, 0, 0]);
+    }
+
+    #[test]
+    fn test_localized_breakdown() {
+        let hand = te_from_string("123456789m1239p").unwrap();
+        let context = WinContext {
+            jikaze: Fon::Nan,
+            dora_indicators: te_from_string("8p").unwrap(),
+            riichi: true,
+            ippatsu: true,
+            discarder: Some(Fon::Ton),
+            ..Default::default()
+        };
+        let breakdown = score(
+            &hand,
+            &[],
+            "9p".parse().unwrap(),
+            WinningMethod::Ron,
+            &context,
+            &Rules::default(),
+        );
+        assert_eq!(
+            breakdown.localized(Locale::English).to_string(),
+            "Riichi 1 han\nOne shot 1 han\nPure straight 2 han\nDora 2\n\
+             40 fu 6 han Haneman\n  Base 20, Closed ron 10, Wait 2, Rounding 8\n\
+             🀀 -12000 🀁 +12000 🀂 +0 🀃 +0"
+        );
+        let japanese = breakdown.to_string();
+        assert!(japanese.starts_with("立直 1翻\n一発 1翻\n一気通貫 2翻\nドラ 2\n40符 6翻 跳満\n"));
+        assert_eq!(japanese, breakdown.localized(Locale::Japanese).to_string());
+    }
+
+    #[test]
+    fn test_yaku_names_are_trimmed() {
+        for locale in [Locale::Japanese, Locale::Romaji, Locale::English] {
+            let name = Yaku::Houteiraoyui.name(locale);
+            assert_eq!(name, name.trim());
+        }
+        assert_eq!(Yaku::Houteiraoyui.name(Locale::Japanese), "河底撈魚");
+        assert_eq!(Yaku::Houteiraoyui.name(Locale::Romaji), "Houtei raoyui");
+    }
+
+    #[test]
+    fn test_open_hand_without_fu() {
+        let hand = te_from_string("234m345p66p56s").unwrap();
+        let winning_tile = "7s".parse().unwrap();
+        let fuuro = ["[6-78m]".parse().unwrap()];
+        let context = WinContext {
+            jikaze: Fon::Nan,
+            discarder: Some(Fon::Shaa),
+            ..Default::default()
+        };
+        let breakdown = score(
+            &hand,
+            &fuuro,
+            winning_tile,
+            WinningMethod::Ron,
+            &context,
+            &Rules::default(),
+        );
+        assert_eq!(breakdown.yaku(), vec![Yaku::Tanyao]);
+        // No menzen ron fu, but counted as 30 fu (喰い平和)
+        assert_eq!(breakdown.fu.menzen_ron, 0);
+        assert_eq!(breakdown.fu.rounding, 10);
+        assert_eq!(breakdown.fu.total(), 30);
+        assert_eq!(breakdown.payments, [0, 1000, -1000, 0]);
+    }
+
+    /// Open ron on `hand` + `winning_tile` after a chi of 678m, as 南家
+    /// against 西家
+    fn score_open_ron(hand: &str, winning_tile: &str) -> ScoreBreakdown {
+        let fuuro = ["[6-78m]".parse().unwrap()];
+        let context = WinContext {
+            jikaze: Fon::Nan,
+            discarder: Some(Fon::Shaa),
+            ..Default::default()
+        };
+        score(
+            &te_from_string(hand).unwrap(),
+            &fuuro,
+            winning_tile.parse().unwrap(),
+            WinningMethod::Ron,
+            &context,
+            &Rules::default(),
+        )
+    }
+
+    #[test]
+    fn test_open_ron_has_no_menzen_fu() {
+        let breakdown = score_open_ron("222p345s55m67s", "8s");
+        assert_eq!(breakdown.yaku(), vec![Yaku::Tanyao]);
+        // 20 + 4 for the concealed 222p: 30 fu, not 40
+        assert_eq!(breakdown.fu.menzen_ron, 0);
+        assert_eq!(breakdown.fu.mentsu.iter().sum::<usize>(), 4);
+        assert_eq!(breakdown.fu.rounding, 6);
+        assert_eq!(breakdown.fu.total(), 30);
+        assert_eq!(breakdown.payments, [0, 1000, -1000, 0]);
+    }
+
+    #[test]
+    fn test_aka_dora_counts_as_han() {
+        let breakdown = score_open_ron("222p345s05m67s", "8s");
+        assert_eq!(breakdown.yaku(), vec![Yaku::Tanyao]);
+        assert_eq!(breakdown.aka, 1);
+        assert_eq!(breakdown.han, YakuValue::Han(2));
+        assert_eq!(breakdown.payments, [0, 2000, -2000, 0]);
+    }
+
+    #[test]
+    fn test_repeated_indicators_count_twice() {
+        let hand = te_from_string("123456789m1239p").unwrap();
+        let context = WinContext {
+            jikaze: Fon::Nan,
+            dora_indicators: te_from_string("8p8p").unwrap(),
+            uradora_indicators: te_from_string("8m8m").unwrap(),
+            riichi: true,
+            discarder: Some(Fon::Ton),
+            ..Default::default()
+        };
+        let breakdown = score(
+            &hand,
+            &[],
+            "9p".parse().unwrap(),
+            WinningMethod::Ron,
+            &context,
+            &Rules::default(),
+        );
+        // Each 9p and 9m counts once for each indicator
+        assert_eq!(breakdown.dora, 4);
+        assert_eq!(breakdown.uradora, 2);
+        assert_eq!(breakdown.han, YakuValue::Han(9));
+    }
+
     use super::super::tiles::ParseHaiError;
     fn mentsu_from_str(mentsu: &[&str], remaining: &str) -> Result<Mentsu, ParseHaiError> {
         let mut mentsu_out = vec![];
@@ -2141,6 +2805,7 @@ mod tests {
         assert_eq!(hupai.len(), 1);
         let agarihai = hupai[0];
 
-        Ok(AgariTe::from_te(te, &game, agarihai, method, Fon::Ton).points())
+        let breakdown = AgariTe::from_te(te, &game, agarihai, method, Fon::Ton).score();
+        Ok((breakdown.yaku(), breakdown.han, breakdown.fu.total()))
     }
 }